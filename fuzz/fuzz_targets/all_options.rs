@@ -35,6 +35,9 @@ struct FuzzExtensionOptions {
     strikethrough: bool,
     tagfilter: bool,
     table: bool,
+    extended_tables: bool,
+    grid_tables: bool,
+    table_captions: bool,
     autolink: bool,
     tasklist: bool,
     superscript: bool,
@@ -43,6 +46,8 @@ struct FuzzExtensionOptions {
     multiline_block_quotes: bool,
     math_dollars: bool,
     math_code: bool,
+    math_labels: bool,
+    cross_references: bool,
     shortcodes: bool,
     wikilinks_title_after_pipe: bool,
     wikilinks_title_before_pipe: bool,
@@ -56,11 +61,15 @@ struct FuzzExtensionOptions {
     cjk_friendly_emphasis: bool,
     highlight: bool,
     block_directive: bool,
+    table_of_contents: bool,
+    block_anchors: bool,
     phoenix_heex: bool,
     insert: bool,
     header_id_prefix_in_href: bool,
+    header_custom_ids: bool,
     // non-bool below
     header_id_prefix: bool,
+    header_reserved_ids: bool,
     front_matter_delimiter: bool,
     image_url_rewriter: bool,
     link_url_rewriter: bool,
@@ -75,6 +84,9 @@ impl FuzzExtensionOptions {
             strikethrough: self.strikethrough,
             tagfilter: self.tagfilter,
             table: self.table,
+            extended_tables: self.extended_tables,
+            grid_tables: self.grid_tables,
+            table_captions: self.table_captions,
             autolink: self.autolink,
             tasklist: self.tasklist,
             superscript: self.superscript,
@@ -84,6 +96,8 @@ impl FuzzExtensionOptions {
             multiline_block_quotes: self.multiline_block_quotes,
             math_dollars: self.math_dollars,
             math_code: self.math_code,
+            math_labels: self.math_labels,
+            cross_references: self.cross_references,
             shortcodes: self.shortcodes,
             wikilinks_title_after_pipe: self.wikilinks_title_after_pipe,
             wikilinks_title_before_pipe: self.wikilinks_title_before_pipe,
@@ -96,15 +110,23 @@ impl FuzzExtensionOptions {
             cjk_friendly_emphasis: self.cjk_friendly_emphasis,
             highlight: self.highlight,
             block_directive: self.block_directive,
+            table_of_contents: self.table_of_contents,
+            block_anchors: self.block_anchors,
             phoenix_heex: self.phoenix_heex,
             insert: self.insert,
             header_id_prefix_in_href: self.header_id_prefix_in_href,
+            header_custom_ids: self.header_custom_ids,
             // non-bool below
             header_id_prefix: if self.header_id_prefix {
                 Some("user-content-".into())
             } else {
                 None
             },
+            header_reserved_ids: if self.header_reserved_ids {
+                vec!["content".into(), "footnotes".into()]
+            } else {
+                vec![]
+            },
             front_matter_delimiter: if self.front_matter_delimiter {
                 Some("---".into())
             } else {
@@ -120,6 +142,7 @@ impl FuzzExtensionOptions {
             } else {
                 None
             },
+            cross_reference_prefixes: Default::default(),
             #[allow(deprecated)]
             header_ids: None,
         }
@@ -129,13 +152,18 @@ impl FuzzExtensionOptions {
 #[derive(Arbitrary, Debug)]
 struct FuzzParseOptions {
     smart: bool,
+    smart_punctuation: options::SmartPunctuation,
+    typographer: bool,
     relaxed_tasklist_matching: bool,
     relaxed_autolinks: bool,
     ignore_setext: bool,
     tasklist_in_table: bool,
     leave_footnote_definitions: bool,
     default_info_string: bool,
+    language_guesser: bool,
     broken_link_callback: bool,
+    include_resolver: bool,
+    wikilink_resolver: bool,
     escaped_char_spans: bool,
     sourcepos_chars: bool,
 }
@@ -147,6 +175,8 @@ impl FuzzParseOptions {
     ) -> options::Parse<'c> {
         options::Parse {
             smart: self.smart,
+            smart_punctuation: self.smart_punctuation,
+            typographer: self.typographer,
             relaxed_tasklist_matching: self.relaxed_tasklist_matching,
             relaxed_autolinks: self.relaxed_autolinks,
             ignore_setext: self.ignore_setext,
@@ -157,6 +187,11 @@ impl FuzzParseOptions {
             } else {
                 None
             },
+            language_guesser: if self.language_guesser {
+                Some(Arc::new(options::FirstLineLanguageGuesser))
+            } else {
+                None
+            },
             broken_link_callback: if self.broken_link_callback {
                 Some(broken_link_callback)
             } else {
@@ -164,6 +199,29 @@ impl FuzzParseOptions {
             },
             escaped_char_spans: self.escaped_char_spans,
             sourcepos_chars: self.sourcepos_chars,
+            external_cross_references: None,
+            include_resolver: if self.include_resolver {
+                // Each document includes itself, exercising cycle detection.
+                Some(Arc::new(|include: options::IncludeReference| {
+                    Some(options::ResolvedInclude {
+                        path: include.target.to_string(),
+                        content: format!("*{0}*\n\n![[{0}]]\n", include.target),
+                    })
+                }))
+            } else {
+                None
+            },
+            document_path: None,
+            wikilink_resolver: if self.wikilink_resolver {
+                Some(Arc::new(|page: &str| {
+                    Some(options::ResolvedWikiLink {
+                        url: format!("/wiki/{page}"),
+                        exists: page.len() % 2 == 0,
+                    })
+                }))
+            } else {
+                None
+            },
         }
     }
 }
@@ -187,6 +245,15 @@ struct FuzzRenderOptions {
     ol_width: usize,
     experimental_minimize_commonmark: bool,
     compact_html: bool,
+    heading_numbers: bool,
+    footnote_placement: options::FootnotePlacement,
+    typographer_source: bool,
+    table_column_widths: bool,
+    // non-bool below
+    footnote_id_prefix: bool,
+    footnote_section_class: bool,
+    footnote_backref_symbol: bool,
+    footnote_backref_label: bool,
 }
 
 impl FuzzRenderOptions {
@@ -209,6 +276,31 @@ impl FuzzRenderOptions {
             ol_width: self.ol_width,
             experimental_minimize_commonmark: self.experimental_minimize_commonmark,
             compact_html: self.compact_html,
+            heading_numbers: self.heading_numbers,
+            footnote_placement: self.footnote_placement,
+            typographer_source: self.typographer_source,
+            table_column_widths: self.table_column_widths,
+            // non-bool below
+            footnote_id_prefix: if self.footnote_id_prefix {
+                Some("doc-".into())
+            } else {
+                None
+            },
+            footnote_section_class: if self.footnote_section_class {
+                Some("notes".into())
+            } else {
+                None
+            },
+            footnote_backref_symbol: if self.footnote_backref_symbol {
+                Some("^".into())
+            } else {
+                None
+            },
+            footnote_backref_label: if self.footnote_backref_label {
+                Some("Back".into())
+            } else {
+                None
+            },
         }
     }
 }
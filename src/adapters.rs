@@ -7,13 +7,19 @@ use std::collections::HashMap;
 use std::fmt;
 
 use crate::nodes::Sourcepos;
+use crate::options::FootnotePlacement;
 
 /// Implement this adapter for custom rendering of codefence blocks.
 pub trait CodefenceRendererAdapter: Send + Sync {
     /// Render a codefence block.
     ///
     /// lang: Name of the programming language (the first token of the info string).
-    /// meta: The remaining codefence info string after the language token, trimmed.
+    /// meta: The remaining codefence info string after the language token, trimmed;
+    /// see [`FenceMeta::parse`] for reading common conventions from it.
+    ///
+    /// A block labelled as a listing per
+    /// [`cross_references`](crate::options::Extension::cross_references) is
+    /// wrapped in a `<div>` with the label as its ID.
     fn write(
         &self,
         output: &mut dyn fmt::Write,
@@ -56,6 +62,154 @@ pub trait SyntaxHighlighterAdapter: Send + Sync {
         output: &mut dyn fmt::Write,
         attributes: HashMap<&'static str, Cow<'_, str>>,
     ) -> fmt::Result;
+
+    /// Generates a syntax highlighted HTML output for a codefence block with
+    /// meta following the language in its info string, such as highlighted
+    /// lines.  The default ignores the meta.
+    ///
+    /// meta: The meta of the codefence block, parsed.
+    /// sourcepos: The source position of the codefence block, when
+    /// [`sourcepos`](crate::options::Render#structfield.sourcepos) is enabled.
+    fn write_highlighted_with_meta(
+        &self,
+        output: &mut dyn fmt::Write,
+        lang: Option<&str>,
+        meta: &FenceMeta,
+        code: &str,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        let _ = (meta, sourcepos);
+        self.write_highlighted(output, lang, code)
+    }
+
+    /// Generates a header preceding the `<pre>` tag of a codefence block
+    /// with a [`title`](FenceMeta::title).  The default writes nothing.
+    fn write_title(&self, output: &mut dyn fmt::Write, title: &str) -> fmt::Result {
+        let _ = (output, title);
+        Ok(())
+    }
+}
+
+/// The meta of a codefence block: the info string after the language,
+/// read per common conventions.
+///
+/// ```rust
+/// # use comrak::adapters::FenceMeta;
+/// let meta = FenceMeta::parse(r#"title="src/main.rs" {1,3-4} showLineNumbers"#);
+/// assert_eq!(meta.title(), Some("src/main.rs"));
+/// assert_eq!(meta.line_numbers(), Some(1));
+/// assert!(meta.is_highlighted(3));
+/// assert!(!meta.is_highlighted(2));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FenceMeta {
+    /// Attributes given as `key=value`, `key="value"` or `key='value'`, in
+    /// order.
+    pub attributes: Vec<(String, String)>,
+
+    /// Lines given as `{1,3-5}`, by their inclusive ranges, counting from 1.
+    pub highlighted_lines: Vec<(usize, usize)>,
+
+    /// Any other words, such as `showLineNumbers`.
+    pub flags: Vec<String>,
+}
+
+impl FenceMeta {
+    /// Reads the meta of a codefence block.
+    pub fn parse(meta: &str) -> Self {
+        let mut fence_meta = FenceMeta::default();
+        let mut rest = meta.trim_start();
+
+        while !rest.is_empty() {
+            let end = token_end(rest);
+            let token = &rest[..end];
+            rest = rest[end..].trim_start();
+
+            if let Some(ranges) = token
+                .strip_prefix('{')
+                .and_then(|t| t.strip_suffix('}'))
+                .and_then(parse_line_ranges)
+            {
+                fence_meta.highlighted_lines.extend(ranges);
+            } else if let Some((key, value)) = token.split_once('=').filter(|(k, _)| !k.is_empty())
+            {
+                let value = ['"', '\'']
+                    .iter()
+                    .find_map(|&q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q)))
+                    .unwrap_or(value);
+                fence_meta
+                    .attributes
+                    .push((key.to_string(), value.to_string()));
+            } else {
+                fence_meta.flags.push(token.to_string());
+            }
+        }
+
+        fence_meta
+    }
+
+    /// The value of the first attribute with the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether the given flag is present.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// The title of the block, given as `title="..."`.
+    pub fn title(&self) -> Option<&str> {
+        self.get("title")
+    }
+
+    /// The number of the first line, if lines are to be numbered: 1 given
+    /// `showLineNumbers`, or `n` given `showLineNumbers=n`.
+    pub fn line_numbers(&self) -> Option<usize> {
+        if self.has_flag("showLineNumbers") {
+            return Some(1);
+        }
+        self.get("showLineNumbers")?.parse().ok()
+    }
+
+    /// Whether the given line, counting from 1, is highlighted.
+    pub fn is_highlighted(&self, line: usize) -> bool {
+        self.highlighted_lines
+            .iter()
+            .any(|&(start, end)| start <= line && line <= end)
+    }
+}
+
+/// The length of the token starting `s`: up to the next whitespace outside
+/// quotes and braces.
+fn token_end(s: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '{' => quote = Some('}'),
+            None if c.is_whitespace() => return i,
+            None => {}
+        }
+    }
+    s.len()
+}
+
+/// Parses `1,3-5` into inclusive ranges.
+fn parse_line_ranges(s: &str) -> Option<Vec<(usize, usize)>> {
+    s.split(',')
+        .map(|range| {
+            let range = range.trim();
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+            (start <= end).then_some((start, end))
+        })
+        .collect()
 }
 
 /// The struct passed to the [`HeadingAdapter`] for custom heading implementations.
@@ -68,6 +222,18 @@ pub struct HeadingMeta {
     /// `<strong>` or other tags are removed. In the Markdown heading `## This is **bold**`, for
     /// example, the would be the string `"This is bold"`.
     pub content: String,
+
+    /// The hierarchical section number of the heading, like `"1.2"`, when
+    /// [`heading_numbers`](crate::options::Render#structfield.heading_numbers) is enabled
+    /// and the heading opens a section.
+    pub number: Option<String>,
+
+    /// The heading's ID, with any
+    /// [`header_id_prefix`](crate::options::Extension#structfield.header_id_prefix), when
+    /// headings get IDs: its explicit `{#id}`, or the anchor generated for it by the
+    /// [`anchorizer_adapter`](crate::options::RenderPlugins#structfield.anchorizer_adapter)
+    /// or the default anchorizer.
+    pub id: Option<String>,
 }
 
 /// Implement this adapter for creating a plugin for custom headings (`h1`, `h2`, etc.). The `enter`
@@ -86,3 +252,134 @@ pub trait HeadingAdapter: Send + Sync {
     /// Render the closing tag.
     fn exit(&self, output: &mut dyn fmt::Write, heading: &HeadingMeta) -> fmt::Result;
 }
+
+/// Implement this adapter to change how heading anchors are generated, such as
+/// to match the slugs produced by another Markdown implementation.
+///
+/// Comrak keeps track of the anchors already generated in a document, and
+/// calls [`unique`](AnchorizerAdapter::unique) when a slug is already in use.
+pub trait AnchorizerAdapter: Send + Sync {
+    /// Converts the flattened content of a heading (see [`HeadingMeta::content`])
+    /// into a slug, without regard for uniqueness.
+    fn slugify(&self, header: &str) -> String;
+
+    /// Returns a variant of `slug` to try when it is already in use.  `n`
+    /// starts at 1 and increments until an unused anchor is returned.
+    ///
+    /// The default appends `-n`, as GitHub does.
+    fn unique(&self, slug: &str, n: usize) -> String {
+        format!("{}-{}", slug, n)
+    }
+}
+
+/// The struct passed to the [`FootnoteAdapter`] for each footnote reference.
+#[derive(Clone, Debug)]
+pub struct FootnoteReferenceMeta {
+    /// The name of the footnote, as given in the document.
+    pub name: String,
+
+    /// The number of the footnote.
+    pub ix: u32,
+
+    /// Which reference to the footnote this is, starting at 1.
+    pub ref_num: u32,
+
+    /// The ID of the reference, including any
+    /// [`footnote_id_prefix`](crate::options::Render#structfield.footnote_id_prefix).
+    pub id: String,
+
+    /// The `href` linking to the footnote definition, which may be on another
+    /// page (see [`split_document`](crate::html::split_document)).  Not
+    /// HTML-escaped.
+    pub href: String,
+}
+
+/// The struct passed to the [`FootnoteAdapter`] for each footnote definition.
+#[derive(Clone, Debug)]
+pub struct FootnoteMeta {
+    /// The name of the footnote, as given in the document.
+    pub name: String,
+
+    /// The number of the footnote.
+    pub ix: u32,
+
+    /// The ID of the definition, including any
+    /// [`footnote_id_prefix`](crate::options::Render#structfield.footnote_id_prefix).
+    pub id: String,
+
+    /// The `href` linking back to each reference to the footnote, in order.
+    /// Not HTML-escaped.
+    pub reference_hrefs: Vec<String>,
+
+    /// Where the definition is being rendered.  Definitions rendered with
+    /// [`FootnotePlacement::Inline`] are not wrapped in a section.
+    pub placement: FootnotePlacement,
+}
+
+/// Implement this adapter to take over rendering of footnotes.  The AST
+/// content of each definition is rendered between
+/// [`enter_definition`](FootnoteAdapter::enter_definition) and
+/// [`exit_definition`](FootnoteAdapter::exit_definition); backreferences are
+/// left to the adapter.
+pub trait FootnoteAdapter: Send + Sync {
+    /// Render a reference to a footnote.
+    fn write_reference(
+        &self,
+        output: &mut dyn fmt::Write,
+        reference: &FootnoteReferenceMeta,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result;
+
+    /// Render the opening of a section of footnote definitions.  `start` is
+    /// the number of the first footnote in the section.
+    fn enter_section(
+        &self,
+        output: &mut dyn fmt::Write,
+        start: u32,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result;
+
+    /// Render the closing of a section of footnote definitions.
+    fn exit_section(&self, output: &mut dyn fmt::Write) -> fmt::Result;
+
+    /// Render the opening of a footnote definition.
+    fn enter_definition(
+        &self,
+        output: &mut dyn fmt::Write,
+        footnote: &FootnoteMeta,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result;
+
+    /// Render the closing of a footnote definition.
+    fn exit_definition(&self, output: &mut dyn fmt::Write, footnote: &FootnoteMeta) -> fmt::Result;
+}
+
+/// Implement this adapter to render math on the server, such as into MathML
+/// or pre-rendered KaTeX, instead of leaving the TeX in `<span
+/// data-math-style>` or `<code>` for a client-side library.
+pub trait MathRendererAdapter: Send + Sync {
+    /// Render a math span, or a ```` ```math ```` code block.
+    ///
+    /// literal: The TeX source of the math.
+    /// display_math: Whether the math is displayed on its own (`$$...$$`,
+    /// ``$`...`$`` or a math code block) rather than inline.
+    fn write(
+        &self,
+        output: &mut dyn fmt::Write,
+        literal: &str,
+        display_math: bool,
+        sourcepos: Option<Sourcepos>,
+    ) -> Result<MathRendering, fmt::Error>;
+}
+
+/// Whether a [`MathRendererAdapter`] rendered some math.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MathRendering {
+    /// The adapter wrote the math.
+    Written,
+
+    /// The adapter couldn't render the math, and wrote nothing.  The math is
+    /// rendered as it is without an adapter, with the given reason in a
+    /// `data-math-error` attribute.
+    Failed(String),
+}
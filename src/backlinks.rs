@@ -0,0 +1,91 @@
+//! An index of the wikilinks between documents, for listing the pages which
+//! link to each page.
+//!
+//! [`Backlinks::add`] gathers the
+//! [wikilinks](crate::options::Extension::wikilinks_title_after_pipe) of a
+//! parsed document, so a build of several documents can list each one's
+//! backlinks, or the missing pages linked to.
+
+use std::collections::HashMap;
+
+use crate::html::collect_text;
+use crate::nodes::{Node, NodeValue, Sourcepos};
+
+/// A wikilink to a page, from another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backlink {
+    /// The name of the page containing the link.
+    pub from: String,
+
+    /// The heading linked to, as in `[[Page#Heading]]`.
+    pub heading: Option<String>,
+
+    /// The block linked to, as in `[[Page#^block-id]]`.
+    pub block: Option<String>,
+
+    /// The text of the link.
+    pub text: String,
+
+    /// Where the link is in the page containing it.
+    pub sourcepos: Sourcepos,
+}
+
+/// The wikilinks to each page, by the page's name as written in the links.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Backlinks {
+    /// The links to each page, in the order they were added.
+    pub pages: HashMap<String, Vec<Backlink>>,
+}
+
+impl Backlinks {
+    /// Constructs an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gathers the wikilinks of the parsed document named `page`.  Links
+    /// within the document, like `[[#Heading]]`, are skipped.
+    ///
+    /// ```rust
+    /// # use comrak::{Arena, parse_document, Options, backlinks::Backlinks};
+    /// let mut options = Options::default();
+    /// options.extension.wikilinks_title_after_pipe = true;
+    ///
+    /// let arena = Arena::new();
+    /// let mut backlinks = Backlinks::new();
+    /// backlinks.add("Home", parse_document(&arena, "See [[Usage#Install|installing]].\n", &options));
+    /// backlinks.add("FAQ", parse_document(&arena, "[[Usage]], [[#Why]]\n", &options));
+    ///
+    /// let usage = backlinks.get("Usage");
+    /// assert_eq!(usage.len(), 2);
+    /// assert_eq!(usage[0].from, "Home");
+    /// assert_eq!(usage[0].heading.as_deref(), Some("Install"));
+    /// assert_eq!(usage[0].text, "installing");
+    /// assert_eq!(usage[1].from, "FAQ");
+    /// assert!(backlinks.get("FAQ").is_empty());
+    /// ```
+    pub fn add(&mut self, page: &str, root: Node<'_>) {
+        for node in root.descendants() {
+            if let NodeValue::WikiLink(ref nl) = node.data().value {
+                if nl.page.is_empty() {
+                    continue;
+                }
+                self.pages
+                    .entry(nl.page.clone())
+                    .or_default()
+                    .push(Backlink {
+                        from: page.to_string(),
+                        heading: nl.heading.clone(),
+                        block: nl.block.clone(),
+                        text: collect_text(node),
+                        sourcepos: node.data().sourcepos,
+                    });
+            }
+        }
+    }
+
+    /// The links to the page named `page`.
+    pub fn get(&self, page: &str) -> &[Backlink] {
+        self.pages.get(page).map_or(&[], Vec::as_slice)
+    }
+}
@@ -1,20 +1,22 @@
 use std::cmp::max;
 use std::collections::HashSet;
 use std::fmt::{self, Write};
+use std::mem;
 use std::str;
 
 use crate::Arena;
 use crate::ctype::{isalpha, isdigit, ispunct, ispunct_char, isspace, isspace_char};
 use crate::nodes::{
-    ListDelimType, ListType, Node, NodeAlert, NodeBlockDirective, NodeCodeBlock, NodeHeading,
-    NodeHtmlBlock, NodeLink, NodeList, NodeMath, NodeTaskItem, NodeValue, NodeWikiLink,
-    TableAlignment,
+    ListDelimType, ListType, Node, NodeAlert, NodeBlockDirective, NodeCodeBlock, NodeEquation,
+    NodeFigure, NodeHeading, NodeHtmlBlock, NodeInclude, NodeLink, NodeList, NodeMath, NodeTable,
+    NodeTableCell, NodeTaskItem, NodeTypographic, NodeValue, NodeWikiLink, TableAlignment,
 };
 use crate::parser::options::{Options, Plugins, WikiLinksMode};
 #[cfg(feature = "phoenix_heex")]
 use crate::parser::phoenix_heex::NodeHeexBlock;
 #[cfg(feature = "shortcodes")]
 use crate::parser::shortcodes::NodeShortCode;
+use crate::parser::typographer;
 use crate::scanners;
 use crate::strings::trim_start_match;
 use crate::{node_matches, strings};
@@ -357,6 +359,18 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
     }
 
     fn format(&mut self, root: Node<'a>) -> fmt::Result {
+        self.format_tree(root)?;
+
+        if !self.wrap_buffer.is_empty() {
+            self.output.write_str(&self.wrap_buffer)?;
+        }
+        if !self.window.is_empty() && self.window.last() != Some(&b'\n') {
+            self.output.write_str("\n")?;
+        }
+        Ok(())
+    }
+
+    fn format_tree(&mut self, root: Node<'a>) -> fmt::Result {
         enum Phase {
             Pre,
             Post,
@@ -378,13 +392,6 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
                 }
             }
         }
-
-        if !self.wrap_buffer.is_empty() {
-            self.output.write_str(&self.wrap_buffer)?;
-        }
-        if !self.window.is_empty() && self.window.last() != Some(&b'\n') {
-            self.output.write_str("\n")?;
-        }
         Ok(())
     }
 
@@ -446,7 +453,7 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
             .next_sibling()
             .is_none_or(|next| next.data().value.block());
         let text_in_cell = node_matches!(node, NodeValue::Text(..))
-            && parent_node.is_some_and(|n| node_matches!(n, NodeValue::TableCell));
+            && parent_node.is_some_and(|n| node_matches!(n, NodeValue::TableCell(..)));
 
         match node.data().value {
             NodeValue::Document => (),
@@ -489,9 +496,17 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
             NodeValue::Image(ref nl) => self.format_image(nl, entering)?,
             #[cfg(feature = "shortcodes")]
             NodeValue::ShortCode(ref ne) => self.format_shortcode(ne, entering)?,
-            NodeValue::Table(..) => self.format_table(entering),
-            NodeValue::TableRow(..) => self.format_table_row(entering)?,
-            NodeValue::TableCell => self.format_table_cell(node, entering)?,
+            NodeValue::Table(ref nt) if nt.grid => {
+                self.format_grid_table(node, nt)?;
+                return Ok(false);
+            }
+            NodeValue::Table(ref nt) => self.format_table(node, nt, entering)?,
+            NodeValue::TableRow(header) => self.format_table_row(node, header, entering)?,
+            NodeValue::TableCell(ref ntc) => self.format_table_cell(node, ntc, entering)?,
+            NodeValue::TableCaption => {
+                // Written by the table, after its rows.
+                return Ok(false);
+            }
             NodeValue::FootnoteDefinition(ref nfd) => {
                 self.format_footnote_definition(&nfd.name, entering)?
             }
@@ -504,6 +519,10 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
                 // AST marker created by escaped_char_spans.
             }
             NodeValue::Math(ref math) => self.format_math(math, entering)?,
+            NodeValue::WikiLink(ref nl) if nl.width.is_some() => {
+                self.format_sized_embed(nl)?;
+                return Ok(false);
+            }
             NodeValue::WikiLink(ref nl) => self.format_wikilink(nl, entering)?,
             NodeValue::Underline => self.format_underline()?,
             NodeValue::Subscript => self.format_subscript()?,
@@ -512,6 +531,19 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
             NodeValue::Alert(ref alert) => self.format_alert(alert, entering)?,
             NodeValue::Subtext => self.format_subtext(entering)?,
             NodeValue::BlockDirective(ref nbd) => self.format_block_directive(nbd, entering)?,
+            NodeValue::TableOfContents => self.format_table_of_contents(entering)?,
+            NodeValue::Typographic(ref nt) => self.format_typographic(nt, entering)?,
+            NodeValue::Equation(ref ne) => self.format_equation(ne, entering)?,
+            NodeValue::Figure(ref nf) => self.format_figure(nf, entering)?,
+            NodeValue::BlockAnchor(ref id) => {
+                if entering {
+                    write!(self, " ^{}", id)?;
+                }
+            }
+            NodeValue::Include(ref ni) => {
+                self.format_include(ni)?;
+                return Ok(false);
+            }
         };
         Ok(true)
     }
@@ -654,6 +686,9 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
             self.begin_content = true;
             self.no_linebreaks = true;
         } else {
+            if let Some(ref id) = nh.id {
+                write!(self, " {{#{}}}", id)?;
+            }
             self.no_linebreaks = false;
             self.blankline();
         }
@@ -755,7 +790,17 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
 
     fn format_text(&mut self, literal: &str, entering: bool, wrap: bool) -> fmt::Result {
         if entering {
-            self.output(literal, wrap, Escaping::Normal)?;
+            let mut start = 0;
+            if self.options.parse.typographer {
+                // Don't let text which wasn't replaced be replaced when the
+                // output is parsed again.
+                for pos in typographer::escape_positions(literal) {
+                    self.output(&literal[start..pos], wrap, Escaping::Normal)?;
+                    self.output("\\", false, Escaping::Literal)?;
+                    start = pos;
+                }
+            }
+            self.output(&literal[start..], wrap, Escaping::Normal)?;
         }
         Ok(())
     }
@@ -951,15 +996,18 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
 
     fn format_wikilink(&mut self, nl: &NodeWikiLink, entering: bool) -> fmt::Result {
         if entering {
+            if nl.embed {
+                write!(self, "!")?;
+            }
             write!(self, "[[")?;
             if self.options.extension.wikilinks() == Some(WikiLinksMode::UrlFirst) {
-                self.output(&nl.url, false, Escaping::Url)?;
+                self.output(&nl.target(), false, Escaping::Url)?;
                 write!(self, "|").unwrap();
             }
         } else {
             if self.options.extension.wikilinks() == Some(WikiLinksMode::TitleFirst) {
                 write!(self, "|")?;
-                self.output(&nl.url, false, Escaping::Url)?;
+                self.output(&nl.target(), false, Escaping::Url)?;
             }
             write!(self, "]]")?;
         }
@@ -967,6 +1015,24 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
         Ok(())
     }
 
+    // The size takes the place of the link label, as in `![[image.png|300]]`.
+    fn format_sized_embed(&mut self, nl: &NodeWikiLink) -> fmt::Result {
+        let size = match (nl.width, nl.height) {
+            (Some(width), Some(height)) => format!("{}x{}", width, height),
+            (Some(width), None) => width.to_string(),
+            _ => String::new(),
+        };
+        write!(self, "![[")?;
+        if self.options.extension.wikilinks() == Some(WikiLinksMode::TitleFirst) {
+            write!(self, "{}|", size)?;
+            self.output(&nl.target(), false, Escaping::Url)?;
+        } else {
+            self.output(&nl.target(), false, Escaping::Url)?;
+            write!(self, "|{}", size)?;
+        }
+        write!(self, "]]")
+    }
+
     fn format_image(&mut self, nl: &NodeLink, entering: bool) -> fmt::Result {
         if entering {
             write!(self, "![")?;
@@ -993,59 +1059,239 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
         Ok(())
     }
 
-    fn format_table(&mut self, entering: bool) {
+    fn format_table(&mut self, node: Node<'a>, nt: &NodeTable, entering: bool) -> fmt::Result {
         if entering {
             self.custom_escape = Some(table_escape);
         } else {
             self.custom_escape = None;
         }
         self.blankline();
+
+        if !entering {
+            self.format_table_caption(node, nt)?;
+        }
+
+        // A table without a header row starts with its delimiter row.
+        if entering
+            && !node
+                .children()
+                .any(|n| node_matches!(n, NodeValue::TableRow(true)))
+        {
+            self.format_table_delimiter_row(nt)?;
+        }
+        Ok(())
     }
 
-    fn format_table_row(&mut self, entering: bool) -> fmt::Result {
+    fn format_table_row(&mut self, node: Node<'a>, header: bool, entering: bool) -> fmt::Result {
         if entering {
             self.cr();
             write!(self, "|")?;
+        } else {
+            let table = node.parent().unwrap();
+            let num_columns = match table.data().value {
+                NodeValue::Table(ref nt) => nt.num_columns,
+                _ => panic!(),
+            };
+            self.format_table_row_spans(node.last_child(), num_columns)?;
+
+            if header
+                && !node
+                    .next_sibling()
+                    .is_some_and(|n| node_matches!(n, NodeValue::TableRow(true)))
+            {
+                let nt = match table.data().value {
+                    NodeValue::Table(ref nt) => nt.clone(),
+                    _ => panic!(),
+                };
+                self.cr();
+                self.format_table_delimiter_row(&nt)?;
+            }
         }
         Ok(())
     }
 
-    fn format_table_cell(&mut self, node: Node<'a>, entering: bool) -> fmt::Result {
+    fn format_table_cell(
+        &mut self,
+        node: Node<'a>,
+        ntc: &NodeTableCell,
+        entering: bool,
+    ) -> fmt::Result {
         if entering {
+            self.format_table_row_spans(node.previous_sibling(), ntc.column)?;
             write!(self, " ")?;
+            self.no_linebreaks = true;
         } else {
+            self.no_linebreaks = false;
             write!(self, " |")?;
+            for _ in 1..ntc.colspan {
+                write!(self, "|")?;
+            }
+        }
+        Ok(())
+    }
 
-            let row = &node.parent().unwrap().data().value;
-            let in_header = match *row {
-                NodeValue::TableRow(header) => header,
-                _ => panic!(),
-            };
+    /// Writes `^^` cells for the columns before `column` that are covered by
+    /// cells spanning rows from above, i.e. that follow `previous` in the row
+    /// without a cell of their own.
+    fn format_table_row_spans(&mut self, previous: Option<Node<'a>>, column: usize) -> fmt::Result {
+        let mut next_column = previous.map_or(0, |n| match n.data().value {
+            NodeValue::TableCell(ref ntc) => ntc.column + ntc.colspan,
+            _ => 0,
+        });
+        while next_column < column {
+            write!(self, " ^^ |")?;
+            next_column += 1;
+        }
+        Ok(())
+    }
 
-            if in_header && node.next_sibling().is_none() {
-                let table = &node.parent().unwrap().parent().unwrap().data().value;
-                let alignments = match table {
-                    NodeValue::Table(nt) => &nt.alignments,
-                    _ => panic!(),
+    /// Writes the delimiter row of a table.  When column widths are
+    /// rendered, each column keeps its number of dashes so that the widths
+    /// survive a round trip.
+    fn format_table_delimiter_row(&mut self, nt: &NodeTable) -> fmt::Result {
+        write!(self, "|")?;
+        for (i, a) in nt.alignments.iter().enumerate() {
+            let width = nt.column_widths.get(i).copied().unwrap_or(0);
+            if self.options.render.table_column_widths && width > 0 {
+                let (left, right) = match *a {
+                    TableAlignment::Left => (":", ""),
+                    TableAlignment::Center => (":", ":"),
+                    TableAlignment::Right => ("", ":"),
+                    TableAlignment::None => ("", ""),
                 };
+                write!(self, " {}{}{} |", left, "-".repeat(width), right)?;
+                continue;
+            }
 
-                self.cr();
-                write!(self, "|")?;
-                for a in alignments {
-                    write!(
-                        self,
-                        " {} |",
-                        match *a {
-                            TableAlignment::Left => ":--",
-                            TableAlignment::Center => ":-:",
-                            TableAlignment::Right => "--:",
-                            TableAlignment::None => "---",
+            write!(
+                self,
+                " {} |",
+                match *a {
+                    TableAlignment::Left => ":--",
+                    TableAlignment::Center => ":-:",
+                    TableAlignment::Right => "--:",
+                    TableAlignment::None => "---",
+                }
+            )?;
+        }
+        self.cr();
+        Ok(())
+    }
+
+    /// Writes a grid table whole: the blocks of each cell are formatted on
+    /// their own, then laid out in columns wide enough to fit them.
+    fn format_grid_table(&mut self, node: Node<'a>, nt: &NodeTable) -> fmt::Result {
+        let mut rows = vec![];
+        for row in node
+            .children()
+            .filter(|n| node_matches!(n, NodeValue::TableRow(..)))
+        {
+            let mut cells = vec![vec![]; nt.num_columns];
+            for cell in row.children() {
+                let NodeValue::TableCell(ref ntc) = cell.data().value else {
+                    continue;
+                };
+                let mut out = String::new();
+                let mut f = CommonMarkFormatter::new(cell, self.options, &mut out);
+                for child in cell.children() {
+                    f.format_tree(child)?;
+                }
+                let wrapped = mem::take(&mut f.wrap_buffer);
+                f.output.write_str(&wrapped)?;
+                cells[ntc.column] = out.lines().map(str::to_string).collect::<Vec<_>>();
+            }
+            rows.push((node_matches!(row, NodeValue::TableRow(true)), cells));
+        }
+
+        let widths = (0..nt.num_columns)
+            .map(|c| {
+                rows.iter()
+                    .flat_map(|(_, cells)| &cells[c])
+                    .map(|l| l.chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .max(1)
+                    .max(match nt.column_widths.get(c) {
+                        // Keep the widths of the source where the content fits.
+                        Some(&width) if self.options.render.table_column_widths => {
+                            width.saturating_sub(2)
                         }
-                    )?;
+                        _ => 0,
+                    })
+            })
+            .collect::<Vec<_>>();
+        let num_header_rows = rows.iter().take_while(|(header, _)| *header).count();
+
+        self.blankline();
+        self.format_grid_table_border(&widths, '-', num_header_rows == 0, &nt.alignments)?;
+        for (i, (_, cells)) in rows.iter().enumerate() {
+            let height = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+            for l in 0..height {
+                write!(self, "|")?;
+                for (lines, &width) in cells.iter().zip(&widths) {
+                    let line = lines.get(l).map_or("", String::as_str);
+                    write!(self, " {:width$} |", line)?;
                 }
                 self.cr();
             }
+
+            let header_border = i + 1 == num_header_rows;
+            self.format_grid_table_border(
+                &widths,
+                if header_border { '=' } else { '-' },
+                header_border,
+                &nt.alignments,
+            )?;
+        }
+        self.blankline();
+        self.format_table_caption(node, nt)
+    }
+
+    fn format_table_caption(&mut self, node: Node<'a>, nt: &NodeTable) -> fmt::Result {
+        if let Some(caption) = node
+            .first_child()
+            .filter(|n| node_matches!(n, NodeValue::TableCaption))
+        {
+            let mut out = String::new();
+            let mut f = CommonMarkFormatter::new(caption, self.options, &mut out);
+            for child in caption.children() {
+                f.format_tree(child)?;
+            }
+            let wrapped = mem::take(&mut f.wrap_buffer);
+            f.output.write_str(&wrapped)?;
+
+            write!(self, "Table: {}", out.trim_end())?;
+            if let Some(ref id) = nt.id {
+                write!(self, " {{#{}}}", id)?;
+            }
+            self.blankline();
+        }
+        Ok(())
+    }
+
+    fn format_grid_table_border(
+        &mut self,
+        widths: &[usize],
+        fill: char,
+        aligned: bool,
+        alignments: &[TableAlignment],
+    ) -> fmt::Result {
+        write!(self, "+")?;
+        for (&width, &alignment) in widths.iter().zip(alignments) {
+            let (left, right) = match alignment {
+                _ if !aligned => (fill, fill),
+                TableAlignment::Left => (':', fill),
+                TableAlignment::Center => (':', ':'),
+                TableAlignment::Right => (fill, ':'),
+                TableAlignment::None => (fill, fill),
+            };
+            write!(self, "{}", left)?;
+            for _ in 0..width {
+                write!(self, "{}", fill)?;
+            }
+            write!(self, "{}+", right)?;
         }
+        self.cr();
         Ok(())
     }
 
@@ -1142,6 +1388,52 @@ impl<'a, 'o, 'c, 'w> CommonMarkFormatter<'a, 'o, 'c, 'w> {
         }
         Ok(())
     }
+
+    fn format_equation(&mut self, ne: &NodeEquation, entering: bool) -> fmt::Result {
+        if !entering {
+            write!(self, " {{#{}}}", ne.label)?;
+        }
+        Ok(())
+    }
+
+    fn format_figure(&mut self, nf: &NodeFigure, entering: bool) -> fmt::Result {
+        if !entering {
+            write!(self, "{{#{}}}", nf.label)?;
+        }
+        Ok(())
+    }
+
+    fn format_typographic(&mut self, nt: &NodeTypographic, entering: bool) -> fmt::Result {
+        if entering {
+            let literal = if self.options.render.typographer_source {
+                nt.source
+            } else {
+                nt.text()
+            };
+            self.output(literal, false, Escaping::Literal)?;
+        }
+        Ok(())
+    }
+
+    fn format_table_of_contents(&mut self, entering: bool) -> fmt::Result {
+        if entering {
+            self.blankline();
+            write!(self, "[[_TOC_]]")?;
+            self.blankline();
+        }
+        Ok(())
+    }
+
+    fn format_include(&mut self, ni: &NodeInclude) -> fmt::Result {
+        self.blankline();
+        if ni.embed {
+            write!(self, "![[{}]]", ni.target)?;
+        } else {
+            write!(self, "{{{{#include {}}}}}", ni.target)?;
+        }
+        self.blankline();
+        Ok(())
+    }
 }
 
 fn longest_byte_sequence(buffer: &[u8], ch: u8) -> usize {
@@ -1210,7 +1502,7 @@ fn is_autolink(node: Node<'_>, nl: &NodeLink) -> bool {
 
 fn table_escape(node: Node<'_>, c: char) -> bool {
     match node.data().value {
-        NodeValue::Table(..) | NodeValue::TableRow(..) | NodeValue::TableCell => false,
+        NodeValue::Table(..) | NodeValue::TableRow(..) | NodeValue::TableCell(..) => false,
         _ => c == '|',
     }
 }
@@ -0,0 +1,602 @@
+//! Numbering of labelled parts of a document, and references to them.
+//!
+//! Display math labelled per
+//! [`math_labels`](crate::options::Extension::math_labels) is numbered, and
+//! `@eq:label` and `\eqref{label}` refer to it.  With
+//! [`cross_references`](crate::options::Extension::cross_references), labelled
+//! figures, tables, sections and code listings are numbered too, each kind on
+//! its own, and `[@label]` refers to any of them, reading e.g. "Figure 3".
+//!
+//! [`CrossReferences::collect`] gathers the labels of a parsed document, so a
+//! build of several documents can resolve references between them with
+//! [`external_cross_references`](crate::options::Parse::external_cross_references).
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::Arena;
+use crate::nodes::{Node, NodeFigure, NodeLink, NodeValue, Sourcepos};
+use crate::parser::inlines::make_inline;
+use crate::parser::options::{BrokenLinkReference, Options};
+use crate::sections::{SectionNumberer, format_section_number, is_section_heading};
+use crate::strings;
+
+/// The kind of a labelled part of a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetKind {
+    /// Display math, labelled like `$$E = mc^2$$ {#eq:energy}`, or in the
+    /// info string of a math code block like ```` ```math {#eq:energy} ````.
+    Equation,
+
+    /// An image, labelled like `![Architecture](arch.png){#fig:arch}`.
+    Figure,
+
+    /// A table, labelled in its caption like `Table: Results {#tbl:results}`.
+    Table,
+
+    /// A section, labelled in its heading like `# Introduction {#sec:intro}`.
+    Section,
+
+    /// A fenced code block other than math, labelled in its info string like
+    /// ```` ```rust {#lst:main} ````.
+    Listing,
+}
+
+/// A labelled part of a document, which references can refer to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    /// What kind of thing is labelled.
+    pub kind: TargetKind,
+
+    /// The number of the target among those of its kind, counted in document
+    /// order, e.g. `"3"`; or the section number, e.g. `"1.2"`.
+    pub number: String,
+
+    /// The URL references link to: `#` followed by the label, for targets in
+    /// the same document.
+    pub url: String,
+}
+
+/// The labelled parts of a document, by label.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CrossReferences {
+    /// The targets, by label.  Where a label is used more than once, the
+    /// first use is the target.
+    pub targets: HashMap<String, Target>,
+}
+
+impl CrossReferences {
+    /// Gathers the labelled parts of a parsed document.
+    ///
+    /// ```rust
+    /// # use comrak::{Arena, parse_document, Options, crossref::{CrossReferences, TargetKind}};
+    /// let mut options = Options::default();
+    /// options.extension.cross_references = true;
+    /// options.extension.header_custom_ids = true;
+    ///
+    /// let arena = Arena::new();
+    /// let root = parse_document(&arena, "# Intro {#sec:intro}\n\n![A cat](cat.png){#fig:cat}\n", &options);
+    ///
+    /// let crossrefs = CrossReferences::collect(root).with_base_url("intro.html");
+    /// let target = &crossrefs.targets["fig:cat"];
+    /// assert_eq!(target.kind, TargetKind::Figure);
+    /// assert_eq!(target.number, "1");
+    /// assert_eq!(target.url, "intro.html#fig:cat");
+    /// ```
+    pub fn collect(root: Node<'_>) -> Self {
+        let mut targets = HashMap::new();
+        for labelled in labelled_nodes(root) {
+            targets
+                .entry(labelled.label.clone())
+                .or_insert_with(|| Target {
+                    kind: labelled.kind,
+                    number: labelled.number,
+                    url: format!("#{}", labelled.label),
+                });
+        }
+        CrossReferences { targets }
+    }
+
+    /// Prefixes the URL of every target with `base`, such as the path of the
+    /// document the targets are in.
+    pub fn with_base_url(mut self, base: &str) -> Self {
+        for target in self.targets.values_mut() {
+            target.url.insert_str(0, base);
+        }
+        self
+    }
+}
+
+/// The words references to each kind of target are prefixed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Prefixes {
+    /// The prefix of references to equations; "Equation" by default.
+    pub equation: String,
+
+    /// The prefix of references to figures; "Figure" by default.
+    pub figure: String,
+
+    /// The prefix of references to tables; "Table" by default.
+    pub table: String,
+
+    /// The prefix of references to sections; "Section" by default.
+    pub section: String,
+
+    /// The prefix of references to code listings; "Listing" by default.
+    pub listing: String,
+}
+
+impl Default for Prefixes {
+    fn default() -> Self {
+        Prefixes {
+            equation: "Equation".to_string(),
+            figure: "Figure".to_string(),
+            table: "Table".to_string(),
+            section: "Section".to_string(),
+            listing: "Listing".to_string(),
+        }
+    }
+}
+
+impl Prefixes {
+    /// The prefix of references to the given kind of target.
+    pub fn get(&self, kind: TargetKind) -> &str {
+        match kind {
+            TargetKind::Equation => &self.equation,
+            TargetKind::Figure => &self.figure,
+            TargetKind::Table => &self.table,
+            TargetKind::Section => &self.section,
+            TargetKind::Listing => &self.listing,
+        }
+    }
+}
+
+/// The label prefixes of each kind of target, per pandoc-crossref.
+/// References to missing labels are passed to the broken link callback only
+/// when they use one of these, so as not to catch citations like `[@doe99]`.
+const LABEL_PREFIXES: &[&str] = &["eq:", "fig:", "tbl:", "sec:", "lst:"];
+
+pub(crate) fn is_label_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b':' | b'.')
+}
+
+/// Returns the info string of a code block without any trailing `{#label}`,
+/// and the label.
+pub(crate) fn listing_label(info: &str) -> Option<(&str, &str)> {
+    strings::chop_trailing_custom_id(info)
+}
+
+/// Whether a code block's info string, without its label, is that of display
+/// math, which is labelled as an equation rather than a listing.
+pub(crate) fn is_math_info(info: &str) -> bool {
+    info.split_whitespace().next() == Some("math")
+}
+
+struct Labelled<'a> {
+    node: Node<'a>,
+    label: String,
+    kind: TargetKind,
+    number: String,
+    /// The number of the target, for those other than sections.
+    ordinal: usize,
+}
+
+fn labelled_nodes(root: Node<'_>) -> Vec<Labelled<'_>> {
+    let mut labelled = vec![];
+    let mut counts: HashMap<TargetKind, usize> = HashMap::new();
+    let mut sections = SectionNumberer::default();
+
+    for node in root.descendants() {
+        let (label, kind, section) = match node.data().value {
+            NodeValue::Equation(ref ne) => (ne.label.clone(), TargetKind::Equation, None),
+            NodeValue::Figure(ref nf) => (nf.label.clone(), TargetKind::Figure, None),
+            NodeValue::Table(ref nt) => match nt.id {
+                Some(ref id) => (id.clone(), TargetKind::Table, None),
+                None => continue,
+            },
+            NodeValue::Heading(ref nh) if is_section_heading(node) => {
+                let number = sections.next(nh.level);
+                match nh.id {
+                    Some(ref id) => (id.clone(), TargetKind::Section, Some(number)),
+                    None => continue,
+                }
+            }
+            NodeValue::CodeBlock(ref ncb) if ncb.fenced => match listing_label(&ncb.info) {
+                Some((info, label)) if is_math_info(info) => {
+                    (label.to_string(), TargetKind::Equation, None)
+                }
+                Some((_, label)) => (label.to_string(), TargetKind::Listing, None),
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        let count = counts.entry(kind).or_default();
+        *count += 1;
+        let number = match section {
+            Some(section) => format_section_number(&section).unwrap(),
+            None => count.to_string(),
+        };
+        labelled.push(Labelled {
+            node,
+            label,
+            kind,
+            number,
+            ordinal: *count,
+        });
+    }
+
+    labelled
+}
+
+/// Labels figures, numbers labelled parts of the document, then turns
+/// references to them into links.  This happens after inline parsing, as
+/// references may come before what they refer to.
+pub(crate) fn process<'a>(arena: &'a Arena<'a>, root: Node<'a>, options: &Options) {
+    let mut runs = vec![];
+    let mut figures = vec![];
+    for node in root.descendants() {
+        match node.data().value {
+            // Only the first of a run of text nodes; see `TextRun`.
+            NodeValue::Text(..)
+                if !node
+                    .previous_sibling()
+                    .is_some_and(|n| matches!(n.data().value, NodeValue::Text(..))) =>
+            {
+                if let Some(image) = node
+                    .previous_sibling()
+                    .filter(|n| matches!(n.data().value, NodeValue::Image(..)))
+                {
+                    figures.push((image, runs.len()));
+                }
+                runs.push(TextRun::new(node));
+            }
+            _ => {}
+        }
+    }
+
+    if options.extension.cross_references {
+        for (image, run) in figures {
+            label_figure(arena, image, &mut runs[run]);
+        }
+    }
+
+    let mut crossrefs = CrossReferences::default();
+    for labelled in labelled_nodes(root) {
+        match labelled.node.data_mut().value {
+            NodeValue::Equation(ref mut ne) => ne.number = labelled.ordinal,
+            NodeValue::Figure(ref mut nf) => nf.number = labelled.ordinal,
+            NodeValue::Table(ref mut nt) if options.extension.cross_references => {
+                nt.number = Some(labelled.ordinal);
+            }
+            _ => {}
+        }
+        crossrefs
+            .targets
+            .entry(labelled.label.clone())
+            .or_insert_with(|| Target {
+                kind: labelled.kind,
+                number: labelled.number,
+                url: format!("#{}", labelled.label),
+            });
+    }
+
+    for mut run in runs {
+        // Text following a figure's image may have been emptied of its label.
+        let Some(&node) = run.nodes.first() else {
+            continue;
+        };
+        let in_link = node.ancestors().any(|n| {
+            matches!(
+                n.data().value,
+                NodeValue::Link(..) | NodeValue::Image(..) | NodeValue::WikiLink(..)
+            )
+        });
+        if !in_link {
+            resolve_references(arena, &mut run, &crossrefs, options);
+        }
+    }
+}
+
+/// A run of adjacent text nodes, whose text is searched as a whole for
+/// references and labels, as a backslash, for one, is left in a text node of
+/// its own.  Only the nodes a reference or label starts or ends inside of are
+/// split, so each piece keeps the sourcepos of what it was parsed from, even
+/// where its text differs from the source, as for an entity.
+struct TextRun<'a> {
+    nodes: Vec<Node<'a>>,
+}
+
+impl<'a> TextRun<'a> {
+    fn new(first: Node<'a>) -> Self {
+        let nodes = std::iter::successors(Some(first), |n| n.next_sibling())
+            .take_while(|n| matches!(n.data().value, NodeValue::Text(..)))
+            .collect();
+        TextRun { nodes }
+    }
+
+    fn text(&self) -> String {
+        self.nodes
+            .iter()
+            .map(|n| n.data().value.text().unwrap().to_string())
+            .collect()
+    }
+
+    /// Splits the node `offset` falls inside of, so that a node starts there,
+    /// returning its index.  Columns are only counted within a node whose text
+    /// is verbatim from the source; otherwise both halves keep its span.
+    fn split_at(&mut self, arena: &'a Arena<'a>, offset: usize) -> usize {
+        let mut start = 0;
+        for (i, &node) in self.nodes.iter().enumerate() {
+            if offset == start {
+                return i;
+            }
+            let mut ast = node.data_mut();
+            let sourcepos = ast.sourcepos;
+            let text = ast.value.text_mut().unwrap();
+            let len = text.len();
+            if offset < start + len {
+                let at = offset - start;
+                let rest = text[at..].to_string();
+                text.to_mut().truncate(at);
+
+                let verbatim = sourcepos.start.line == sourcepos.end.line
+                    && sourcepos.end.column + 1 == sourcepos.start.column + len;
+                let (end, rest_start) = if verbatim {
+                    (
+                        sourcepos.start.column_add(at as isize - 1),
+                        sourcepos.start.column_add(at as isize),
+                    )
+                } else {
+                    (sourcepos.end, sourcepos.start)
+                };
+                ast.sourcepos.end = end;
+                drop(ast);
+
+                let after = make_inline(
+                    arena,
+                    NodeValue::Text(rest.into()),
+                    (rest_start, sourcepos.end).into(),
+                );
+                node.insert_after(after);
+                self.nodes.insert(i + 1, after);
+                return i + 1;
+            }
+            start += len;
+        }
+        self.nodes.len()
+    }
+
+    /// Takes the nodes holding the bytes `range` of the text out of the run,
+    /// replacing them in the tree with what `replacement` makes given their
+    /// sourcepos.
+    fn replace(
+        &mut self,
+        arena: &'a Arena<'a>,
+        range: Range<usize>,
+        replacement: impl FnOnce(Sourcepos) -> Node<'a>,
+    ) {
+        let first = self.split_at(arena, range.start);
+        let last = self.split_at(arena, range.end);
+        let taken = self.nodes.drain(first..last).collect::<Vec<_>>();
+        let sourcepos = (
+            taken[0].data().sourcepos.start,
+            taken[taken.len() - 1].data().sourcepos.end,
+        )
+            .into();
+        taken[0].insert_before(replacement(sourcepos));
+        for node in taken {
+            node.detach();
+        }
+    }
+}
+
+/// Wraps an image directly followed by `{#label}` in a figure.
+fn label_figure<'a>(arena: &'a Arena<'a>, image: Node<'a>, run: &mut TextRun<'a>) {
+    let text = run.text();
+    let Some(rest) = text.strip_prefix("{#") else {
+        return;
+    };
+    let Some(len) = rest.find('}') else {
+        return;
+    };
+    let label = &rest[..len];
+    if label.is_empty() || !label.bytes().all(is_label_byte) {
+        return;
+    }
+
+    let nf = NodeFigure {
+        label: label.to_string(),
+        number: 0,
+    };
+    run.replace(arena, 0..len + 3, |sourcepos| {
+        let figure = make_inline(
+            arena,
+            NodeValue::Figure(Box::new(nf)),
+            (image.data().sourcepos.start, sourcepos.end).into(),
+        );
+        figure.append(image);
+        figure
+    });
+}
+
+/// How a reference is written, which determines how it reads once resolved.
+#[derive(Clone, Copy)]
+enum Style {
+    /// `[@label]`, reading e.g. "Figure 3".
+    Bracketed,
+    /// `@eq:label`, reading e.g. "(3)".
+    Equation,
+    /// `\eqref{label}`, reading e.g. "(3)"; the label may omit `eq:`.
+    Eqref,
+}
+
+/// A reference found in text: its byte range, and the label it names.
+struct Reference<'t> {
+    start: usize,
+    end: usize,
+    label: &'t str,
+    style: Style,
+}
+
+fn scan_references<'t>(text: &'t str, options: &Options) -> Vec<Reference<'t>> {
+    let bytes = text.as_bytes();
+    let mut references = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let found = if options.extension.cross_references && text[i..].starts_with("[@") {
+            let len = bytes[i + 2..]
+                .iter()
+                .take_while(|&&b| is_label_byte(b))
+                .count();
+            let end = i + 2 + len;
+            (len > 0 && bytes.get(end) == Some(&b']')).then(|| Reference {
+                start: i,
+                end: end + 1,
+                label: &text[i + 2..end],
+                style: Style::Bracketed,
+            })
+        } else if !options.extension.math_labels {
+            None
+        } else if bytes[i] == b'@' && (i == 0 || !bytes[i - 1].is_ascii_alphanumeric()) {
+            let len = bytes[i + 1..]
+                .iter()
+                .take_while(|&&b| is_label_byte(b))
+                .count();
+            // Sentence punctuation after a reference isn't part of it.
+            let label = text[i + 1..i + 1 + len].trim_end_matches(['.', ':', '-']);
+            (label.len() > 3 && label.starts_with("eq:")).then(|| Reference {
+                start: i,
+                end: i + 1 + label.len(),
+                label,
+                style: Style::Equation,
+            })
+        } else if let Some(rest) = text[i..].strip_prefix("\\eqref{") {
+            rest.find('}').and_then(|len| {
+                let label = &rest[..len];
+                (!label.is_empty() && label.bytes().all(is_label_byte)).then(|| Reference {
+                    start: i,
+                    end: i + "\\eqref{".len() + len + 1,
+                    label,
+                    style: Style::Eqref,
+                })
+            })
+        } else {
+            None
+        };
+
+        match found {
+            Some(reference) => {
+                i = reference.end;
+                references.push(reference);
+            }
+            None => i += 1,
+        }
+    }
+
+    references
+}
+
+fn lookup<'o>(
+    label: &str,
+    crossrefs: &'o CrossReferences,
+    options: &'o Options,
+) -> Option<&'o Target> {
+    crossrefs.targets.get(label).or_else(|| {
+        options
+            .parse
+            .external_cross_references
+            .as_ref()?
+            .targets
+            .get(label)
+    })
+}
+
+fn resolve<'o>(
+    reference: &Reference<'_>,
+    crossrefs: &'o CrossReferences,
+    options: &'o Options,
+) -> Option<&'o Target> {
+    match lookup(reference.label, crossrefs, options) {
+        None if matches!(reference.style, Style::Eqref) => {
+            lookup(&format!("eq:{}", reference.label), crossrefs, options)
+        }
+        target => target,
+    }
+}
+
+fn resolve_references<'a>(
+    arena: &'a Arena<'a>,
+    run: &mut TextRun<'a>,
+    crossrefs: &CrossReferences,
+    options: &Options,
+) {
+    let text = run.text();
+    // The bytes of the text taken out of the run so far.
+    let mut taken = 0;
+    for reference in scan_references(&text, options) {
+        let original = &text[reference.start..reference.end];
+
+        let (link, link_text) = match resolve(&reference, crossrefs, options) {
+            Some(target) => {
+                let link_text = match reference.style {
+                    Style::Bracketed => {
+                        let prefix = options.extension.cross_reference_prefixes.get(target.kind);
+                        if prefix.is_empty() {
+                            target.number.clone()
+                        } else {
+                            format!("{} {}", prefix, target.number)
+                        }
+                    }
+                    Style::Equation | Style::Eqref => format!("({})", target.number),
+                };
+                (
+                    NodeLink {
+                        url: target.url.clone(),
+                        title: String::new(),
+                    },
+                    link_text,
+                )
+            }
+            None => {
+                let Some(callback) = &options.parse.broken_link_callback else {
+                    continue;
+                };
+                if matches!(reference.style, Style::Bracketed)
+                    && !LABEL_PREFIXES
+                        .iter()
+                        .any(|prefix| reference.label.starts_with(prefix))
+                {
+                    continue;
+                }
+                let Some(reff) = callback.resolve(BrokenLinkReference {
+                    normalized: reference.label,
+                    original,
+                }) else {
+                    continue;
+                };
+                (
+                    NodeLink {
+                        url: reff.url,
+                        title: reff.title,
+                    },
+                    original.to_string(),
+                )
+            }
+        };
+
+        let range = reference.start - taken..reference.end - taken;
+        run.replace(arena, range, |sourcepos| {
+            let link = make_inline(arena, NodeValue::Link(Box::new(link)), sourcepos);
+            link.append(make_inline(
+                arena,
+                NodeValue::Text(link_text.into()),
+                sourcepos,
+            ));
+            link
+        });
+        taken += reference.end - reference.start;
+    }
+}
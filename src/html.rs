@@ -7,28 +7,37 @@
 
 mod anchorizer;
 mod context;
+mod deferred;
+mod footnotes;
+mod pages;
+mod toc;
 
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::{self, Write};
 use std::str;
 
-use crate::adapters::HeadingMeta;
+use crate::adapters::{FenceMeta, FootnoteMeta, FootnoteReferenceMeta, HeadingMeta, MathRendering};
 use crate::character_set::character_set;
 use crate::ctype::isspace;
 #[cfg(feature = "shortcodes")]
 use crate::nodes::NodeShortCode;
 use crate::nodes::{
-    ListType, Node, NodeAlert, NodeBlockDirective, NodeCode, NodeCodeBlock, NodeFootnoteDefinition,
-    NodeFootnoteReference, NodeHeading, NodeHtmlBlock, NodeLink, NodeList, NodeMath, NodeTaskItem,
-    NodeValue, NodeWikiLink, TableAlignment,
+    ListType, Node, NodeAlert, NodeBlockDirective, NodeCode, NodeCodeBlock, NodeEquation,
+    NodeFootnoteDefinition, NodeFootnoteReference, NodeHeading, NodeHtmlBlock, NodeLink, NodeList,
+    NodeMath, NodeTable, NodeTableCell, NodeTaskItem, NodeTypographic, NodeValue, NodeWikiLink,
+    Sourcepos, TableAlignment,
 };
-use crate::parser::options::{Options, Plugins};
-use crate::{node_matches, scanners};
+use crate::parser::options::{FootnotePlacement, Options, Plugins};
+use crate::{crossref, node_matches, scanners, sections};
 
 #[doc(hidden)]
 pub use anchorizer::Anchorizer;
 pub use context::Context;
+pub use deferred::{Deferred, DeferredJob, DeferredKind};
+use footnotes::FootnotePlacer;
+pub use pages::{Page, Pages, split_document};
+pub use toc::{TocEntry, toc, toc_with_plugins};
 
 /// Formats an AST as HTML, modified by the given options.
 pub fn format_document(root: Node<'_>, options: &Options, output: &mut dyn Write) -> fmt::Result {
@@ -277,6 +286,19 @@ pub fn format_document_with_formatter<'a, 'o, 'c: 'o, T>(
         entering: bool,
     ) -> Result<ChildRendering, fmt::Error>,
     user: T,
+) -> Result<T, fmt::Error> {
+    let context = Context::new(output, root, options, plugins, user);
+    format_with_context(context, root, formatter)
+}
+
+fn format_with_context<'a, T>(
+    mut context: Context<T>,
+    root: Node<'a>,
+    formatter: fn(
+        context: &mut Context<T>,
+        node: Node<'a>,
+        entering: bool,
+    ) -> Result<ChildRendering, fmt::Error>,
 ) -> Result<T, fmt::Error> {
     // Traverse the AST iteratively using a work stack, with pre- and
     // post-child-traversal phases. During pre-order traversal render the
@@ -284,17 +306,40 @@ pub fn format_document_with_formatter<'a, 'o, 'c: 'o, T>(
     // post-order traversal phase, then push the children in reverse order
     // onto the stack and begin rendering first child.
 
-    let mut context = Context::new(output, options, plugins, user);
+    // Footnote definitions placed elsewhere than where they appear in the
+    // document are pushed onto the stack out of order, followed by a
+    // CloseFootnotes marker when they are rendered in a section.
 
     enum Phase {
         Pre,
         Post,
+        CloseFootnotes,
     }
     let mut stack = vec![(root, ChildRendering::HTML, Phase::Pre)];
+    let mut footnotes = FootnotePlacer::new(root, context.options);
 
     while let Some((node, child_rendering, phase)) = stack.pop() {
         match phase {
             Phase::Pre => {
+                if matches!(child_rendering, ChildRendering::HTML) {
+                    let definitions = footnotes.flush_before(node);
+                    if !definitions.is_empty() {
+                        stack.push((node, child_rendering, Phase::Pre));
+                        stack.push((node, child_rendering, Phase::CloseFootnotes));
+                        for definition in definitions.into_iter().rev() {
+                            stack.push((definition, ChildRendering::HTML, Phase::Pre));
+                        }
+                        continue;
+                    }
+
+                    if matches!(node.data().value, NodeValue::FootnoteDefinition(..))
+                        && !footnotes.place(node)
+                    {
+                        continue;
+                    }
+                    footnotes.visit(node);
+                }
+
                 let new_cr = match child_rendering {
                     ChildRendering::Plain => {
                         match node.data().value {
@@ -311,6 +356,9 @@ pub fn format_document_with_formatter<'a, 'o, 'c: 'o, T>(
                             NodeValue::Math(NodeMath { ref literal, .. }) => {
                                 context.escape(literal)?;
                             }
+                            NodeValue::Typographic(ref nt) => {
+                                context.escape(nt.text())?;
+                            }
                             _ => (),
                         }
                         ChildRendering::Plain
@@ -334,6 +382,13 @@ pub fn format_document_with_formatter<'a, 'o, 'c: 'o, T>(
             Phase::Post => {
                 debug_assert!(matches!(child_rendering, ChildRendering::HTML));
                 formatter(&mut context, node, false)?;
+
+                for definition in footnotes.flush_after(node).into_iter().rev() {
+                    stack.push((definition, ChildRendering::HTML, Phase::Pre));
+                }
+            }
+            Phase::CloseFootnotes => {
+                context.close_footnote_section()?;
             }
         }
     }
@@ -385,8 +440,9 @@ pub fn format_node_default<T>(
         NodeValue::Strikethrough => render_strikethrough(context, node, entering),
         NodeValue::Highlight => render_highlight(context, node, entering),
         NodeValue::Insert => render_insert(context, node, entering),
-        NodeValue::Table(_) => render_table(context, node, entering),
-        NodeValue::TableCell => render_table_cell(context, node, entering),
+        NodeValue::Table(ref nt) => render_table(context, node, entering, nt),
+        NodeValue::TableCell(ref ntc) => render_table_cell(context, node, entering, ntc),
+        NodeValue::TableCaption => render_table_caption(context, node, entering),
         NodeValue::TableRow(thead) => render_table_row(context, node, entering, thead),
         NodeValue::TaskItem(ref nti) => render_task_item(context, node, entering, nti),
 
@@ -411,6 +467,13 @@ pub fn format_node_default<T>(
         NodeValue::WikiLink(ref nwl) => render_wiki_link(context, node, entering, nwl),
         NodeValue::Subtext => render_subtext(context, node, entering),
         NodeValue::BlockDirective(ref nbd) => render_block_directive(context, node, entering, nbd),
+        NodeValue::TableOfContents => render_table_of_contents(context, node, entering),
+        NodeValue::Typographic(ref nt) => render_typographic(context, node, entering, nt),
+        NodeValue::Equation(ref ne) => render_equation(context, node, entering, ne),
+        NodeValue::Figure(_) => Ok(ChildRendering::HTML),
+        NodeValue::Include(_) => Ok(ChildRendering::HTML),
+        // Rendered as the ID of the enclosing paragraph or list item.
+        NodeValue::BlockAnchor(_) => Ok(ChildRendering::HTML),
     }
 }
 
@@ -431,6 +494,15 @@ pub fn render_sourcepos<T>(context: &mut Context<T>, node: Node<'_>) -> fmt::Res
     Ok(())
 }
 
+/// The sourcepos of a node to pass to adapters, if enabled.
+fn sourcepos<T>(context: &Context<T>, node: Node<'_>) -> Option<Sourcepos> {
+    if context.options.render.sourcepos {
+        Some(node.data().sourcepos)
+    } else {
+        None
+    }
+}
+
 fn render_block_directive<T>(
     context: &mut Context<T>,
     node: Node<'_>,
@@ -496,7 +568,18 @@ fn render_code_block<T>(
     ncb: &NodeCodeBlock,
 ) -> Result<ChildRendering, fmt::Error> {
     if entering {
-        let info = &ncb.info;
+        let mut info = ncb.info.as_str();
+        let mut listing_label = None;
+        if (context.options.extension.cross_references || context.options.extension.math_labels)
+            && ncb.fenced
+        {
+            if let Some((rest, label)) = crossref::listing_label(info) {
+                if context.options.extension.cross_references || crossref::is_math_info(rest) {
+                    info = rest;
+                    listing_label = Some(label);
+                }
+            }
+        }
         let info_bytes = info.as_bytes();
         let mut first_tag = 0;
 
@@ -505,10 +588,21 @@ fn render_code_block<T>(
         }
 
         let lang = &info[..first_tag];
+        let aliases = &context.plugins.render.codefence_language_aliases;
+        let lang = match aliases.get(lang) {
+            Some(name) => name.as_str(),
+            None if aliases.is_empty() => lang,
+            None => aliases
+                .get(&lang.to_lowercase())
+                .map_or(lang, String::as_str),
+        };
         let meta = info[first_tag..].trim();
 
         if lang.eq("math") {
-            render_math_code_block(context, node, &ncb.literal)?;
+            match listing_label {
+                Some(label) => render_labelled_math_code_block(context, node, label, ncb)?,
+                None => render_math_code_block(context, node, &ncb.literal)?,
+            };
         } else if !lang.is_empty() {
             if let Some(adapter) = context.plugins.render.codefence_renderers.get(lang) {
                 context.cr()?;
@@ -518,7 +612,16 @@ fn render_code_block<T>(
                     None
                 };
 
+                // The adapter has no way to be given the listing's ID.
+                if let Some(label) = listing_label {
+                    write_opening_tag(context, "div", [("id", label)])?;
+                    context.lf()?;
+                }
                 adapter.write(context, lang, meta, &ncb.literal, sourcepos)?;
+                if listing_label.is_some() {
+                    context.write_str("</div>")?;
+                    context.lf()?;
+                }
                 return Ok(ChildRendering::HTML);
             }
         }
@@ -549,6 +652,10 @@ fn render_code_block<T>(
                 }
             }
 
+            if let Some(label) = listing_label {
+                pre_attributes.insert("id", label.into());
+            }
+
             if context.options.render.sourcepos {
                 let ast = node.data();
                 pre_attributes.insert("data-sourcepos", ast.sourcepos.to_string().into());
@@ -565,10 +672,22 @@ fn render_code_block<T>(
                     context.lf()?
                 }
                 Some(highlighter) => {
+                    let fence_meta = FenceMeta::parse(meta);
+                    if let Some(title) = fence_meta.title() {
+                        highlighter.write_title(context, title)?;
+                    }
+
                     highlighter.write_pre_tag(context, pre_attributes)?;
                     highlighter.write_code_tag(context, code_attributes)?;
 
-                    highlighter.write_highlighted(context, Some(lang), &ncb.literal)?;
+                    let sourcepos = sourcepos(context, node);
+                    highlighter.write_highlighted_with_meta(
+                        context,
+                        Some(lang),
+                        &fence_meta,
+                        &ncb.literal,
+                        sourcepos,
+                    )?;
 
                     context.write_str("</code></pre>")?;
                     context.lf()?
@@ -602,6 +721,8 @@ fn render_heading<T>(
     entering: bool,
     nh: &NodeHeading,
 ) -> Result<ChildRendering, fmt::Error> {
+    let number = heading_number(context, node, entering, nh.level);
+
     match context.plugins.render.heading_adapter {
         None => {
             if entering {
@@ -610,19 +731,30 @@ fn render_heading<T>(
                 render_sourcepos(context, node)?;
                 context.write_str(">")?;
 
-                if let Some(prefix) = context.options.extension.effective_header_id_prefix() {
+                if let Some(prefix) = heading_id_prefix(context, nh) {
                     let text_content = collect_text(node);
-                    let id = context.anchorizer.anchorize(&text_content);
+                    let id =
+                        context
+                            .anchorizer
+                            .anchorize_heading(nh, &text_content, context.plugins);
                     let href_prefix = if context.options.extension.header_id_prefix_in_href {
-                        prefix.as_str()
+                        prefix
                     } else {
                         ""
                     };
+                    write!(context, "<a href=\"#{}", href_prefix)?;
+                    context.escape(&id)?;
                     write!(
                         context,
-                        "<a href=\"#{}{}\" aria-hidden=\"true\" class=\"anchor\" id=\"{}{}\"></a>",
-                        href_prefix, id, prefix, id
+                        "\" aria-hidden=\"true\" class=\"anchor\" id=\"{}",
+                        prefix
                     )?;
+                    context.escape(&id)?;
+                    context.write_str("\"></a>")?;
+                }
+
+                if let Some(number) = number {
+                    write!(context, "<span class=\"heading-number\">{}</span> ", number)?;
                 }
             } else {
                 write!(context, "</h{}>", nh.level)?;
@@ -631,18 +763,29 @@ fn render_heading<T>(
         }
         Some(adapter) => {
             let text_content = collect_text(node);
+            let id = if entering {
+                let id = heading_id_prefix(context, nh).map(|prefix| {
+                    let id =
+                        context
+                            .anchorizer
+                            .anchorize_heading(nh, &text_content, context.plugins);
+                    format!("{}{}", prefix, id)
+                });
+                context.heading_id.clone_from(&id);
+                id
+            } else {
+                context.heading_id.take()
+            };
             let heading = HeadingMeta {
                 level: nh.level,
                 content: text_content,
+                number,
+                id,
             };
 
             if entering {
                 context.cr()?;
-                let sp = if context.options.render.sourcepos {
-                    Some(node.data().sourcepos)
-                } else {
-                    None
-                };
+                let sp = sourcepos(context, node);
                 adapter.enter(context, &heading, sp)?;
             } else {
                 adapter.exit(context, &heading)?;
@@ -653,6 +796,37 @@ fn render_heading<T>(
     Ok(ChildRendering::HTML)
 }
 
+/// Returns the prefix of a heading's ID, if it's to have one: when header IDs
+/// are enabled, it has an explicit ID, or the document has a table of
+/// contents.
+fn heading_id_prefix<'o, T>(context: &Context<'o, '_, T>, nh: &NodeHeading) -> Option<&'o str> {
+    match context.options.extension.effective_header_id_prefix() {
+        Some(prefix) => Some(prefix.as_str()),
+        None if nh.id.is_some() || context.heading_ids => Some(""),
+        None => None,
+    }
+}
+
+/// Returns the section number of a heading, if it's to be rendered.  The
+/// numbering advances on entering each section heading.
+fn heading_number<T>(
+    context: &mut Context<T>,
+    node: Node<'_>,
+    entering: bool,
+    level: u8,
+) -> Option<String> {
+    if !context.options.render.heading_numbers || !sections::is_section_heading(node) {
+        return None;
+    }
+
+    let number = if entering {
+        context.section_numberer.next(level)
+    } else {
+        context.section_numberer.current()
+    };
+    sections::format_section_number(&number)
+}
+
 fn render_html_block<T>(
     context: &mut Context<T>,
     entering: bool,
@@ -733,12 +907,30 @@ fn render_image<T>(
     entering: bool,
     nl: &NodeLink,
 ) -> Result<ChildRendering, fmt::Error> {
+    let figure = node.parent().and_then(|parent| match parent.data().value {
+        NodeValue::Figure(ref nf) => Some((nf.label.clone(), nf.number)),
+        _ => None,
+    });
+
     if entering {
         if context.options.render.figure_with_caption {
-            context.write_str("<figure>")?;
+            context.write_str("<figure")?;
+            if let Some((ref label, _)) = figure {
+                context.write_str(" id=\"")?;
+                context.escape(label)?;
+                context.write_str("\"")?;
+            }
+            context.write_str(">")?;
         }
         context.write_str("<img")?;
         render_sourcepos(context, node)?;
+        if let Some((ref label, _)) = figure {
+            if !context.options.render.figure_with_caption {
+                context.write_str(" id=\"")?;
+                context.escape(label)?;
+                context.write_str("\"")?;
+            }
+        }
         context.write_str(" src=\"")?;
         let url = &nl.url;
         if context.options.render.r#unsafe || !dangerous_url(url) {
@@ -757,7 +949,21 @@ fn render_image<T>(
         }
         context.write_str("\" />")?;
         if context.options.render.figure_with_caption {
-            if !nl.title.is_empty() {
+            if let Some((_, number)) = figure {
+                context.write_str("<figcaption>")?;
+                let prefix = &context.options.extension.cross_reference_prefixes.figure;
+                if prefix.is_empty() {
+                    write!(context, "{}", number)?;
+                } else {
+                    context.escape(prefix)?;
+                    write!(context, " {}", number)?;
+                }
+                if !nl.title.is_empty() {
+                    context.write_str(": ")?;
+                    context.escape(&nl.title)?;
+                }
+                context.write_str("</figcaption>")?;
+            } else if !nl.title.is_empty() {
                 context.write_str("<figcaption>")?;
                 context.escape(&nl.title)?;
                 context.write_str("</figcaption>")?;
@@ -778,6 +984,9 @@ fn render_item<T>(
         context.cr()?;
         context.write_str("<li")?;
         render_sourcepos(context, node)?;
+        if let Some(paragraph) = node.first_child() {
+            render_block_anchor(context, paragraph)?;
+        }
         context.write_str(">")?;
     } else {
         context.write_str("</li>")?;
@@ -897,18 +1106,30 @@ fn render_paragraph<T>(
         })
         || node
             .parent()
-            .is_some_and(|n| node_matches!(n, NodeValue::DescriptionTerm));
+            .is_some_and(|n| node_matches!(n, NodeValue::DescriptionTerm))
+        // A grid table cell holding a single paragraph renders like any other
+        // table cell.
+        || (node
+            .parent()
+            .is_some_and(|n| node_matches!(n, NodeValue::TableCell(..)))
+            && node.previous_sibling().is_none()
+            && node.next_sibling().is_none());
 
     if !tight {
         if entering {
             context.cr()?;
             context.write_str("<p")?;
             render_sourcepos(context, node)?;
+            if !begins_item(node) {
+                render_block_anchor(context, node)?;
+            }
             context.write_str(">")?;
         } else {
             if let Some(parent) = node.parent() {
                 if let NodeValue::FootnoteDefinition(ref nfd) = parent.data().value {
-                    if node.next_sibling().is_none() {
+                    if node.next_sibling().is_none()
+                        && context.plugins.render.footnote_adapter.is_none()
+                    {
                         context.write_str(" ")?;
                         put_footnote_backref(context, nfd)?;
                     }
@@ -999,26 +1220,84 @@ fn render_footnote_definition<T>(
     entering: bool,
     nfd: &NodeFootnoteDefinition,
 ) -> Result<ChildRendering, fmt::Error> {
+    let placement = context.options.render.footnote_placement;
+
+    if let Some(adapter) = context.plugins.render.footnote_adapter {
+        if entering {
+            if placement != FootnotePlacement::Inline && !context.footnote_section_open {
+                context.footnote_section_open = true;
+                let start = context.footnote_ix + 1;
+                context.cr()?;
+                let sp = sourcepos(context, node);
+                adapter.enter_section(context, start, sp)?;
+            }
+            context.footnote_ix += 1;
+        }
+        let footnote = FootnoteMeta {
+            name: nfd.name.clone(),
+            ix: context.footnote_ix,
+            id: footnote_id(context, "fn-", &nfd.name),
+            reference_hrefs: (1..=nfd.total_references)
+                .map(|ref_num| fragment_href(context, "fnref-", &reference_name(nfd, ref_num)))
+                .collect(),
+            placement,
+        };
+        if entering {
+            context.cr()?;
+            let sp = sourcepos(context, node);
+            adapter.enter_definition(context, &footnote, sp)?;
+        } else {
+            context.cr()?;
+            adapter.exit_definition(context, &footnote)?;
+        }
+        return Ok(ChildRendering::HTML);
+    }
+
+    let tag = if placement == FootnotePlacement::Inline {
+        "aside"
+    } else {
+        "li"
+    };
+
     if entering {
-        if context.footnote_ix == 0 {
+        if placement != FootnotePlacement::Inline && !context.footnote_section_open {
+            context.footnote_section_open = true;
+            context.cr()?;
             context.write_str("<section")?;
             render_sourcepos(context, node)?;
-            context.write_str(" class=\"footnotes\" data-footnotes>")?;
+            context.write_str(" class=\"")?;
+            let class = context
+                .options
+                .render
+                .footnote_section_class
+                .as_deref()
+                .unwrap_or("footnotes");
+            context.escape(class)?;
+            context.write_str("\" data-footnotes>")?;
             context.lf()?;
-            context.write_str("<ol>")?;
+            if context.footnote_ix > 0 {
+                write!(context, "<ol start=\"{}\">", context.footnote_ix + 1)?;
+            } else {
+                context.write_str("<ol>")?;
+            }
             context.lf()?;
         }
         context.footnote_ix += 1;
-        context.write_str("<li")?;
+        context.cr()?;
+        write!(context, "<{}", tag)?;
         render_sourcepos(context, node)?;
-        context.write_str(" id=\"fn-")?;
-        context.escape_href(&nfd.name)?;
+        if placement == FootnotePlacement::Inline {
+            context.write_str(" class=\"footnote\"")?;
+        }
+        context.write_str(" id=\"")?;
+        let id = footnote_id(context, "fn-", &nfd.name);
+        context.escape_href(&id)?;
         context.write_str("\">")?;
     } else {
         if put_footnote_backref(context, nfd)? {
             context.lf()?;
         }
-        context.write_str("</li>")?;
+        write!(context, "</{}>", tag)?;
         context.lf()?;
     }
 
@@ -1032,15 +1311,30 @@ fn render_footnote_reference<T>(
     nfr: &NodeFootnoteReference,
 ) -> Result<ChildRendering, fmt::Error> {
     if entering {
-        let mut ref_id = format!("fnref-{}", nfr.name);
+        let mut ref_name = nfr.name.clone();
         if nfr.ref_num > 1 {
-            ref_id = format!("{}-{}", ref_id, nfr.ref_num);
+            ref_name = format!("{}-{}", ref_name, nfr.ref_num);
+        }
+        let ref_id = footnote_id(context, "fnref-", &ref_name);
+
+        if let Some(adapter) = context.plugins.render.footnote_adapter {
+            let reference = FootnoteReferenceMeta {
+                name: nfr.name.clone(),
+                ix: nfr.ix,
+                ref_num: nfr.ref_num,
+                id: ref_id,
+                href: fragment_href(context, "fn-", &nfr.name),
+            };
+            let sp = sourcepos(context, node);
+            return adapter
+                .write_reference(context, &reference, sp)
+                .map(|_| ChildRendering::HTML);
         }
 
         context.write_str("<sup")?;
         render_sourcepos(context, node)?;
-        context.write_str(" class=\"footnote-ref\"><a href=\"#fn-")?;
-        context.escape_href(&nfr.name)?;
+        context.write_str(" class=\"footnote-ref\"><a href=\"")?;
+        write_fragment_href(context, "fn-", &nfr.name)?;
         context.write_str("\" id=\"")?;
         context.escape_href(&ref_id)?;
         write!(context, "\" data-footnote-ref>{}</a></sup>", nfr.ix)?;
@@ -1101,18 +1395,23 @@ fn render_table<T>(
     context: &mut Context<T>,
     node: Node<'_>,
     entering: bool,
+    nt: &NodeTable,
 ) -> Result<ChildRendering, fmt::Error> {
     if entering {
         context.cr()?;
         context.write_str("<table")?;
         render_sourcepos(context, node)?;
+        if let Some(ref id) = nt.id {
+            context.write_str(" id=\"")?;
+            context.escape(id)?;
+            context.write_str("\"")?;
+        }
         context.write_str(">")?;
         context.lf()?;
     } else {
-        if let Some(true) = node
+        if node
             .last_child()
-            .map(|n| !n.same_node(node.first_child().unwrap()))
-        // node.first_child() guaranteed to exist in block since last_child does!
+            .is_some_and(|n| node_matches!(n, NodeValue::TableRow(false)))
         {
             context.cr()?;
             context.write_str("</tbody>")?;
@@ -1126,10 +1425,60 @@ fn render_table<T>(
     Ok(ChildRendering::HTML)
 }
 
+fn render_table_caption<T>(
+    context: &mut Context<T>,
+    node: Node<'_>,
+    entering: bool,
+) -> Result<ChildRendering, fmt::Error> {
+    if entering {
+        context.cr()?;
+        context.write_str("<caption")?;
+        render_sourcepos(context, node)?;
+        context.write_str(">")?;
+
+        let number = node.parent().and_then(|table| match table.data().value {
+            NodeValue::Table(ref nt) => nt.number,
+            _ => None,
+        });
+        if let Some(number) = number {
+            let prefix = &context.options.extension.cross_reference_prefixes.table;
+            if prefix.is_empty() {
+                write!(context, "{}: ", number)?;
+            } else {
+                context.escape(prefix)?;
+                write!(context, " {}: ", number)?;
+            }
+        }
+    } else {
+        context.write_str("</caption>")?;
+        context.lf()?;
+    }
+
+    Ok(ChildRendering::HTML)
+}
+
+/// Writes the `<colgroup>` of a table, when column widths are rendered.
+fn render_table_column_widths<T>(context: &mut Context<T>, nt: &NodeTable) -> fmt::Result {
+    let total: usize = nt.column_widths.iter().sum();
+    if context.options.render.table_column_widths && total > 0 {
+        context.write_str("<colgroup>")?;
+        context.lf()?;
+        for width in &nt.column_widths {
+            let percent = (width * 200 + total) / (total * 2);
+            write!(context, "<col style=\"width: {}%\" />", percent)?;
+            context.lf()?;
+        }
+        context.write_str("</colgroup>")?;
+        context.lf()?;
+    }
+    Ok(())
+}
+
 fn render_table_cell<T>(
     context: &mut Context<T>,
     node: Node<'_>,
     entering: bool,
+    ntc: &NodeTableCell,
 ) -> Result<ChildRendering, fmt::Error> {
     let Some(row_node) = node.parent() else {
         panic!("rendered a table cell without a containing table row");
@@ -1161,14 +1510,7 @@ fn render_table_cell<T>(
             render_sourcepos(context, node)?;
         }
 
-        let mut start = row_node.first_child().unwrap(); // guaranteed to exist because `node' itself does!
-        let mut i = 0;
-        while !start.same_node(node) {
-            i += 1;
-            start = start.next_sibling().unwrap();
-        }
-
-        match alignments[i] {
+        match alignments[ntc.column] {
             TableAlignment::Left => {
                 context.write_str(" align=\"left\"")?;
             }
@@ -1181,6 +1523,13 @@ fn render_table_cell<T>(
             TableAlignment::None => (),
         }
 
+        if ntc.colspan > 1 {
+            write!(context, " colspan=\"{}\"", ntc.colspan)?;
+        }
+        if ntc.rowspan > 1 {
+            write!(context, " rowspan=\"{}\"", ntc.rowspan)?;
+        }
+
         context.write_str(">")?;
     } else if in_header {
         context.write_str("</th>")?;
@@ -1199,14 +1548,24 @@ fn render_table_row<T>(
 ) -> Result<ChildRendering, fmt::Error> {
     if entering {
         context.cr()?;
-        if thead {
+        let previous_thead = node.previous_sibling().and_then(|n| match n.data().value {
+            NodeValue::TableRow(header) => Some(header),
+            _ => None,
+        });
+        if previous_thead.is_none() {
+            // The first row: the column widths follow any caption.
+            if let Some(table) = node.parent() {
+                if let NodeValue::Table(ref nt) = table.data().value {
+                    render_table_column_widths(context, nt)?;
+                }
+            }
+        }
+        if thead && previous_thead != Some(true) {
             context.write_str("<thead>")?;
             context.lf()?;
-        } else if let Some(n) = node.previous_sibling() {
-            if let NodeValue::TableRow(true) = n.data().value {
-                context.write_str("<tbody>")?;
-                context.lf()?;
-            }
+        } else if !thead && previous_thead != Some(false) {
+            context.write_str("<tbody>")?;
+            context.lf()?;
         }
         context.write_str("<tr")?;
         render_sourcepos(context, node)?;
@@ -1214,7 +1573,11 @@ fn render_table_row<T>(
     } else {
         context.cr()?;
         context.write_str("</tr>")?;
-        if thead {
+        if thead
+            && !node
+                .next_sibling()
+                .is_some_and(|n| node_matches!(n, NodeValue::TableRow(true)))
+        {
             context.cr()?;
             context.write_str("</thead>")?;
         }
@@ -1242,6 +1605,9 @@ fn render_task_item<T>(
                 context.write_str(" class=\"task-list-item\"")?;
             }
             render_sourcepos(context, node)?;
+            if let Some(paragraph) = node.first_child() {
+                render_block_anchor(context, paragraph)?;
+            }
             context.write_str(">")?;
         }
         context.write_str("<input type=\"checkbox\"")?;
@@ -1387,6 +1753,15 @@ pub fn render_math<T>(
     nm: &NodeMath,
 ) -> Result<ChildRendering, fmt::Error> {
     if entering {
+        let mut math_error = None;
+        if let Some(adapter) = context.plugins.render.math_renderer_adapter {
+            let sourcepos = sourcepos(context, node);
+            match adapter.write(context, &nm.literal, nm.display_math, sourcepos)? {
+                MathRendering::Written => return Ok(ChildRendering::HTML),
+                MathRendering::Failed(error) => math_error = Some(error),
+            }
+        }
+
         let mut tag_attributes: Vec<(&str, Cow<str>)> = Vec::new();
         let style_attr = if nm.display_math { "display" } else { "inline" };
         let tag: &str = if nm.dollar_math { "span" } else { "code" };
@@ -1398,6 +1773,10 @@ pub fn render_math<T>(
             tag_attributes.push(("data-sourcepos", ast.sourcepos.to_string().into()));
         }
 
+        if let Some(error) = math_error {
+            tag_attributes.push(("data-math-error", error.into()));
+        }
+
         write_opening_tag(context, tag, tag_attributes.into_iter())?;
         context.escape(&nm.literal)?;
         write!(context, "</{tag}>")?;
@@ -1414,6 +1793,18 @@ pub fn render_math_code_block<T>(
 ) -> Result<ChildRendering, fmt::Error> {
     context.cr()?;
 
+    let mut math_error = None;
+    if let Some(adapter) = context.plugins.render.math_renderer_adapter {
+        let sourcepos = sourcepos(context, node);
+        match adapter.write(context, literal, true, sourcepos)? {
+            MathRendering::Written => {
+                context.lf()?;
+                return Ok(ChildRendering::HTML);
+            }
+            MathRendering::Failed(error) => math_error = Some(error),
+        }
+    }
+
     // use vectors to ensure attributes always written in the same order,
     // for testing stability
     let mut pre_attributes: Vec<(&str, Cow<str>)> = Vec::new();
@@ -1434,6 +1825,10 @@ pub fn render_math_code_block<T>(
         pre_attributes.push(("data-sourcepos", ast.sourcepos.to_string().into()));
     }
 
+    if let Some(error) = math_error {
+        pre_attributes.push(("data-math-error", error.into()));
+    }
+
     write_opening_tag(context, "pre", pre_attributes.into_iter())?;
     write_opening_tag(context, "code", code_attributes.into_iter())?;
 
@@ -1444,6 +1839,36 @@ pub fn render_math_code_block<T>(
     Ok(ChildRendering::HTML)
 }
 
+/// Renders a ```` ```math ```` block labelled like an equation per
+/// [`math_labels`](crate::options::Extension::math_labels), numbered as
+/// `$$…$$ {#label}` would be.
+fn render_labelled_math_code_block<T>(
+    context: &mut Context<T>,
+    node: Node<'_>,
+    label: &str,
+    ncb: &NodeCodeBlock,
+) -> Result<ChildRendering, fmt::Error> {
+    context.cr()?;
+    context.write_str("<div class=\"math-equation\" id=\"")?;
+    context.escape(label)?;
+    context.write_str("\">")?;
+    context.lf()?;
+    render_math_code_block(context, node, &ncb.literal)?;
+    let number = context.equation_numbers.get(label).cloned();
+    if let Some(number) = number {
+        write!(
+            context,
+            "<span class=\"math-equation-number\">({})</span>",
+            number
+        )?;
+        context.lf()?;
+    }
+    context.write_str("</div>")?;
+    context.lf()?;
+
+    Ok(ChildRendering::HTML)
+}
+
 fn render_multiline_block_quote<T>(
     context: &mut Context<T>,
     node: Node<'_>,
@@ -1573,12 +1998,164 @@ fn render_underline<T>(
     Ok(ChildRendering::HTML)
 }
 
+fn render_equation<T>(
+    context: &mut Context<T>,
+    node: Node<'_>,
+    entering: bool,
+    ne: &NodeEquation,
+) -> Result<ChildRendering, fmt::Error> {
+    if entering {
+        context.write_str("<span class=\"math-equation\"")?;
+        render_sourcepos(context, node)?;
+        context.write_str(" id=\"")?;
+        context.escape(&ne.label)?;
+        context.write_str("\">")?;
+    } else {
+        write!(
+            context,
+            " <span class=\"math-equation-number\">({})</span></span>",
+            ne.number
+        )?;
+    }
+
+    Ok(ChildRendering::HTML)
+}
+
+fn render_typographic<T>(
+    context: &mut Context<T>,
+    node: Node<'_>,
+    entering: bool,
+    nt: &NodeTypographic,
+) -> Result<ChildRendering, fmt::Error> {
+    if entering {
+        match nt.replacement {
+            Some(replacement) => context.escape(replacement)?,
+            None => {
+                // An ordinal suffix.
+                context.write_str("<sup")?;
+                render_sourcepos(context, node)?;
+                context.write_str(">")?;
+                context.escape(nt.source)?;
+                context.write_str("</sup>")?;
+            }
+        }
+    }
+
+    Ok(ChildRendering::HTML)
+}
+
+fn render_table_of_contents<T>(
+    context: &mut Context<T>,
+    node: Node<'_>,
+    entering: bool,
+) -> Result<ChildRendering, fmt::Error> {
+    if !entering {
+        return Ok(ChildRendering::HTML);
+    }
+
+    let root = node.ancestors().last().unwrap();
+    let entries = toc_with_plugins(root, context.options, context.plugins);
+    if entries.is_empty() {
+        return Ok(ChildRendering::HTML);
+    }
+
+    let href_prefix = match context.options.extension.effective_header_id_prefix() {
+        Some(prefix) if context.options.extension.header_id_prefix_in_href => prefix.clone(),
+        _ => String::new(),
+    };
+
+    context.cr()?;
+    context.write_str("<ul class=\"table-of-contents\"")?;
+    render_sourcepos(context, node)?;
+    context.write_str(">")?;
+    context.lf()?;
+
+    let items = entries.iter().map(|entry| NestedListItem {
+        level: entry.level,
+        href: format!("#{}{}", href_prefix, entry.anchor),
+        number: entry.number.as_deref(),
+        text: &entry.text,
+    });
+    write_nested_list_items(context, items)?;
+    context.write_str("</ul>")?;
+    context.lf()?;
+
+    Ok(ChildRendering::HTML)
+}
+
+struct NestedListItem<'i> {
+    level: u8,
+    href: String,
+    number: Option<&'i str>,
+    text: &'i str,
+}
+
+/// Writes links as list items, nesting them by level, for a table of contents
+/// or page index.  The caller writes the outermost `<ul>` and `</ul>`.
+fn write_nested_list_items<'i, T>(
+    context: &mut Context<T>,
+    items: impl Iterator<Item = NestedListItem<'i>>,
+) -> fmt::Result {
+    // Each level in the stack has an open <ul> and <li>.  A deeper item
+    // opens a nested list; a shallower one closes lists until it finds its
+    // level, regardless of any levels skipped in between.
+    let mut levels: Vec<u8> = vec![];
+    for item in items {
+        match levels.last_mut() {
+            None => levels.push(item.level),
+            Some(top) if item.level > *top => {
+                context.lf()?;
+                context.write_str("<ul>")?;
+                context.lf()?;
+                levels.push(item.level);
+            }
+            Some(_) => {
+                while levels.len() > 1 && item.level <= levels[levels.len() - 2] {
+                    context.write_str("</li>")?;
+                    context.lf()?;
+                    context.write_str("</ul>")?;
+                    context.lf()?;
+                    levels.pop();
+                }
+                *levels.last_mut().unwrap() = item.level;
+                context.write_str("</li>")?;
+                context.lf()?;
+            }
+        }
+
+        context.write_str("<li><a href=\"")?;
+        context.escape_href(&item.href)?;
+        context.write_str("\">")?;
+        if let Some(number) = item.number {
+            write!(context, "<span class=\"heading-number\">{}</span> ", number)?;
+        }
+        context.escape(item.text)?;
+        context.write_str("</a>")?;
+    }
+
+    if levels.is_empty() {
+        return Ok(());
+    }
+    for _ in 1..levels.len() {
+        context.write_str("</li>")?;
+        context.lf()?;
+        context.write_str("</ul>")?;
+        context.lf()?;
+    }
+    context.write_str("</li>")?;
+    context.lf()
+}
+
 fn render_wiki_link<T>(
     context: &mut Context<T>,
     node: Node<'_>,
     entering: bool,
     nwl: &NodeWikiLink,
 ) -> Result<ChildRendering, fmt::Error> {
+    if nwl.is_image_embed() {
+        return render_wiki_link_image(context, node, entering, nwl);
+    }
+
     if entering {
         context.write_str("<a")?;
         render_sourcepos(context, node)?;
@@ -1588,6 +2165,12 @@ fn render_wiki_link<T>(
             context.escape_href(url)?;
         }
         context.write_str("\" data-wikilink=\"true")?;
+        if nwl.embed {
+            context.write_str("\" data-embed=\"true")?;
+        }
+        if nwl.missing {
+            context.write_str("\" class=\"new")?;
+        }
         context.write_str("\">")?;
     } else {
         context.write_str("</a>")?;
@@ -1596,8 +2179,69 @@ fn render_wiki_link<T>(
     Ok(ChildRendering::HTML)
 }
 
+fn render_wiki_link_image<T>(
+    context: &mut Context<T>,
+    node: Node<'_>,
+    entering: bool,
+    nwl: &NodeWikiLink,
+) -> Result<ChildRendering, fmt::Error> {
+    if entering {
+        context.write_str("<img")?;
+        render_sourcepos(context, node)?;
+        context.write_str(" src=\"")?;
+        let url = &nwl.url;
+        if context.options.render.r#unsafe || !dangerous_url(url) {
+            if let Some(rewriter) = &context.options.extension.image_url_rewriter {
+                context.escape_href(&rewriter.to_html(url))?;
+            } else {
+                context.escape_href(url)?;
+            }
+        }
+        context.write_str("\" alt=\"")?;
+        return Ok(ChildRendering::Plain);
+    }
+
+    context.write_str("\"")?;
+    if let Some(width) = nwl.width {
+        write!(context, " width=\"{}\"", width)?;
+    }
+    if let Some(height) = nwl.height {
+        write!(context, " height=\"{}\"", height)?;
+    }
+    context.write_str(" data-wikilink=\"true\"")?;
+    if nwl.missing {
+        context.write_str(" class=\"new\"")?;
+    }
+    context.write_str(" />")?;
+
+    Ok(ChildRendering::HTML)
+}
+
+fn render_block_anchor<T>(context: &mut Context<T>, paragraph: Node<'_>) -> fmt::Result {
+    if !node_matches!(paragraph, NodeValue::Paragraph) {
+        return Ok(());
+    }
+    if let Some(anchor) = paragraph.last_child() {
+        if let NodeValue::BlockAnchor(ref id) = anchor.data().value {
+            context.write_str(" id=\"")?;
+            context.escape(id)?;
+            context.write_str("\"")?;
+        }
+    }
+    Ok(())
+}
+
 // Helpers
 
+/// Whether the node is the first child of a list item, whose block anchor is
+/// the item's.
+fn begins_item(node: Node<'_>) -> bool {
+    node.previous_sibling().is_none()
+        && node
+            .parent()
+            .is_some_and(|p| node_matches!(p, NodeValue::Item(..) | NodeValue::TaskItem(..)))
+}
+
 /// Recurses through a node and all of its children in depth-first (document)
 /// order, returning the concatenated literal contents of text, code and math
 /// blocks. Line breaks and soft breaks are represented as a single whitespace
@@ -1618,6 +2262,7 @@ pub fn collect_text_append(node: Node<'_>, output: &mut String) {
         NodeValue::Code(NodeCode { ref literal, .. }) => output.push_str(literal),
         NodeValue::LineBreak | NodeValue::SoftBreak => output.push(' '),
         NodeValue::Math(NodeMath { ref literal, .. }) => output.push_str(literal),
+        NodeValue::Typographic(ref nt) => output.push_str(nt.text()),
         _ => {
             for n in node.children() {
                 collect_text_append(n, output);
@@ -1630,12 +2275,21 @@ fn put_footnote_backref<T>(
     context: &mut Context<T>,
     nfd: &NodeFootnoteDefinition,
 ) -> Result<bool, fmt::Error> {
-    if context.written_footnote_ix >= context.footnote_ix {
+    if context.written_footnote_ix >= context.footnote_ix
+        || context.plugins.render.footnote_adapter.is_some()
+    {
         return Ok(false);
     }
 
     context.written_footnote_ix = context.footnote_ix;
 
+    let render = &context.options.render;
+    let symbol = render.footnote_backref_symbol.as_deref().unwrap_or("↩");
+    let label = render
+        .footnote_backref_label
+        .as_deref()
+        .unwrap_or("Back to reference");
+
     let mut ref_suffix = String::new();
     let mut superscript = String::new();
 
@@ -1646,17 +2300,62 @@ fn put_footnote_backref<T>(
             write!(context, " ")?;
         }
 
-        context.write_str("<a href=\"#fnref-")?;
-        context.escape_href(&nfd.name)?;
+        context.write_str("<a href=\"")?;
+        write_fragment_href(context, "fnref-", &reference_name(nfd, ref_num))?;
         let fnix = context.footnote_ix;
         write!(
             context,
-            "{ref_suffix}\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"{fnix}{ref_suffix}\" aria-label=\"Back to reference {fnix}{ref_suffix}\">↩{superscript}</a>",
+            "\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"{fnix}{ref_suffix}\" aria-label=\"",
         )?;
+        context.escape(label)?;
+        write!(context, " {fnix}{ref_suffix}\">")?;
+        context.escape(symbol)?;
+        write!(context, "{superscript}</a>")?;
     }
     Ok(true)
 }
 
+/// The name of a footnote's `ref_num`th reference, as used in its fragment ID.
+fn reference_name(nfd: &NodeFootnoteDefinition, ref_num: u32) -> String {
+    if ref_num > 1 {
+        format!("{}-{}", nfd.name, ref_num)
+    } else {
+        nfd.name.clone()
+    }
+}
+
+/// The ID of the fragment `{kind}{name}`, including any
+/// [`footnote_id_prefix`](crate::options::Render#structfield.footnote_id_prefix).
+fn footnote_id<T>(context: &Context<T>, kind: &str, name: &str) -> String {
+    let prefix = context
+        .options
+        .render
+        .footnote_id_prefix
+        .as_deref()
+        .unwrap_or("");
+    format!("{}{}{}", prefix, kind, name)
+}
+
+/// The `href` of the fragment `{kind}{name}`, which is on the page being
+/// rendered unless [`split_document`] has placed it on another page.
+fn fragment_href<T>(context: &Context<T>, kind: &str, name: &str) -> String {
+    let mut href = String::new();
+    if !context.external_fragments.is_empty() {
+        let fragment = format!("{}{}", kind, name);
+        if let Some(filename) = context.external_fragments.get(&fragment) {
+            href.push_str(filename);
+        }
+    }
+    href.push('#');
+    href.push_str(&footnote_id(context, kind, name));
+    href
+}
+
+fn write_fragment_href<T>(context: &mut Context<T>, kind: &str, name: &str) -> fmt::Result {
+    let href = fragment_href(context, kind, name);
+    context.escape_href(&href)
+}
+
 fn tagfilter(literal: &str) -> bool {
     let bytes = literal.as_bytes();
 
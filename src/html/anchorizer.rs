@@ -2,6 +2,20 @@ use finl_unicode::categories::CharacterCategories;
 use std::borrow::Cow;
 use std::collections::HashSet;
 
+use crate::adapters::AnchorizerAdapter;
+use crate::nodes::{Node, NodeHeading, NodeValue};
+use crate::options::{Options, Plugins};
+
+/// Whether the default anchorizer keeps `c`, mapping a space to `-`.
+pub(super) fn is_permitted_char(&c: &char) -> bool {
+    c == ' '
+        || c == '-'
+        || c.is_letter()
+        || c.is_mark()
+        || c.is_number()
+        || c.is_punctuation_connector()
+}
+
 /// Converts header strings to canonical, unique, but still human-readable,
 /// anchors.
 ///
@@ -40,15 +54,6 @@ impl Anchorizer {
     /// assert_eq!("ticks-arent-in", anchorizer.anchorize(source));
     /// ```
     pub fn anchorize(&mut self, header: &str) -> String {
-        fn is_permitted_char(&c: &char) -> bool {
-            c == ' '
-                || c == '-'
-                || c.is_letter()
-                || c.is_mark()
-                || c.is_number()
-                || c.is_punctuation_connector()
-        }
-
         let mut id = header.to_lowercase();
         id = id
             .chars()
@@ -56,12 +61,95 @@ impl Anchorizer {
             .map(|c| if c == ' ' { '-' } else { c })
             .collect();
 
+        self.make_unique(id, |id, uniq| format!("{}-{}", id, uniq))
+    }
+
+    /// Returns a String that has been converted into an anchor by the given
+    /// [`AnchorizerAdapter`], suffixed by it as needed to make the resultant
+    /// anchor unique.
+    ///
+    /// ```rust
+    /// # use comrak::{Anchorizer, adapters::AnchorizerAdapter};
+    /// struct Underscores;
+    /// impl AnchorizerAdapter for Underscores {
+    ///     fn slugify(&self, header: &str) -> String {
+    ///         header.to_lowercase().replace(' ', "_")
+    ///     }
+    ///
+    ///     fn unique(&self, slug: &str, n: usize) -> String {
+    ///         format!("{}_{}", slug, n)
+    ///     }
+    /// }
+    ///
+    /// let mut anchorizer = Anchorizer::new();
+    /// assert_eq!("hello_world", anchorizer.anchorize_with("Hello World", &Underscores));
+    /// assert_eq!("hello_world_1", anchorizer.anchorize_with("Hello World", &Underscores));
+    /// ```
+    pub fn anchorize_with(&mut self, header: &str, adapter: &dyn AnchorizerAdapter) -> String {
+        let id = adapter.slugify(header);
+        self.make_unique(id, |id, uniq| adapter.unique(id, uniq))
+    }
+
+    /// Marks an anchor as in use, so that it won't be returned by later calls
+    /// to [`anchorize`](Self::anchorize) or [`anchorize_with`](Self::anchorize_with).
+    ///
+    /// Returns whether the anchor was not already in use.
+    ///
+    /// ```rust
+    /// # use comrak::Anchorizer;
+    /// let mut anchorizer = Anchorizer::new();
+    /// assert!(anchorizer.reserve("content"));
+    /// assert_eq!("content-1", anchorizer.anchorize("Content"));
+    /// ```
+    pub fn reserve(&mut self, anchor: &str) -> bool {
+        self.0.insert(anchor.to_string())
+    }
+
+    /// Construct an anchorizer for rendering the given document, with the
+    /// reserved and explicit heading IDs already marked as in use.
+    pub(crate) fn for_document(root: Node<'_>, options: &Options) -> Self {
+        let mut anchorizer = Anchorizer::new();
+        for id in &options.extension.header_reserved_ids {
+            anchorizer.reserve(id);
+        }
+        if options.extension.header_custom_ids {
+            for node in root.descendants() {
+                if let NodeValue::Heading(NodeHeading {
+                    id: Some(ref id), ..
+                }) = node.data().value
+                {
+                    anchorizer.reserve(id);
+                }
+            }
+        }
+        anchorizer
+    }
+
+    /// Returns the anchor for a heading with the given flattened content:
+    /// its explicit ID if it has one, otherwise one generated with the
+    /// configured [`AnchorizerAdapter`], if any.
+    pub(crate) fn anchorize_heading(
+        &mut self,
+        nh: &NodeHeading,
+        content: &str,
+        plugins: &Plugins,
+    ) -> String {
+        if let Some(ref id) = nh.id {
+            return id.clone();
+        }
+        match plugins.render.anchorizer_adapter {
+            Some(adapter) => self.anchorize_with(content, adapter),
+            None => self.anchorize(content),
+        }
+    }
+
+    fn make_unique(&mut self, id: String, suffixed: impl Fn(&str, usize) -> String) -> String {
         let mut uniq = 0;
-        id = loop {
+        let id = loop {
             let anchor = if uniq == 0 {
-                Cow::from(&id)
+                Cow::from(id.as_str())
             } else {
-                Cow::from(format!("{}-{}", id, uniq))
+                Cow::from(suffixed(&id, uniq))
             };
 
             if !self.0.contains(&*anchor) {
@@ -1,7 +1,11 @@
+use crate::crossref::{CrossReferences, TargetKind};
 use crate::html::{self, Anchorizer};
+use crate::nodes::{Node, NodeValue};
+use crate::sections::SectionNumberer;
 use crate::{Options, options::Plugins};
 
 use std::cell::Cell;
+use std::collections::HashMap;
 use std::fmt::{self, Write};
 
 /// Context struct given to formatter functions as taken by
@@ -22,11 +26,27 @@ pub struct Context<'o, 'c, T = ()> {
 
     pub(super) footnote_ix: u32,
     pub(super) written_footnote_ix: u32,
+    pub(super) footnote_section_open: bool,
+    /// Fragment IDs which are on other pages, mapped to their page's file name.
+    pub(super) external_fragments: HashMap<String, String>,
+    pub(super) section_numberer: SectionNumberer,
+    /// Whether headings get IDs without a prefix configured, for the
+    /// document's table of contents to link to.
+    pub(super) heading_ids: bool,
+    /// The ID of the heading being rendered by a [`HeadingAdapter`], for its
+    /// exit.
+    ///
+    /// [`HeadingAdapter`]: crate::adapters::HeadingAdapter
+    pub(super) heading_id: Option<String>,
+    /// The numbers of labelled equations, by label, for the ```` ```math ````
+    /// blocks among them, which have nowhere to keep their own.
+    pub(super) equation_numbers: HashMap<String, String>,
 }
 
 impl<'o, 'c, T> Context<'o, 'c, T> {
     pub(super) fn new(
         output: &'o mut dyn Write,
+        root: Node<'_>,
         options: &'o Options<'c>,
         plugins: &'o Plugins<'o>,
         user: T,
@@ -36,23 +56,54 @@ impl<'o, 'c, T> Context<'o, 'c, T> {
             last_was_lf: Cell::new(true),
             options,
             plugins,
-            anchorizer: Anchorizer::new(),
+            anchorizer: Anchorizer::for_document(root, options),
             user,
             footnote_ix: 0,
             written_footnote_ix: 0,
+            footnote_section_open: false,
+            external_fragments: HashMap::new(),
+            section_numberer: SectionNumberer::default(),
+            heading_ids: options.extension.table_of_contents
+                && root
+                    .descendants()
+                    .any(|n| matches!(n.data().value, NodeValue::TableOfContents)),
+            heading_id: None,
+            equation_numbers: if options.extension.math_labels || options.extension.cross_references
+            {
+                CrossReferences::collect(root)
+                    .targets
+                    .into_iter()
+                    .filter(|(_, target)| target.kind == TargetKind::Equation)
+                    .map(|(label, target)| (label, target.number))
+                    .collect()
+            } else {
+                HashMap::new()
+            },
         }
     }
 
     pub(super) fn finish(mut self) -> Result<T, fmt::Error> {
-        if self.footnote_ix > 0 {
-            self.write_str("</ol>")?;
-            self.lf()?;
-            self.write_str("</section>")?;
-            self.lf()?;
-        }
+        self.close_footnote_section()?;
         Ok(self.user)
     }
 
+    /// Closes the section of footnote definitions, if one is open.
+    pub(super) fn close_footnote_section(&mut self) -> fmt::Result {
+        if !self.footnote_section_open {
+            return Ok(());
+        }
+        self.footnote_section_open = false;
+
+        if let Some(adapter) = self.plugins.render.footnote_adapter {
+            self.cr()?;
+            return adapter.exit_section(self);
+        }
+        self.write_str("</ol>")?;
+        self.lf()?;
+        self.write_str("</section>")?;
+        self.lf()
+    }
+
     /// If the last byte written to ts [`Write`] interface was **not** a U+000A
     /// LINE FEED, writes one. Otherwise, does nothing.
     ///
@@ -0,0 +1,388 @@
+//! Deferred rendering of code blocks and headings, for plugins too slow to
+//! run in the middle of formatting.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::fmt::{self, Write};
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+
+use crate::adapters::{
+    CodefenceRendererAdapter, FenceMeta, HeadingAdapter, HeadingMeta, SyntaxHighlighterAdapter,
+};
+use crate::html;
+use crate::nodes::Sourcepos;
+use crate::parser::options::RenderPlugins;
+
+const PLACEHOLDER_PREFIX: &str = "<!--comrak-deferred-";
+const PLACEHOLDER_SUFFIX: &str = "-->";
+
+/// An adapter which writes placeholders in place of the output of other
+/// adapters, and records what each was asked to render as a
+/// [`DeferredJob`].
+///
+/// Render a document with it standing in for any of the
+/// [`codefence_renderers`](RenderPlugins::codefence_renderers), the
+/// [`codefence_syntax_highlighter`](RenderPlugins::codefence_syntax_highlighter)
+/// and the [`heading_adapter`](RenderPlugins::heading_adapter).  Then
+/// [`take_jobs`](Self::take_jobs), render each however suits, such as in
+/// parallel, through a cache, or with [`DeferredJob::render`], and put the
+/// results in place with [`substitute`](Self::substitute).
+///
+/// Each placeholder carries a nonce random to the adapter, so that raw HTML
+/// in the document can't forge one.
+///
+/// ```rust
+/// # use comrak::{Options, options::Plugins, markdown_to_html_with_plugins};
+/// # use comrak::html::Deferred;
+/// # use comrak::adapters::SyntaxHighlighterAdapter;
+/// # use std::borrow::Cow;
+/// # use std::collections::HashMap;
+/// # use std::fmt;
+/// struct Shouting;
+/// impl SyntaxHighlighterAdapter for Shouting {
+///     fn write_highlighted(&self, output: &mut dyn fmt::Write, _lang: Option<&str>, code: &str) -> fmt::Result {
+///         output.write_str(&code.to_uppercase())
+///     }
+///     fn write_pre_tag(&self, output: &mut dyn fmt::Write, _: HashMap<&'static str, Cow<'_, str>>) -> fmt::Result {
+///         output.write_str("<pre>")
+///     }
+///     fn write_code_tag(&self, output: &mut dyn fmt::Write, _: HashMap<&'static str, Cow<'_, str>>) -> fmt::Result {
+///         output.write_str("<code>")
+///     }
+/// }
+///
+/// let deferred = Deferred::new();
+/// let mut plugins = Plugins::default();
+/// plugins.render.codefence_syntax_highlighter = Some(&deferred);
+/// let html = markdown_to_html_with_plugins("```\nhi\n```\n", &Options::default(), &plugins);
+/// let jobs = deferred.take_jobs();
+/// assert_eq!(html, format!("<pre><code>{}</code></pre>\n", jobs[0].placeholder()));
+///
+/// let mut plugins = Plugins::default();
+/// plugins.render.codefence_syntax_highlighter = Some(&Shouting);
+/// let outputs: Vec<String> = jobs
+///     .iter()
+///     .map(|job| job.render(&plugins.render).unwrap())
+///     .collect();
+/// assert_eq!(deferred.substitute(&html, &outputs), "<pre><code>HI\n</code></pre>\n");
+/// ```
+pub struct Deferred<'h> {
+    jobs: Mutex<Vec<DeferredJob>>,
+    highlighter: Option<&'h dyn SyntaxHighlighterAdapter>,
+    nonce: u64,
+}
+
+/// A call to an adapter recorded by [`Deferred`], in place of which its
+/// [`placeholder`](Self::placeholder) was written.
+#[derive(Debug, Clone)]
+pub struct DeferredJob {
+    /// The number of the job, counting from 0 in document order.
+    pub id: usize,
+
+    /// What was to be rendered.
+    pub kind: DeferredKind,
+
+    /// The source position of the node rendered, when
+    /// [`sourcepos`](crate::options::Render#structfield.sourcepos) is enabled.
+    pub sourcepos: Option<Sourcepos>,
+
+    nonce: u64,
+}
+
+/// What a [`DeferredJob`] was to render.
+#[derive(Debug, Clone)]
+pub enum DeferredKind {
+    /// A code block, per [`CodefenceRendererAdapter::write`].
+    CodeBlock {
+        /// The language of the block.
+        lang: String,
+        /// The rest of the info string.
+        meta: String,
+        /// The content of the block.
+        code: String,
+    },
+
+    /// The content of a code block, per
+    /// [`SyntaxHighlighterAdapter::write_highlighted_with_meta`].
+    Highlight {
+        /// The language of the block, if any.
+        lang: Option<String>,
+        /// The rest of the info string, parsed.
+        meta: FenceMeta,
+        /// The content of the block.
+        code: String,
+    },
+
+    /// The opening of a heading, per [`HeadingAdapter::enter`].
+    HeadingEnter(HeadingMeta),
+
+    /// The closing of a heading, per [`HeadingAdapter::exit`].
+    HeadingExit(HeadingMeta),
+}
+
+impl Default for Deferred<'_> {
+    fn default() -> Self {
+        Deferred {
+            jobs: Mutex::default(),
+            highlighter: None,
+            nonce: RandomState::new().build_hasher().finish(),
+        }
+    }
+}
+
+impl<'h> Deferred<'h> {
+    /// Constructs an adapter with no jobs recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the `<pre>` and `<code>` tags and titles of highlighted code
+    /// blocks with `highlighter`, leaving only the highlighting deferred.
+    /// Otherwise they are written as Comrak writes them without a
+    /// highlighter.
+    pub fn with_highlighter(mut self, highlighter: &'h dyn SyntaxHighlighterAdapter) -> Self {
+        self.highlighter = Some(highlighter);
+        self
+    }
+
+    /// Returns the jobs recorded, in document order, and forgets them, so
+    /// that the next document's jobs are numbered from 0 again.
+    pub fn take_jobs(&self) -> Vec<DeferredJob> {
+        std::mem::take(&mut *self.jobs.lock().unwrap())
+    }
+
+    /// Replaces the placeholders in `html` with the output of each job, as
+    /// indexed by its [`id`](DeferredJob::id).  Placeholders of jobs without
+    /// outputs are left as they are.
+    ///
+    /// Comrak starts a line after a placeholder as it would after any tag,
+    /// so an output ending in a newline takes the place of the one that
+    /// follows its placeholder.
+    pub fn substitute<S: AsRef<str>>(&self, html: &str, outputs: &[S]) -> String {
+        let prefix = placeholder_prefix(self.nonce);
+        let mut result = String::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(start) = rest.find(&prefix) {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let output = rest[prefix.len()..]
+                .split_once(PLACEHOLDER_SUFFIX)
+                .and_then(|(id, _)| Some((id.len(), outputs.get(id.parse::<usize>().ok()?)?)));
+            match output {
+                Some((id_len, output)) => {
+                    let output = output.as_ref();
+                    result.push_str(output);
+                    rest = &rest[prefix.len() + id_len + PLACEHOLDER_SUFFIX.len()..];
+                    if output.ends_with('\n') && rest.starts_with('\n') {
+                        rest = &rest[1..];
+                    }
+                }
+                None => {
+                    result.push_str(&prefix);
+                    rest = &rest[prefix.len()..];
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
+    fn defer(
+        &self,
+        output: &mut dyn Write,
+        kind: DeferredKind,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = DeferredJob {
+            id: jobs.len(),
+            kind,
+            sourcepos,
+            nonce: self.nonce,
+        };
+        output.write_str(&job.placeholder())?;
+        jobs.push(job);
+        Ok(())
+    }
+}
+
+fn placeholder_prefix(nonce: u64) -> String {
+    format!("{}{:016x}:", PLACEHOLDER_PREFIX, nonce)
+}
+
+impl fmt::Debug for Deferred<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deferred")
+            .field("jobs", &self.jobs)
+            .field("highlighter", &self.highlighter.is_some())
+            .finish()
+    }
+}
+
+impl DeferredJob {
+    /// The placeholder written in place of the job's output.
+    pub fn placeholder(&self) -> String {
+        format!(
+            "{}{}{}",
+            placeholder_prefix(self.nonce),
+            self.id,
+            PLACEHOLDER_SUFFIX
+        )
+    }
+
+    /// Renders the job with the adapters of `plugins`, or as Comrak renders
+    /// without them: a code block escaped in `<pre><code>`, and a heading as
+    /// a bare `<hN>`.
+    pub fn render(&self, plugins: &RenderPlugins) -> Result<String, fmt::Error> {
+        let mut output = String::new();
+        match &self.kind {
+            DeferredKind::CodeBlock { lang, meta, code } => {
+                match plugins.codefence_renderers.get(lang) {
+                    Some(adapter) => {
+                        adapter.write(&mut output, lang, meta, code, self.sourcepos)?
+                    }
+                    None => {
+                        html::write_opening_tag(&mut output, "pre", self.sourcepos_attribute())?;
+                        html::write_opening_tag(
+                            &mut output,
+                            "code",
+                            [("class", format!("language-{}", lang))],
+                        )?;
+                        html::escape(&mut output, code)?;
+                        output.write_str("</code></pre>\n")?;
+                    }
+                }
+            }
+            DeferredKind::Highlight { lang, meta, code } => {
+                match plugins.codefence_syntax_highlighter {
+                    Some(highlighter) => highlighter.write_highlighted_with_meta(
+                        &mut output,
+                        lang.as_deref(),
+                        meta,
+                        code,
+                        self.sourcepos,
+                    )?,
+                    None => html::escape(&mut output, code)?,
+                }
+            }
+            DeferredKind::HeadingEnter(heading) => match plugins.heading_adapter {
+                Some(adapter) => adapter.enter(&mut output, heading, self.sourcepos)?,
+                None => {
+                    write!(output, "<h{}", heading.level)?;
+                    if let Some(sourcepos) = self.sourcepos {
+                        write!(output, " data-sourcepos=\"{}\"", sourcepos)?;
+                    }
+                    output.write_str(">")?;
+                }
+            },
+            DeferredKind::HeadingExit(heading) => match plugins.heading_adapter {
+                Some(adapter) => adapter.exit(&mut output, heading)?,
+                None => writeln!(output, "</h{}>", heading.level)?,
+            },
+        }
+        Ok(output)
+    }
+
+    fn sourcepos_attribute(&self) -> Vec<(&'static str, String)> {
+        self.sourcepos
+            .map(|sourcepos| ("data-sourcepos", sourcepos.to_string()))
+            .into_iter()
+            .collect()
+    }
+}
+
+impl CodefenceRendererAdapter for Deferred<'_> {
+    fn write(
+        &self,
+        output: &mut dyn Write,
+        lang: &str,
+        meta: &str,
+        code: &str,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        let kind = DeferredKind::CodeBlock {
+            lang: lang.to_string(),
+            meta: meta.to_string(),
+            code: code.to_string(),
+        };
+        self.defer(output, kind, sourcepos)
+    }
+}
+
+impl SyntaxHighlighterAdapter for Deferred<'_> {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn Write,
+        lang: Option<&str>,
+        code: &str,
+    ) -> fmt::Result {
+        self.write_highlighted_with_meta(output, lang, &FenceMeta::default(), code, None)
+    }
+
+    fn write_highlighted_with_meta(
+        &self,
+        output: &mut dyn Write,
+        lang: Option<&str>,
+        meta: &FenceMeta,
+        code: &str,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        let kind = DeferredKind::Highlight {
+            lang: lang.filter(|lang| !lang.is_empty()).map(String::from),
+            meta: meta.clone(),
+            code: code.to_string(),
+        };
+        self.defer(output, kind, sourcepos)
+    }
+
+    fn write_pre_tag(
+        &self,
+        output: &mut dyn Write,
+        attributes: HashMap<&'static str, Cow<'_, str>>,
+    ) -> fmt::Result {
+        match self.highlighter {
+            Some(highlighter) => highlighter.write_pre_tag(output, attributes),
+            None => html::write_opening_tag(output, "pre", attributes),
+        }
+    }
+
+    fn write_code_tag(
+        &self,
+        output: &mut dyn Write,
+        attributes: HashMap<&'static str, Cow<'_, str>>,
+    ) -> fmt::Result {
+        match self.highlighter {
+            Some(highlighter) => highlighter.write_code_tag(output, attributes),
+            None => html::write_opening_tag(output, "code", attributes),
+        }
+    }
+
+    fn write_title(&self, output: &mut dyn Write, title: &str) -> fmt::Result {
+        match self.highlighter {
+            Some(highlighter) => highlighter.write_title(output, title),
+            None => Ok(()),
+        }
+    }
+}
+
+impl HeadingAdapter for Deferred<'_> {
+    fn enter(
+        &self,
+        output: &mut dyn Write,
+        heading: &HeadingMeta,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        self.defer(
+            output,
+            DeferredKind::HeadingEnter(heading.clone()),
+            sourcepos,
+        )
+    }
+
+    fn exit(&self, output: &mut dyn Write, heading: &HeadingMeta) -> fmt::Result {
+        self.defer(output, DeferredKind::HeadingExit(heading.clone()), None)
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::nodes::{AstNode, Node, NodeValue};
+use crate::options::{FootnotePlacement, Options};
+
+/// Decides where footnote definitions are rendered during traversal, per
+/// [`footnote_placement`](crate::options::Render#structfield.footnote_placement).
+///
+/// Definitions are queued as they're first referenced, and handed out to be
+/// rendered at the next flush point; the traversal then skips them where they
+/// appear in the document.
+pub(super) struct FootnotePlacer<'a> {
+    placement: FootnotePlacement,
+    definitions: HashMap<String, Node<'a>>,
+    pending: Vec<Node<'a>>,
+    placed: HashSet<*const AstNode<'a>>,
+    section_level: Option<u8>,
+}
+
+impl<'a> FootnotePlacer<'a> {
+    pub(super) fn new(root: Node<'a>, options: &Options) -> Self {
+        let placement = options.render.footnote_placement;
+        let mut definitions = HashMap::new();
+        let mut section_level = None;
+
+        if placement != FootnotePlacement::End {
+            for node in root.descendants() {
+                if let NodeValue::FootnoteDefinition(ref nfd) = node.data().value {
+                    definitions.entry(nfd.name.clone()).or_insert(node);
+                }
+            }
+            for node in root.children() {
+                if let NodeValue::Heading(ref nh) = node.data().value {
+                    section_level = Some(section_level.map_or(nh.level, |l: u8| l.min(nh.level)));
+                }
+            }
+        }
+
+        FootnotePlacer {
+            placement,
+            definitions,
+            pending: vec![],
+            placed: HashSet::new(),
+            section_level,
+        }
+    }
+
+    /// Notes a node about to be rendered, queueing the definition of a
+    /// footnote on its first reference.
+    pub(super) fn visit(&mut self, node: Node<'a>) {
+        if self.placement == FootnotePlacement::End {
+            return;
+        }
+        if let NodeValue::FootnoteReference(ref nfr) = node.data().value {
+            if let Some(&definition) = self.definitions.get(&nfr.name) {
+                let key: *const AstNode<'a> = definition;
+                if !self.placed.contains(&key)
+                    && !self.pending.iter().any(|&n| std::ptr::eq(n, definition))
+                {
+                    self.pending.push(definition);
+                }
+            }
+        }
+    }
+
+    /// Records that a definition is being rendered, returning `false` if it
+    /// already has been.
+    pub(super) fn place(&mut self, node: Node<'a>) -> bool {
+        self.placement == FootnotePlacement::End || self.placed.insert(node)
+    }
+
+    /// The definitions to render before `node`, which opens a top-level
+    /// section, with [`FootnotePlacement::Section`].
+    pub(super) fn flush_before(&mut self, node: Node<'a>) -> Vec<Node<'a>> {
+        if self.placement != FootnotePlacement::Section || !is_top_level(node) {
+            return vec![];
+        }
+        match node.data().value {
+            NodeValue::Heading(ref nh) if Some(nh.level) == self.section_level => self.flush(),
+            _ => vec![],
+        }
+    }
+
+    /// The definitions to render after `node`, a top-level block, with
+    /// [`FootnotePlacement::Inline`].
+    pub(super) fn flush_after(&mut self, node: Node<'a>) -> Vec<Node<'a>> {
+        if self.placement != FootnotePlacement::Inline || !is_top_level(node) {
+            return vec![];
+        }
+        self.flush()
+    }
+
+    fn flush(&mut self) -> Vec<Node<'a>> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+fn is_top_level(node: Node<'_>) -> bool {
+    node.parent()
+        .is_some_and(|parent| matches!(parent.data().value, NodeValue::Document))
+}
@@ -0,0 +1,371 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+
+use crate::Arena;
+use crate::html::anchorizer::is_permitted_char;
+use crate::html::{
+    Context, NestedListItem, collect_text, format_node_default, format_with_context,
+    toc_with_plugins, write_nested_list_items,
+};
+use crate::nodes::{AstNode, Node, NodeValue, Sourcepos};
+use crate::parser::options::{Options, Plugins};
+use crate::sections::{SectionNumberer, format_section_number};
+
+/// A page of a document, as split by [`split_document`].
+#[derive(Debug)]
+pub struct Page<'a> {
+    /// A document node holding the blocks of this page, which have been moved
+    /// here from the original document.
+    pub root: Node<'a>,
+
+    /// The file name the page is to be published as, which links from other
+    /// pages point to.  This is `index.html` for any content preceding the
+    /// first split heading, and the heading's anchor suffixed with `.html`
+    /// otherwise, keeping only the characters the default anchorizer does, or
+    /// `page-N.html` for the Nth page if none are left.
+    /// Names already taken, including `index.html`, are suffixed with `-1`,
+    /// `-2` and so on to make them unique.
+    pub filename: String,
+
+    /// The flattened text of the heading opening this page, or `None` for the
+    /// content preceding the first split heading.
+    pub title: Option<String>,
+
+    /// The anchor of the heading opening this page, or `None` for the content
+    /// preceding the first split heading.
+    pub anchor: Option<String>,
+
+    /// The level of the heading opening this page, or 0 for the content
+    /// preceding the first split heading.
+    pub level: u8,
+
+    footnote_start: u32,
+    numberer: SectionNumberer,
+}
+
+/// A document split into pages by [`split_document`].
+#[derive(Debug)]
+pub struct Pages<'a> {
+    /// The pages, in document order.
+    pub pages: Vec<Page<'a>>,
+
+    /// The index of the page each fragment ID is rendered on.
+    fragments: HashMap<String, usize>,
+}
+
+/// Splits a document into pages at each top-level heading of `level` or
+/// lower, moving its blocks into a new document node for each page.
+///
+/// Heading anchors are fixed (as if given explicitly, per
+/// [`header_custom_ids`](crate::options::Extension#structfield.header_custom_ids))
+/// so they don't change when pages are rendered separately, and links to
+/// anchors on other pages are rewritten to point to that page.  Each footnote
+/// definition is moved to the page of its first reference, keeping its number
+/// from the whole document.
+///
+/// ```rust
+/// # use comrak::{Arena, parse_document, Options, html::split_document, options::Plugins};
+/// let arena = Arena::new();
+/// let mut options = Options::default();
+/// options.extension.header_id_prefix = Some("".to_string());
+/// let root = parse_document(
+///     &arena,
+///     "# Install\n\nSee [usage](#usage).\n\n# Usage\n\nRun it.\n",
+///     &options,
+/// );
+///
+/// let pages = split_document(&arena, root, 1, &options, &Plugins::default());
+/// assert_eq!(pages.pages.len(), 2);
+/// assert_eq!(pages.pages[1].filename, "usage.html");
+///
+/// let mut html = String::new();
+/// pages.format_page(0, &options, &mut html, &Plugins::default()).unwrap();
+/// assert_eq!(
+///     html,
+///     "<h1><a href=\"#install\" aria-hidden=\"true\" class=\"anchor\" id=\"install\"></a>Install</h1>\n\
+///      <p>See <a href=\"usage.html#usage\">usage</a>.</p>\n"
+/// );
+/// ```
+pub fn split_document<'a>(
+    arena: &'a Arena<'a>,
+    root: Node<'a>,
+    level: u8,
+    options: &Options,
+    plugins: &Plugins,
+) -> Pages<'a> {
+    let prefix = options.extension.effective_header_id_prefix();
+
+    // Fix each heading's anchor now; rendering pages separately would
+    // otherwise disambiguate them per page.
+    let entries = toc_with_plugins(root, options, plugins);
+    let headings = root
+        .descendants()
+        .filter(|node| matches!(node.data().value, NodeValue::Heading(..)));
+    let mut anchors: HashMap<*const AstNode<'a>, String> = HashMap::new();
+    for (node, entry) in headings.zip(entries) {
+        if let NodeValue::Heading(ref mut nh) = node.data_mut().value {
+            if prefix.is_some() || nh.id.is_some() {
+                nh.id = Some(entry.anchor.clone());
+            }
+        }
+        anchors.insert(node, entry.anchor);
+    }
+
+    let mut definitions = vec![];
+    if !options.parse.leave_footnote_definitions {
+        for node in root.children().collect::<Vec<_>>() {
+            if let NodeValue::FootnoteDefinition(ref nfd) = node.data().value {
+                definitions.push((nfd.name.clone(), node));
+                node.detach();
+            }
+        }
+    }
+
+    let mut pages: Vec<Page<'a>> = vec![];
+    let mut filenames = HashSet::from(["index.html".to_string()]);
+    let mut numberer = SectionNumberer::default();
+    for node in root.children().collect::<Vec<_>>() {
+        let heading_level = match node.data().value {
+            NodeValue::Heading(ref nh) => Some(nh.level),
+            _ => None,
+        };
+
+        match heading_level {
+            Some(heading_level) if heading_level <= level => {
+                let key: *const AstNode<'a> = node;
+                let anchor = anchors[&key].clone();
+                let filename = page_filename(&anchor, pages.len() + 1, &mut filenames);
+                pages.push(Page {
+                    root: arena.alloc(NodeValue::Document.into()),
+                    filename,
+                    title: Some(collect_text(node)),
+                    anchor: Some(anchor),
+                    level: heading_level,
+                    footnote_start: 0,
+                    numberer: numberer.clone(),
+                });
+            }
+            _ if pages.is_empty() => {
+                pages.push(Page {
+                    root: arena.alloc(NodeValue::Document.into()),
+                    filename: "index.html".to_string(),
+                    title: None,
+                    anchor: None,
+                    level: 0,
+                    footnote_start: 0,
+                    numberer: numberer.clone(),
+                });
+            }
+            _ => (),
+        }
+
+        if let Some(heading_level) = heading_level {
+            numberer.next(heading_level);
+        }
+
+        node.detach();
+        pages.last().unwrap().root.append(node);
+    }
+
+    if pages.is_empty() {
+        return Pages {
+            pages,
+            fragments: HashMap::new(),
+        };
+    }
+
+    let mut fragments = HashMap::new();
+    let mut definition_pages = HashMap::new();
+    for (ix, page) in pages.iter().enumerate() {
+        for node in page.root.descendants() {
+            match node.data().value {
+                NodeValue::Heading(ref nh) => {
+                    if let Some(ref id) = nh.id {
+                        fragments.insert(id.clone(), ix);
+                        if let Some(prefix) = prefix {
+                            fragments.insert(format!("{}{}", prefix, id), ix);
+                        }
+                    }
+                }
+                NodeValue::FootnoteReference(ref nfr) => {
+                    definition_pages.entry(nfr.name.clone()).or_insert(ix);
+                    let mut id = format!("fnref-{}", nfr.name);
+                    if nfr.ref_num > 1 {
+                        id = format!("{}-{}", id, nfr.ref_num);
+                    }
+                    fragments.insert(id, ix);
+                }
+                NodeValue::FootnoteDefinition(ref nfd) => {
+                    fragments.insert(format!("fn-{}", nfd.name), ix);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // Definitions are in order of first reference, and so are pages; each
+    // page's definitions thus continue the numbering of the previous page's.
+    for (name, node) in definitions {
+        let ix = definition_pages
+            .get(&name)
+            .copied()
+            .unwrap_or(pages.len() - 1);
+        pages[ix].root.append(node);
+        fragments.insert(format!("fn-{}", name), ix);
+    }
+
+    let mut footnote_start = 0;
+    for page in &mut pages {
+        page.footnote_start = footnote_start;
+        footnote_start += page
+            .root
+            .children()
+            .filter(|node| matches!(node.data().value, NodeValue::FootnoteDefinition(..)))
+            .count() as u32;
+
+        let first = page.root.first_child().unwrap().data().sourcepos;
+        let last = page.root.last_child().unwrap().data().sourcepos;
+        page.root.data_mut().sourcepos = Sourcepos {
+            start: first.start,
+            end: last.end,
+        };
+    }
+
+    for (ix, page) in pages.iter().enumerate() {
+        for node in page.root.descendants() {
+            if let NodeValue::Link(ref mut nl) | NodeValue::Image(ref mut nl) =
+                node.data_mut().value
+            {
+                let target = nl
+                    .url
+                    .strip_prefix('#')
+                    .and_then(|fragment| fragments.get(fragment));
+                if let Some(&target) = target {
+                    if target != ix {
+                        nl.url = format!("{}{}", pages[target].filename, nl.url);
+                    }
+                }
+            }
+        }
+    }
+
+    Pages { pages, fragments }
+}
+
+/// Returns a file name for the `number`th page, opened by a heading with the
+/// given anchor, unique among `taken`.
+fn page_filename(anchor: &str, number: usize, taken: &mut HashSet<String>) -> String {
+    // Custom IDs may hold anything, such as `../`, which mustn't reach the
+    // file system.
+    let stem: String = anchor
+        .chars()
+        .filter(is_permitted_char)
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect();
+    let stem = if stem.is_empty() {
+        format!("page-{}", number)
+    } else {
+        stem
+    };
+    let mut filename = format!("{}.html", stem);
+    let mut uniq = 0;
+    while taken.contains(&filename) {
+        uniq += 1;
+        filename = format!("{}-{}.html", stem, uniq);
+    }
+    taken.insert(filename.clone());
+    filename
+}
+
+impl Pages<'_> {
+    /// Formats the page at index `ix` as HTML.  Footnote references and
+    /// backreferences to other pages link to those pages, and heading numbers
+    /// continue from the previous page.
+    pub fn format_page(
+        &self,
+        ix: usize,
+        options: &Options,
+        output: &mut dyn fmt::Write,
+        plugins: &Plugins,
+    ) -> fmt::Result {
+        let page = &self.pages[ix];
+
+        let mut context = Context::new(output, page.root, options, plugins, ());
+        context.footnote_ix = page.footnote_start;
+        context.written_footnote_ix = page.footnote_start;
+        context.section_numberer = page.numberer.clone();
+        context.external_fragments = self
+            .fragments
+            .iter()
+            .filter(|&(fragment, &target)| {
+                target != ix && (fragment.starts_with("fn-") || fragment.starts_with("fnref-"))
+            })
+            .map(|(fragment, &target)| (fragment.clone(), self.pages[target].filename.clone()))
+            .collect();
+
+        format_with_context(context, page.root, format_node_default)
+    }
+
+    /// Formats a navigation index linking to each page opened by a heading,
+    /// nested by heading level.
+    ///
+    /// ```rust
+    /// # use comrak::{Arena, parse_document, Options, html::split_document, options::Plugins};
+    /// let arena = Arena::new();
+    /// let options = Options::default();
+    /// let root = parse_document(&arena, "# A\n\n## B\n\n# C\n", &options);
+    ///
+    /// let pages = split_document(&arena, root, 2, &options, &Plugins::default());
+    /// let mut html = String::new();
+    /// pages.format_index(&options, &mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     "<ul class=\"page-index\">\n\
+    ///      <li><a href=\"a.html\">A</a>\n\
+    ///      <ul>\n\
+    ///      <li><a href=\"b.html\">B</a></li>\n\
+    ///      </ul>\n\
+    ///      </li>\n\
+    ///      <li><a href=\"c.html\">C</a></li>\n\
+    ///      </ul>\n"
+    /// );
+    /// ```
+    pub fn format_index(&self, options: &Options, output: &mut dyn fmt::Write) -> fmt::Result {
+        let titled: Vec<_> = self
+            .pages
+            .iter()
+            .filter_map(|page| page.title.as_ref().map(|title| (page, title)))
+            .collect();
+        if titled.is_empty() {
+            return Ok(());
+        }
+
+        let numbers: Vec<_> = titled
+            .iter()
+            .map(|(page, _)| {
+                if options.render.heading_numbers {
+                    format_section_number(&page.numberer.clone().next(page.level))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let plugins = Plugins::default();
+        let mut context = Context::new(output, titled[0].0.root, options, &plugins, ());
+        context.write_str("<ul class=\"page-index\">")?;
+        context.lf()?;
+        let items = titled
+            .iter()
+            .zip(&numbers)
+            .map(|(&(page, title), number)| NestedListItem {
+                level: page.level,
+                href: page.filename.clone(),
+                number: number.as_deref(),
+                text: title,
+            });
+        write_nested_list_items(&mut context, items)?;
+        context.write_str("</ul>")?;
+        context.lf()
+    }
+}
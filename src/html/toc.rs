@@ -0,0 +1,77 @@
+use crate::html::{Anchorizer, collect_text};
+use crate::nodes::{Node, NodeValue, Sourcepos};
+use crate::parser::options::{Options, Plugins};
+use crate::sections::{SectionNumberer, format_section_number, is_section_heading};
+
+/// A single heading in a document's table of contents, as returned by
+/// [`toc`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    /// The level of the heading; from 1 to 6.
+    pub level: u8,
+
+    /// The heading's content, flattened as per [`collect_text`].
+    pub text: String,
+
+    /// The heading's anchor, as generated by the [`Anchorizer`] used by the
+    /// HTML formatter, or as given explicitly per
+    /// [`header_custom_ids`](crate::options::Extension#structfield.header_custom_ids).  The rendered `id` attribute is this value prefixed
+    /// with [`header_id_prefix`](crate::options::Extension#structfield.header_id_prefix).
+    pub anchor: String,
+
+    /// The heading's section number, like `"1.2"`, when
+    /// [`heading_numbers`](crate::options::Render#structfield.heading_numbers) is
+    /// enabled and the heading opens a section.
+    pub number: Option<String>,
+
+    /// The position of the heading in the source document.
+    pub sourcepos: Sourcepos,
+}
+
+/// Collects the headings of a document in document order, along with the
+/// anchors the HTML formatter gives them.
+///
+/// ```rust
+/// # use comrak::{Arena, parse_document, Options, html::toc};
+/// let arena = Arena::new();
+/// let options = Options::default();
+/// let root = parse_document(&arena, "# Intro\n\n## Usage\n\n# Intro\n", &options);
+///
+/// let entries = toc(root, &options);
+/// let anchors: Vec<_> = entries.iter().map(|e| (e.level, e.anchor.as_str())).collect();
+/// assert_eq!(anchors, [(1, "intro"), (2, "usage"), (1, "intro-1")]);
+/// ```
+pub fn toc(root: Node<'_>, options: &Options) -> Vec<TocEntry> {
+    toc_with_plugins(root, options, &Plugins::default())
+}
+
+/// Collects the headings of a document, as per [`toc`], using the
+/// [`AnchorizerAdapter`](crate::adapters::AnchorizerAdapter) configured in the
+/// given plugins, if any.
+pub fn toc_with_plugins(root: Node<'_>, options: &Options, plugins: &Plugins) -> Vec<TocEntry> {
+    let mut anchorizer = Anchorizer::for_document(root, options);
+    let mut numberer = SectionNumberer::default();
+    let mut entries = vec![];
+
+    for node in root.descendants() {
+        let ast = node.data();
+        if let NodeValue::Heading(ref nh) = ast.value {
+            let text = collect_text(node);
+            let anchor = anchorizer.anchorize_heading(nh, &text, plugins);
+            let number = if options.render.heading_numbers && is_section_heading(node) {
+                format_section_number(&numberer.next(nh.level))
+            } else {
+                None
+            };
+            entries.push(TocEntry {
+                level: nh.level,
+                text,
+                anchor,
+                number,
+                sourcepos: ast.sourcepos,
+            });
+        }
+    }
+
+    entries
+}
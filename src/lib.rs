@@ -68,9 +68,12 @@
 
 pub mod adapters;
 pub mod arena_tree;
+pub mod backlinks;
+pub mod crossref;
 pub mod html;
 pub mod nodes;
 pub mod plugins;
+pub mod sections;
 
 mod character_set;
 mod cm;
@@ -13,12 +13,21 @@ use clap::{Parser, ValueEnum};
 use comrak::options;
 use comrak::{Arena, Options};
 #[cfg(feature = "syntect")]
-use comrak::{adapters::SyntaxHighlighterAdapter, plugins::syntect::SyntectAdapter};
+use comrak::{
+    adapters::SyntaxHighlighterAdapter,
+    plugins::syntect::{StylesheetError, SyntectAdapter, SyntectAdapterBuilder},
+};
 
 const EXIT_SUCCESS: i32 = 0;
 const EXIT_PARSE_CONFIG: i32 = 2;
 const EXIT_READ_INPUT: i32 = 3;
 const EXIT_CHECK_FILE_NUM: i32 = 4;
+#[cfg(feature = "syntect")]
+const EXIT_UNKNOWN_THEME: i32 = 5;
+#[cfg(feature = "syntect")]
+const EXIT_LOAD_SYNTAX: i32 = 6;
+#[cfg(feature = "syntect")]
+const EXIT_GENERATE_STYLESHEET: i32 = 7;
 
 #[derive(Debug, Parser)]
 #[command(about, author, version)]
@@ -48,6 +57,19 @@ struct Cli {
     #[arg(long)]
     smart: bool,
 
+    /// Follow the conventions of the given locale for smart punctuation
+    #[arg(long, value_enum, value_name = "LOCALE", default_value_t = SmartPunctuation::En)]
+    smart_punctuation: SmartPunctuation,
+
+    /// Replace sequences like (c), -> and 1/2 with typographic symbols
+    #[arg(long)]
+    typographer: bool,
+
+    /// Write typographic replacements in their ASCII source form in
+    /// CommonMark output
+    #[arg(long, requires = "typographer")]
+    typographer_source: bool,
+
     /// Use GitHub-style "<pre lang>" for code blocks
     #[arg(long)]
     github_pre_lang: bool,
@@ -140,6 +162,22 @@ struct Cli {
     #[arg(long)]
     header_id_prefix_in_href: bool,
 
+    /// Allow explicit header IDs with a trailing "{#id}" on the header line
+    #[arg(long)]
+    header_custom_ids: bool,
+
+    /// Reserve the given header ID, so that generated IDs avoid it
+    #[arg(long = "header-reserved-id", value_name = "ID")]
+    header_reserved_ids: Vec<String>,
+
+    /// Prefix footnote and footnote reference IDs with the given string
+    #[arg(long, value_name = "PREFIX")]
+    footnote_id_prefix: Option<String>,
+
+    /// Where to render footnote definitions in HTML output
+    #[arg(long, value_enum, default_value_t = FootnotePlacement::End)]
+    footnote_placement: FootnotePlacement,
+
     /// Detect frontmatter that starts and ends with the given string, and do
     /// not include it in the resulting document
     #[arg(long, value_name = "DELIMITER", allow_hyphen_values = true)]
@@ -151,6 +189,47 @@ struct Cli {
     #[cfg(feature = "syntect")]
     syntax_highlighting: String,
 
+    /// Print the stylesheet for code highlighted with '--syntax-highlighting
+    /// css' in the given theme, and exit
+    #[arg(long, value_name = "THEME")]
+    #[cfg(feature = "syntect")]
+    syntax_highlighting_css: Option<String>,
+
+    /// Use the given theme in the printed stylesheet when the reader prefers a
+    /// dark colour scheme
+    #[arg(long, value_name = "THEME", requires = "syntax_highlighting_css")]
+    #[cfg(feature = "syntect")]
+    syntax_highlighting_css_dark: Option<String>,
+
+    /// Load the Sublime Text syntaxes (".sublime-syntax" files) in the given
+    /// directory for syntax highlighting; may be passed multiple times
+    #[arg(long, value_name = "DIR", action = clap::ArgAction::Append)]
+    #[cfg(feature = "syntect")]
+    syntax_dir: Vec<PathBuf>,
+
+    /// Load the TextMate themes (".tmTheme" files) in the given directory for
+    /// syntax highlighting, each named for its file; may be passed multiple
+    /// times
+    #[arg(long, value_name = "DIR", action = clap::ArgAction::Append)]
+    #[cfg(feature = "syntect")]
+    theme_dir: Vec<PathBuf>,
+
+    /// Load a TextMate theme (".tmTheme" file) for syntax highlighting, named
+    /// for the file; may be passed multiple times
+    #[arg(long, value_name = "FILE", action = clap::ArgAction::Append)]
+    #[cfg(feature = "syntect")]
+    theme_file: Vec<PathBuf>,
+
+    /// List the available syntax highlighting themes, and exit
+    #[arg(long)]
+    #[cfg(feature = "syntect")]
+    list_themes: bool,
+
+    /// List the available syntaxes and their file extensions, and exit
+    #[arg(long)]
+    #[cfg(feature = "syntect")]
+    list_syntaxes: bool,
+
     /// Specify bullet character for lists ("-", "+", "*") in CommonMark output
     #[arg(long, value_enum, default_value_t = ListStyle::Dash)]
     list_style: ListStyle,
@@ -179,6 +258,14 @@ struct Cli {
     /// Suppress pretty-printing newlines between block-level HTML elements
     #[arg(long)]
     compact: bool,
+
+    /// Prefix headings with their hierarchical section number, like "1.2"
+    #[arg(long)]
+    heading_numbers: bool,
+
+    /// Render the relative widths of table columns as a <colgroup>
+    #[arg(long)]
+    table_column_widths: bool,
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -196,6 +283,9 @@ enum Extension {
     Strikethrough,
     Tagfilter,
     Table,
+    ExtendedTables,
+    GridTables,
+    TableCaptions,
     Autolink,
     Tasklist,
     Superscript,
@@ -205,6 +295,8 @@ enum Extension {
     MultilineBlockQuotes,
     MathDollars,
     MathCode,
+    MathLabels,
+    CrossReferences,
     WikilinksTitleAfterPipe,
     WikilinksTitleBeforePipe,
     Underline,
@@ -218,6 +310,8 @@ enum Extension {
     Insert,
     PhoenixHeex,
     BlockDirective,
+    TableOfContents,
+    BlockAnchors,
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -227,6 +321,46 @@ enum ListStyle {
     Star,
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SmartPunctuation {
+    En,
+    De,
+    Fr,
+    Ch,
+    Ru,
+    Ja,
+}
+
+impl From<SmartPunctuation> for options::SmartPunctuation {
+    fn from(smart_punctuation: SmartPunctuation) -> Self {
+        match smart_punctuation {
+            SmartPunctuation::En => Self::En,
+            SmartPunctuation::De => Self::De,
+            SmartPunctuation::Fr => Self::Fr,
+            SmartPunctuation::Ch => Self::Ch,
+            SmartPunctuation::Ru => Self::Ru,
+            SmartPunctuation::Ja => Self::Ja,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum FootnotePlacement {
+    End,
+    Section,
+    Inline,
+}
+
+impl From<FootnotePlacement> for options::FootnotePlacement {
+    fn from(placement: FootnotePlacement) -> Self {
+        match placement {
+            FootnotePlacement::End => Self::End,
+            FootnotePlacement::Section => Self::Section,
+            FootnotePlacement::Inline => Self::Inline,
+        }
+    }
+}
+
 impl From<ListStyle> for options::ListStyleType {
     fn from(style: ListStyle) -> Self {
         match style {
@@ -269,6 +403,50 @@ fn cli_with_config() -> Cli {
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = cli_with_config();
 
+    #[cfg(feature = "syntect")]
+    let adapter = build_syntect_adapter(&cli);
+
+    #[cfg(feature = "syntect")]
+    if cli.list_themes {
+        for theme in adapter.theme_names() {
+            println!("{}", theme);
+        }
+        process::exit(EXIT_SUCCESS);
+    }
+
+    #[cfg(feature = "syntect")]
+    if cli.list_syntaxes {
+        for syntax in adapter.syntaxes() {
+            println!("{} ({})", syntax.name, syntax.file_extensions.join(", "));
+        }
+        process::exit(EXIT_SUCCESS);
+    }
+
+    #[cfg(feature = "syntect")]
+    if let Some(ref theme) = cli.syntax_highlighting_css {
+        let stylesheet = match cli.syntax_highlighting_css_dark {
+            Some(ref dark) => adapter.stylesheet_with_dark_theme(theme, dark),
+            None => adapter.stylesheet(theme),
+        };
+        match stylesheet {
+            Ok(stylesheet) => {
+                print!("{}", stylesheet);
+                process::exit(EXIT_SUCCESS);
+            }
+            Err(error @ StylesheetError::UnknownTheme(_)) => {
+                eprintln!("{}", error);
+                process::exit(EXIT_UNKNOWN_THEME);
+            }
+            Err(error) => {
+                eprintln!(
+                    "failed to generate the syntax highlighting stylesheet: {}",
+                    error
+                );
+                process::exit(EXIT_GENERATE_STYLESHEET);
+            }
+        }
+    }
+
     if cli.inplace && cli.files.is_none() {
         eprintln!("no input file specified: cannot use standard input with in-place mode");
         process::exit(EXIT_CHECK_FILE_NUM);
@@ -284,17 +462,24 @@ fn main() -> Result<(), Box<dyn Error>> {
         .strikethrough(exts.contains(&Extension::Strikethrough) || cli.gfm)
         .tagfilter(exts.contains(&Extension::Tagfilter) || cli.gfm)
         .table(exts.contains(&Extension::Table) || cli.gfm)
+        .extended_tables(exts.contains(&Extension::ExtendedTables))
+        .grid_tables(exts.contains(&Extension::GridTables))
+        .table_captions(exts.contains(&Extension::TableCaptions))
         .autolink(exts.contains(&Extension::Autolink) || cli.gfm)
         .tasklist(exts.contains(&Extension::Tasklist) || cli.gfm)
         .superscript(exts.contains(&Extension::Superscript))
         .maybe_header_id_prefix(cli.header_id_prefix.or(cli.header_ids))
         .header_id_prefix_in_href(cli.header_id_prefix_in_href)
+        .header_custom_ids(cli.header_custom_ids)
+        .header_reserved_ids(cli.header_reserved_ids)
         .footnotes(exts.contains(&Extension::Footnotes))
         .inline_footnotes(exts.contains(&Extension::InlineFootnotes))
         .description_lists(exts.contains(&Extension::DescriptionLists))
         .multiline_block_quotes(exts.contains(&Extension::MultilineBlockQuotes))
         .math_dollars(exts.contains(&Extension::MathDollars))
         .math_code(exts.contains(&Extension::MathCode))
+        .math_labels(exts.contains(&Extension::MathLabels))
+        .cross_references(exts.contains(&Extension::CrossReferences))
         .wikilinks_title_after_pipe(exts.contains(&Extension::WikilinksTitleAfterPipe))
         .wikilinks_title_before_pipe(exts.contains(&Extension::WikilinksTitleBeforePipe))
         .underline(exts.contains(&Extension::Underline))
@@ -308,7 +493,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         .highlight(exts.contains(&Extension::Highlight))
         .insert(exts.contains(&Extension::Insert))
         .phoenix_heex(exts.contains(&Extension::PhoenixHeex))
-        .block_directive(exts.contains(&Extension::BlockDirective));
+        .block_directive(exts.contains(&Extension::BlockDirective))
+        .table_of_contents(exts.contains(&Extension::TableOfContents))
+        .block_anchors(exts.contains(&Extension::BlockAnchors));
 
     #[cfg(feature = "shortcodes")]
     let extension = extension.shortcodes(cli.gemoji);
@@ -317,6 +504,8 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let parse = options::Parse::builder()
         .smart(cli.smart)
+        .smart_punctuation(cli.smart_punctuation.into())
+        .typographer(cli.typographer)
         .maybe_default_info_string(cli.default_info_string)
         .relaxed_tasklist_matching(cli.relaxed_tasklist_character)
         .relaxed_autolinks(cli.relaxed_autolinks)
@@ -335,6 +524,11 @@ fn main() -> Result<(), Box<dyn Error>> {
         .sourcepos(cli.sourcepos)
         .experimental_minimize_commonmark(cli.experimental_minimize_commonmark)
         .compact_html(cli.compact)
+        .heading_numbers(cli.heading_numbers)
+        .table_column_widths(cli.table_column_widths)
+        .maybe_footnote_id_prefix(cli.footnote_id_prefix)
+        .footnote_placement(cli.footnote_placement.into())
+        .typographer_source(cli.typographer_source)
         .escaped_char_spans(cli.escaped_char_spans)
         .ignore_empty_links(cli.ignore_empty_links)
         .gfm_quirks(cli.gfm_quirks || cli.gfm)
@@ -347,27 +541,21 @@ fn main() -> Result<(), Box<dyn Error>> {
         render,
     };
 
-    #[cfg(feature = "syntect")]
-    let syntax_highlighter: Option<&dyn SyntaxHighlighterAdapter>;
-    #[cfg(feature = "syntect")]
-    let adapter: SyntectAdapter;
-
     #[cfg_attr(not(feature = "syntect"), allow(unused_mut))]
     let mut plugins = options::Plugins::default();
 
     #[cfg(feature = "syntect")]
-    {
-        let theme = cli.syntax_highlighting;
+    let syntax_highlighter: Option<&dyn SyntaxHighlighterAdapter> = {
+        let theme = &cli.syntax_highlighting;
         if theme.is_empty() || theme == "none" {
-            syntax_highlighter = None;
-        } else if theme == "css" {
-            adapter = SyntectAdapter::new(None);
-            syntax_highlighter = Some(&adapter);
+            None
+        } else if theme == "css" || adapter.theme_names().any(|name| name == theme) {
+            Some(&adapter)
         } else {
-            adapter = SyntectAdapter::new(Some(&theme));
-            syntax_highlighter = Some(&adapter);
+            eprintln!("unknown syntax highlighting theme: {}", theme);
+            process::exit(EXIT_UNKNOWN_THEME);
         }
-    }
+    };
 
     if cli.inplace {
         for path in cli.files.as_ref().unwrap() {
@@ -450,6 +638,40 @@ fn main() -> Result<(), Box<dyn Error>> {
     process::exit(EXIT_SUCCESS);
 }
 
+#[cfg(feature = "syntect")]
+fn build_syntect_adapter(cli: &Cli) -> SyntectAdapter {
+    let theme = &cli.syntax_highlighting;
+    let mut builder = if theme.is_empty() || theme == "none" || theme == "css" {
+        SyntectAdapterBuilder::new().css()
+    } else {
+        SyntectAdapterBuilder::new().theme(theme)
+    };
+
+    for dir in &cli.syntax_dir {
+        builder = builder
+            .add_syntaxes_from_folder(dir)
+            .unwrap_or_else(|e| exit_load_failed("syntaxes", dir, e));
+    }
+    for dir in &cli.theme_dir {
+        builder = builder
+            .add_themes_from_folder(dir)
+            .unwrap_or_else(|e| exit_load_failed("themes", dir, e));
+    }
+    for file in &cli.theme_file {
+        builder = builder
+            .add_theme_file(file)
+            .unwrap_or_else(|e| exit_load_failed("theme", file, e));
+    }
+
+    builder.build()
+}
+
+#[cfg(feature = "syntect")]
+fn exit_load_failed(what: &str, path: &std::path::Path, e: syntect::LoadingError) -> ! {
+    eprintln!("failed to load {} from {}: {}", what, path.display(), e);
+    process::exit(EXIT_LOAD_SYNTAX);
+}
+
 #[cfg(all(not(windows), not(target_arch = "wasm32")))]
 fn get_default_config_path() -> String {
     let xdg_dirs = xdg::BaseDirectories::with_prefix("comrak");
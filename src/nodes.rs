@@ -118,7 +118,7 @@ pub enum NodeValue {
     FootnoteDefinition(NodeFootnoteDefinition),
 
     /// **Block**. A [table](https://github.github.com/gfm/#tables-extension-) per the GFM spec.
-    /// Contains table rows.
+    /// Contains table rows, preceded by a table caption if it has one.
     Table(Box<NodeTable>),
 
     /// **Block**. A table row.  The `bool` represents whether the row is the header row or not.
@@ -126,7 +126,11 @@ pub enum NodeValue {
     TableRow(bool),
 
     /// **Block**.  A table cell.  Contains **inlines**.
-    TableCell,
+    TableCell(NodeTableCell),
+
+    /// **Block**.  The caption of a table, with the `table_captions`
+    /// extension.  The first child of its table.  Contains **inlines**.
+    TableCaption,
 
     /// **Inline**.  [Textual content](https://github.github.com/gfm/#textual-content).  All text
     /// in a document will be contained in a `Text` node.
@@ -229,8 +233,9 @@ pub enum NodeValue {
     /// tags in output HTML and XML.
     Escaped,
 
-    /// **Inline**.  A wikilink to some URL.
-    WikiLink(NodeWikiLink),
+    /// **Inline**.  A wikilink to some URL, or an embed of the page or image
+    /// at it, like `![[Note#Section]]` or `![[image.png|300]]`.
+    WikiLink(Box<NodeWikiLink>),
 
     /// **Inline**.  Underline. Enabled with `underline` option.
     Underline,
@@ -265,6 +270,42 @@ pub enum NodeValue {
     /// :::
     /// ```
     BlockDirective(Box<NodeBlockDirective>),
+
+    /// **Block**. A table of contents placeholder, enabled with the `table_of_contents` option.
+    /// Rendered as a nested list of links to the document's headings.  Has no children.
+    ///
+    /// ```markdown
+    /// [[_TOC_]]
+    /// ```
+    TableOfContents,
+
+    /// **Inline**.  A typographic replacement, such as `(c)` for `©`, enabled
+    /// with the `typographer` option.  Has no children.
+    Typographic(NodeTypographic),
+
+    /// **Inline**.  Display math given a label, such as `$$E = mc^2$$
+    /// {#eq:energy}`, enabled with the `math_labels` option.  Contains the
+    /// labelled [`Math`](NodeValue::Math) node.
+    Equation(Box<NodeEquation>),
+
+    /// **Inline**.  An image given a label, such as
+    /// `![Architecture](arch.png){#fig:arch}`, enabled with the
+    /// `cross_references` option.  Contains the labelled
+    /// [`Image`](NodeValue::Image) node.
+    Figure(Box<NodeFigure>),
+
+    /// **Block**.  Another document spliced in place of a paragraph
+    /// consisting solely of `{{#include path.md}}` or `![[note]]`, with the
+    /// `include_resolver` option.  Contains the included document's
+    /// **blocks**, whose source positions refer to that document.
+    Include(Box<NodeInclude>),
+
+    /// **Inline**.  A block anchor, like ` ^block-id` at the end of a
+    /// paragraph or list item, enabled with the `block_anchors` option, which
+    /// [wikilinks](NodeValue::WikiLink) like `[[Page#^block-id]]` link to.
+    /// The ID is rendered on the paragraph, or the list item it begins.  Has
+    /// no children.
+    BlockAnchor(String),
 }
 
 /// Alignment of a single table cell.
@@ -308,6 +349,44 @@ pub struct NodeTable {
 
     /// Number of non-empty, non-autocompleted cells
     pub num_nonempty_cells: usize,
+
+    /// Whether this is a grid table, whose cells contain blocks rather than
+    /// inlines.
+    pub grid: bool,
+
+    /// The relative width of each column: the number of dashes in its
+    /// delimiter cell, or its width in a grid table.
+    pub column_widths: Vec<usize>,
+
+    /// The ID given with the caption of the table, if any.
+    pub id: Option<String>,
+
+    /// The number of the table, counting tables with an ID from 1 in document
+    /// order, when the `cross_references` option is enabled.
+    pub number: Option<usize>,
+}
+
+/// The metadata of a table cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeTableCell {
+    /// The index of the first column the cell occupies
+    pub column: usize,
+
+    /// Number of columns the cell spans
+    pub colspan: usize,
+
+    /// Number of rows the cell spans
+    pub rowspan: usize,
+}
+
+impl Default for NodeTableCell {
+    fn default() -> Self {
+        Self {
+            column: 0,
+            colspan: 1,
+            rowspan: 1,
+        }
+    }
 }
 
 /// A task list item's contents, and where it was found
@@ -352,8 +431,62 @@ pub struct NodeLink {
 pub struct NodeWikiLink {
     /// The URL for the link destination.
     pub url: String,
+
+    /// The page linked to, percent-decoded, without any `#heading` or
+    /// `#^block`.  Empty for a link within the page, like `[[#Heading]]`.
+    pub page: String,
+
+    /// The heading linked to, as in `[[Page#Heading]]`.
+    pub heading: Option<String>,
+
+    /// The block linked to, as in `[[Page#^block-id]]`.
+    pub block: Option<String>,
+
+    /// Whether the [`wikilink_resolver`](crate::options::Parse::wikilink_resolver)
+    /// reported the page as missing.
+    pub missing: bool,
+
+    /// Whether the link embeds the page, as in `![[Note]]`.
+    pub embed: bool,
+
+    /// The width given to an embed, as in `![[image.png|300]]`.
+    pub width: Option<u32>,
+
+    /// The height given to an embed, as in `![[image.png|300x200]]`.
+    pub height: Option<u32>,
+}
+
+impl NodeWikiLink {
+    /// The link's target as written: the page, then any heading or block.
+    pub fn target(&self) -> String {
+        let mut target = self.page.clone();
+        if let Some(block) = &self.block {
+            target.push_str("#^");
+            target.push_str(block);
+        } else if let Some(heading) = &self.heading {
+            target.push('#');
+            target.push_str(heading);
+        }
+        target
+    }
+
+    /// Whether the link embeds an image, going by the page's file extension.
+    pub fn is_image_embed(&self) -> bool {
+        self.embed
+            && self.page.rsplit_once('.').is_some_and(|(_, extension)| {
+                IMAGE_EXTENSIONS
+                    .iter()
+                    .any(|image| extension.eq_ignore_ascii_case(image))
+            })
+    }
 }
 
+/// The file extensions of images, which [`NodeWikiLink`] embeds render as
+/// `<img>`.
+pub const IMAGE_EXTENSIONS: &[&str] = &[
+    "apng", "avif", "bmp", "gif", "jpeg", "jpg", "png", "svg", "webp",
+];
+
 /// The metadata of a list; the kind of list, the delimiter used and so on.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct NodeList {
@@ -458,7 +591,7 @@ pub struct NodeCodeBlock {
 }
 
 /// The metadata of a heading.
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct NodeHeading {
     /// The level of the header; from 1 to 6 for ATX headings, 1 or 2 for setext headings.
     pub level: u8,
@@ -469,6 +602,10 @@ pub struct NodeHeading {
     /// Whether this ATX heading had a closing sequence of trailing hashes.
     /// Only meaningful for ATX headings (i.e. when `setext` is false).
     pub closed: bool,
+
+    /// An explicit anchor given with a trailing `{#id}`, per
+    /// [`header_custom_ids`](crate::options::Extension#structfield.header_custom_ids).
+    pub id: Option<String>,
 }
 
 /// The metadata of an included HTML block.
@@ -518,6 +655,26 @@ pub struct NodeMultilineBlockQuote {
     pub fence_offset: usize,
 }
 
+/// A typographic replacement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeTypographic {
+    /// The ASCII source of the replacement, e.g. `(c)`, or the suffix of an
+    /// ordinal number, e.g. `st`.
+    pub source: &'static str,
+
+    /// The replacement, e.g. `©`, or `None` for the suffix of an ordinal
+    /// number, which is rendered as superscript.
+    pub replacement: Option<&'static str>,
+}
+
+impl NodeTypographic {
+    /// The text this stands for: the replacement, or the source of an
+    /// ordinal suffix.
+    pub fn text(&self) -> &'static str {
+        self.replacement.unwrap_or(self.source)
+    }
+}
+
 /// An inline math span
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct NodeMath {
@@ -535,6 +692,42 @@ pub struct NodeMath {
     pub literal: String,
 }
 
+/// The details of labelled display math.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct NodeEquation {
+    /// The label, without the surrounding `{#` and `}`.
+    pub label: String,
+
+    /// The number of the equation, counting labelled display math from 1 in
+    /// document order.
+    pub number: usize,
+}
+
+/// The details of a labelled image.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct NodeFigure {
+    /// The label, without the surrounding `{#` and `}`.
+    pub label: String,
+
+    /// The number of the figure, counting labelled images from 1 in document
+    /// order.
+    pub number: usize,
+}
+
+/// The details of an included document.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct NodeInclude {
+    /// The included path as written, including any line range.
+    pub target: String,
+
+    /// The path of the included document, as resolved.
+    pub path: String,
+
+    /// Whether the include was written as an Obsidian-style `![[note]]` embed,
+    /// rather than an mdBook-style `{{#include path.md}}`.
+    pub embed: bool,
+}
+
 /// The metadata of an Alert node.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NodeAlert {
@@ -631,12 +824,15 @@ impl NodeValue {
             | NodeValue::ThematicBreak
             | NodeValue::Table(..)
             | NodeValue::TableRow(..)
-            | NodeValue::TableCell
+            | NodeValue::TableCell(..)
+            | NodeValue::TableCaption
             | NodeValue::TaskItem(..)
             | NodeValue::MultilineBlockQuote(_)
             | NodeValue::Alert(_)
             | NodeValue::Subtext => true,
-            NodeValue::BlockDirective(_) => true,
+            NodeValue::BlockDirective(_) | NodeValue::TableOfContents | NodeValue::Include(_) => {
+                true
+            }
             #[cfg(feature = "phoenix_heex")]
             NodeValue::HeexBlock(..) => true,
             _ => false,
@@ -649,7 +845,8 @@ impl NodeValue {
             *self,
             NodeValue::Paragraph
                 | NodeValue::Heading(..)
-                | NodeValue::TableCell
+                | NodeValue::TableCell(..)
+                | NodeValue::TableCaption
                 | NodeValue::Subtext
         )
     }
@@ -697,7 +894,8 @@ impl NodeValue {
             NodeValue::ThematicBreak => "thematic_break",
             NodeValue::Table(..) => "table",
             NodeValue::TableRow(..) => "table_row",
-            NodeValue::TableCell => "table_cell",
+            NodeValue::TableCell(..) => "table_cell",
+            NodeValue::TableCaption => "table_caption",
             NodeValue::Text(..) => "text",
             NodeValue::SoftBreak => "softbreak",
             NodeValue::LineBreak => "linebreak",
@@ -730,6 +928,12 @@ impl NodeValue {
             NodeValue::Alert(_) => "alert",
             NodeValue::Subtext => "subtext",
             NodeValue::BlockDirective(_) => "block_directive",
+            NodeValue::TableOfContents => "table_of_contents",
+            NodeValue::Typographic(_) => "typographic",
+            NodeValue::Equation(_) => "equation",
+            NodeValue::Figure(_) => "figure",
+            NodeValue::Include(_) => "include",
+            NodeValue::BlockAnchor(_) => "block_anchor",
         }
     }
 
@@ -739,6 +943,7 @@ impl NodeValue {
             | NodeValue::Heading(..)
             | NodeValue::CodeBlock(..)
             | NodeValue::Subtext => true,
+            NodeValue::Table(ref nt) => nt.grid,
             #[cfg(feature = "phoenix_heex")]
             NodeValue::HeexBlock(..) => true,
             _ => false,
@@ -1018,14 +1223,24 @@ impl<'a> arena_tree::Node<'a, RefCell<Ast>> {
             | NodeValue::Underline
             | NodeValue::Subscript
             | NodeValue::Subtext
+            | NodeValue::TableCaption
             // XXX: this is quite a hack: the EscapedTag _contains_ whatever was
             // possibly going to fall into the spoiler. This should be fixed in
             // inlines.
             | NodeValue::EscapedTag(_)
             => !child.block(),
-            NodeValue::Table(..) => matches!(*child, NodeValue::TableRow(..)),
-            NodeValue::TableRow(..) => matches!(*child, NodeValue::TableCell),
-            NodeValue::TableCell => {
+            NodeValue::Table(..) => {
+                matches!(*child, NodeValue::TableRow(..) | NodeValue::TableCaption)
+            }
+            NodeValue::TableRow(..) => matches!(*child, NodeValue::TableCell(..)),
+            NodeValue::TableCell(..)
+                if self.parent().and_then(|row| row.parent()).is_some_and(
+                    |table| matches!(table.data().value, NodeValue::Table(ref nt) if nt.grid),
+                ) =>
+            {
+                child.block() && !matches!(*child, NodeValue::Item(..) | NodeValue::TaskItem(..))
+            }
+            NodeValue::TableCell(..) => {
                 #[cfg(feature = "shortcodes")]
                 if matches!(*child, NodeValue::ShortCode(..)) {
                     return true;
@@ -1039,6 +1254,9 @@ impl<'a> arena_tree::Node<'a, RefCell<Ast>> {
                 matches!(
                     *child,
                     NodeValue::Text(..)
+                    // Breaks occur in the multi-line cells of extended tables.
+                    | NodeValue::SoftBreak
+                    | NodeValue::LineBreak
                     | NodeValue::Code(..)
                     | NodeValue::Emph
                     | NodeValue::Strong
@@ -1058,6 +1276,9 @@ impl<'a> arena_tree::Node<'a, RefCell<Ast>> {
                     | NodeValue::TaskItem(_)
                     | NodeValue::Escaped
                     | NodeValue::EscapedTag(_)
+                    | NodeValue::Typographic(_)
+                    | NodeValue::Equation(_)
+                    | NodeValue::Figure(_)
                 )
             }
             NodeValue::MultilineBlockQuote(_) => {
@@ -1066,11 +1287,13 @@ impl<'a> arena_tree::Node<'a, RefCell<Ast>> {
             NodeValue::Alert(_) => {
                 child.block() && !matches!(*child, NodeValue::Item(..) | NodeValue::TaskItem(..))
             }
-            NodeValue::BlockDirective(_) => {
+            NodeValue::BlockDirective(_) | NodeValue::Include(_) => {
                 child.block() && !matches!(*child, NodeValue::Item(..) | NodeValue::TaskItem(..))
             }
 
             NodeValue::Escaped => matches!(*child, NodeValue::Text(_)),
+            NodeValue::Equation(_) => matches!(*child, NodeValue::Math(_)),
+            NodeValue::Figure(_) => matches!(*child, NodeValue::Image(_)),
 
             NodeValue::FrontMatter(_)
             | NodeValue::CodeBlock(_)
@@ -1083,7 +1306,10 @@ impl<'a> arena_tree::Node<'a, RefCell<Ast>> {
             | NodeValue::HtmlInline(_)
             | NodeValue::Raw(_)
             | NodeValue::FootnoteReference(_)
-            | NodeValue::Math(_) => false,
+            | NodeValue::Math(_)
+            | NodeValue::TableOfContents
+            | NodeValue::Typographic(_)
+            | NodeValue::BlockAnchor(_) => false,
 
             #[cfg(feature = "phoenix_heex")]
             NodeValue::HeexBlock(_) | NodeValue::HeexInline(_) => false,
@@ -0,0 +1,301 @@
+//! Grid tables, whose cells are separated by `+---+` borders and `|` column
+//! rules, and may contain any block content.
+
+use std::mem;
+use std::ops::Range;
+
+use crate::nodes::{Ast, Node, NodeTable, NodeTableCell, NodeValue, Sourcepos, TableAlignment};
+use crate::parser::Parser;
+use crate::strings::is_blank;
+
+/// Returns true if the line is the top border of a grid table, like
+/// `+---+:--:+`.
+pub(crate) fn is_border(line: &str) -> bool {
+    let line = line.trim_end();
+    line.len() >= 3
+        && line.starts_with('+')
+        && line.ends_with('+')
+        && !line.contains("++")
+        && line.contains('-')
+        && line.bytes().all(|b| matches!(b, b'+' | b'-' | b':'))
+}
+
+/// Returns true if the line may continue an open grid table.
+pub(crate) fn matches(line: &str) -> bool {
+    matches!(line.as_bytes().first(), Some(b'+' | b'|'))
+}
+
+struct Grid {
+    /// Character positions of the column boundaries.
+    boundaries: Vec<usize>,
+    alignments: Vec<TableAlignment>,
+    /// The content lines of each row, as indices into the table's lines.
+    rows: Vec<Range<usize>>,
+    num_header_rows: usize,
+}
+
+impl Grid {
+    fn parse(lines: &[&str]) -> Option<Self> {
+        if !is_border(lines.first()?) {
+            return None;
+        }
+        let lines = lines
+            .iter()
+            .map(|l| l.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let top = &lines[0];
+        let boundaries = top
+            .iter()
+            .enumerate()
+            .filter(|&(_, &c)| c == '+')
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        let mut rows = vec![];
+        let mut header_separator = None;
+        let mut row_start = 1;
+
+        for (i, line) in lines.iter().enumerate().skip(1) {
+            if line.len() != top.len() {
+                return None;
+            }
+
+            if line[0] == '+' {
+                if row_start == i || !is_separator(line, &boundaries) {
+                    return None;
+                }
+                rows.push(row_start..i);
+                row_start = i + 1;
+
+                if header_separator.is_none() && line.contains(&'=') {
+                    header_separator = Some((rows.len(), i));
+                }
+            } else if !boundaries.iter().all(|&b| line[b] == '|') {
+                return None;
+            }
+        }
+
+        if rows.is_empty() || row_start != lines.len() {
+            return None;
+        }
+
+        let (num_header_rows, alignment_line) = header_separator.unwrap_or((0, 0));
+        let alignment_line = &lines[alignment_line];
+        let alignments = boundaries
+            .windows(2)
+            .map(|b| {
+                let left = alignment_line[b[0] + 1] == ':';
+                let right = alignment_line[b[1] - 1] == ':';
+                match (left, right) {
+                    (true, true) => TableAlignment::Center,
+                    (true, false) => TableAlignment::Left,
+                    (false, true) => TableAlignment::Right,
+                    (false, false) => TableAlignment::None,
+                }
+            })
+            .collect();
+
+        Some(Grid {
+            boundaries,
+            alignments,
+            rows,
+            num_header_rows,
+        })
+    }
+
+    /// Returns the byte offset of the given column boundary in a line.
+    fn byte_offset(&self, line: &str, boundary: usize) -> usize {
+        line.char_indices()
+            .nth(self.boundaries[boundary])
+            .map_or(line.len(), |(i, _)| i)
+    }
+
+    /// Returns the byte range of the given column's cell in a content line,
+    /// after its padding space and trailing whitespace.
+    fn cell_range(&self, line: &str, column: usize) -> Range<usize> {
+        let mut start = self.byte_offset(line, column) + 1;
+        let end = self.byte_offset(line, column + 1);
+
+        if start < end && line.as_bytes()[start] == b' ' {
+            start += 1;
+        }
+        start..start + line[start..end].trim_end().len()
+    }
+}
+
+/// Returns true if the line is a row separator: a `+` at each of the given
+/// positions, and `-`, `=` or `:` everywhere else.
+fn is_separator(line: &[char], boundaries: &[usize]) -> bool {
+    line.iter().enumerate().all(|(i, &c)| {
+        if boundaries.contains(&i) {
+            c == '+'
+        } else {
+            matches!(c, '-' | '=' | ':')
+        }
+    })
+}
+
+/// Splits the lines of every grid table below `root` into rows and cells,
+/// and parses the content of each cell as a document of its own.
+pub(crate) fn process_tables<'a>(parser: &mut Parser<'a, '_, '_>, root: Node<'a>) {
+    let tables = root
+        .descendants()
+        .filter(|n| matches!(n.data().value, NodeValue::Table(ref nt) if nt.grid))
+        .collect::<Vec<_>>();
+
+    for table in tables {
+        process_table(parser, table);
+    }
+}
+
+fn process_table<'a>(parser: &mut Parser<'a, '_, '_>, table: Node<'a>) {
+    let (content, line_offsets, start_line) = {
+        let mut ast = table.data_mut();
+        (
+            mem::take(&mut ast.content),
+            mem::take(&mut ast.line_offsets),
+            ast.sourcepos.start.line,
+        )
+    };
+    let lines = content.lines().map(str::trim_end).collect::<Vec<_>>();
+
+    let Some(grid) = Grid::parse(&lines) else {
+        // Anything that isn't a well-formed grid is left as a paragraph.
+        let mut ast = table.data_mut();
+        ast.value = NodeValue::Paragraph;
+        ast.content = content;
+        ast.line_offsets = line_offsets;
+        return;
+    };
+
+    let mut nt = NodeTable {
+        alignments: grid.alignments.clone(),
+        num_columns: grid.boundaries.len() - 1,
+        num_rows: grid.rows.len(),
+        num_nonempty_cells: 0,
+        grid: true,
+        column_widths: grid
+            .boundaries
+            .windows(2)
+            .map(|b| b[1] - b[0] - 1)
+            .collect(),
+        ..Default::default()
+    };
+
+    for (r, row) in grid.rows.iter().enumerate() {
+        let (first, last) = (row.start, row.end - 1);
+        let row_node = alloc_closed(
+            parser,
+            NodeValue::TableRow(r < grid.num_header_rows),
+            (
+                start_line + first,
+                line_offsets[first] + 1,
+                start_line + last,
+                line_offsets[last] + lines[last].len(),
+            )
+                .into(),
+        );
+        table.append(row_node);
+
+        for column in 0..nt.num_columns {
+            let mut text = String::new();
+            let mut shifts = vec![];
+            for i in row.clone() {
+                let range = grid.cell_range(lines[i], column);
+                text.push_str(&lines[i][range.clone()]);
+                text.push('\n');
+                shifts.push(line_offsets[i] + range.start);
+            }
+            if !is_blank(&text) {
+                nt.num_nonempty_cells += 1;
+            }
+
+            let start_column = line_offsets[first] + grid.byte_offset(lines[first], column) + 1;
+            let end_column = line_offsets[last] + grid.byte_offset(lines[last], column + 1);
+            let cell = alloc_closed(
+                parser,
+                NodeValue::TableCell(NodeTableCell {
+                    column,
+                    ..Default::default()
+                }),
+                (
+                    start_line + first,
+                    start_column + 1,
+                    start_line + last,
+                    end_column,
+                )
+                    .into(),
+            );
+            row_node.append(cell);
+
+            parse_cell(parser, cell, &text, start_line + first, &shifts);
+        }
+    }
+
+    table.data_mut().value = NodeValue::Table(Box::new(nt));
+}
+
+fn alloc_closed<'a>(
+    parser: &Parser<'a, '_, '_>,
+    value: NodeValue,
+    sourcepos: Sourcepos,
+) -> Node<'a> {
+    let mut ast = Ast::new_with_sourcepos(value, sourcepos);
+    ast.open = false;
+    parser.arena.alloc(ast.into())
+}
+
+/// Parses the blocks of a cell with a parser of its own, then moves them into
+/// the cell. `shifts` holds the byte column at which each line of the cell
+/// starts in the source; inlines are parsed later with the rest of the
+/// document.
+fn parse_cell<'a>(
+    parser: &mut Parser<'a, '_, '_>,
+    cell: Node<'a>,
+    text: &str,
+    start_line: usize,
+    shifts: &[usize],
+) {
+    let root = parser
+        .arena
+        .alloc(Ast::new(NodeValue::Document, (1, 1).into()).into());
+    let mut sub = Parser::new(parser.arena, root, parser.options);
+    for line in text.split_inclusive('\n') {
+        sub.process_line(line);
+    }
+    while !sub.current.same_node(root) {
+        sub.current = sub.finalize(sub.current).unwrap();
+    }
+    sub.finalize(root);
+    process_tables(&mut sub, root);
+
+    // Footnote definitions need no merging: they're moved into the cell with
+    // the other blocks, and gathered from the whole document once it's parsed.
+    for (label, rr) in mem::take(&mut sub.refmap.map) {
+        parser.refmap.map.entry(label).or_insert(rr);
+    }
+
+    let remap = |line: &mut usize, column: &mut usize| {
+        if let Some(shift) = line.checked_sub(1).and_then(|l| shifts.get(l)) {
+            if *column > 0 {
+                *column += shift;
+            }
+            *line += start_line - 1;
+        }
+    };
+    for node in root.descendants().skip(1) {
+        let mut ast = node.data_mut();
+        let first = ast.sourcepos.start.line.saturating_sub(1);
+        for (offset, shift) in ast.line_offsets.iter_mut().zip(shifts.iter().skip(first)) {
+            *offset += shift;
+        }
+        let sp = &mut ast.sourcepos;
+        remap(&mut sp.start.line, &mut sp.start.column);
+        remap(&mut sp.end.line, &mut sp.end.column);
+    }
+
+    while let Some(child) = root.first_child() {
+        cell.append(child);
+    }
+}
@@ -0,0 +1,238 @@
+//! Includes, which splice another document's blocks in place of a paragraph
+//! consisting solely of `{{#include path.md}}` or `![[note]]`.  An embed may
+//! name a section of the note, as in `![[note#Heading]]` or
+//! `![[note#^block-id]]`.
+
+use std::mem;
+
+use crate::nodes::{Ast, Node, NodeInclude, NodeValue};
+use crate::parser::Parser;
+use crate::parser::options::{IncludeReference, IncludeResolver};
+
+/// How deeply includes may nest, in case a resolver returns ever-new paths.
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+struct Directive<'t> {
+    /// The path and any line range, as written.
+    target: &'t str,
+
+    /// The path alone, as given to the resolver.
+    path: &'t str,
+
+    /// The first line and, if any, the last line to include, counting from 1.
+    lines: (usize, Option<usize>),
+
+    /// The heading, or `^` and the block ID, of an embed's section.
+    fragment: Option<&'t str>,
+
+    embed: bool,
+}
+
+/// Splices in the document named by the paragraph `content` of `node`, if
+/// it's an include, returning the details to make `node` an
+/// [`Include`](NodeValue::Include) of.
+pub(crate) fn resolve<'a>(
+    parser: &mut Parser<'a, '_, '_>,
+    node: Node<'a>,
+    content: &str,
+) -> Option<NodeInclude> {
+    let resolver = parser.options.parse.include_resolver.as_ref()?;
+    let directive = parse_directive(content)?;
+    if parser.include_stack.len() >= MAX_INCLUDE_DEPTH {
+        return None;
+    }
+
+    let resolved = resolver.resolve(IncludeReference {
+        target: directive.path,
+        from: parser.include_stack.last().map(String::as_str),
+    })?;
+    if parser.include_stack.contains(&resolved.path) {
+        return None;
+    }
+
+    let root = parser
+        .arena
+        .alloc(Ast::new(NodeValue::Document, (1, 1).into()).into());
+    let mut sub = Parser::new(parser.arena, root, parser.options);
+    sub.include_stack = parser.include_stack.clone();
+    sub.include_stack.push(resolved.path.clone());
+
+    // Number lines as in the included document, so source positions point
+    // into it.
+    let (start, end) = directive.lines;
+    sub.line_number = start - 1;
+    let count = end.map_or(usize::MAX, |end| (end + 1).saturating_sub(start));
+    for line in resolved
+        .content
+        .split_inclusive('\n')
+        .skip(start - 1)
+        .take(count)
+    {
+        sub.process_line(line);
+    }
+    while !sub.current.same_node(root) {
+        sub.current = sub.finalize(sub.current).unwrap();
+    }
+    sub.finalize(root);
+
+    for (label, rr) in mem::take(&mut sub.refmap.map) {
+        parser.refmap.map.entry(label).or_insert(rr);
+    }
+    parser.included.append(&mut sub.included);
+
+    if let Some(fragment) = directive.fragment {
+        select_section(root, fragment, resolver.as_ref())?;
+    }
+
+    while let Some(child) = root.first_child() {
+        node.append(child);
+    }
+
+    let ni = NodeInclude {
+        target: directive.target.to_string(),
+        path: resolved.path,
+        embed: directive.embed,
+    };
+    if parser.options.parse.sourcepos_chars {
+        parser.included.push((node, resolved.content));
+    }
+    Some(ni)
+}
+
+fn parse_directive(content: &str) -> Option<Directive<'_>> {
+    let content = content.trim();
+
+    if let Some(inner) = content
+        .strip_prefix("{{#include")
+        .and_then(|s| s.strip_suffix("}}"))
+    {
+        if !inner.starts_with(|c: char| c.is_ascii_whitespace()) {
+            return None;
+        }
+        let target = inner.trim();
+        if target.is_empty() || target.contains('\n') {
+            return None;
+        }
+        let (path, lines) = match target.split_once(':') {
+            Some((path, range)) => (path, parse_range(range)?),
+            None => (target, (1, None)),
+        };
+        return Some(Directive {
+            target,
+            path,
+            lines,
+            fragment: None,
+            embed: false,
+        });
+    }
+
+    let target = content.strip_prefix("![[")?.strip_suffix("]]")?;
+    if target.contains(['\n', '[', ']']) {
+        return None;
+    }
+    let path = target.split('|').next().unwrap().trim();
+    let (path, fragment) = match path.split_once('#') {
+        Some((path, fragment)) => (path.trim_end(), Some(fragment.trim_start())),
+        None => (path, None),
+    };
+    if path.is_empty() || fragment.is_some_and(str::is_empty) {
+        return None;
+    }
+    Some(Directive {
+        target,
+        path,
+        lines: (1, None),
+        fragment,
+        embed: true,
+    })
+}
+
+/// Parses an mdBook line range: `:10` for line 10 alone, `:10:` for line 10
+/// on, `::20` for up to line 20, or `:10:20` for lines 10 to 20.
+fn parse_range(range: &str) -> Option<(usize, Option<usize>)> {
+    let line = |n: &str| n.parse::<usize>().ok().filter(|&n| n > 0);
+    match range.split_once(':') {
+        None => {
+            let n = line(range)?;
+            Some((n, Some(n)))
+        }
+        Some((start, end)) => {
+            let start = if start.is_empty() { 1 } else { line(start)? };
+            let end = if end.is_empty() {
+                None
+            } else {
+                Some(line(end)?)
+            };
+            Some((start, end))
+        }
+    }
+}
+
+/// Detaches all of the blocks of the included document `root` but the section
+/// named by `fragment`: the heading whose anchor, per the resolver, matches,
+/// with the blocks up to the next heading of the same or a higher level, or
+/// the paragraph ending in the block anchor `^id`, with its list item if it
+/// starts one.
+fn select_section(root: Node<'_>, fragment: &str, resolver: &dyn IncludeResolver) -> Option<()> {
+    if let Some(id) = fragment.strip_prefix('^') {
+        let paragraph = root.descendants().find(|n| {
+            let ast = n.data();
+            matches!(ast.value, NodeValue::Paragraph)
+                && ast
+                    .content
+                    .trim_end()
+                    .rsplit_once(' ')
+                    .is_some_and(|(_, anchor)| anchor.strip_prefix('^') == Some(id))
+        })?;
+
+        let mut block = paragraph;
+        if paragraph.previous_sibling().is_none() {
+            if let Some(item) = paragraph.parent().filter(|p| {
+                matches!(
+                    p.data().value,
+                    NodeValue::Item(..) | NodeValue::TaskItem(..)
+                )
+            }) {
+                for sibling in item.preceding_siblings().skip(1).collect::<Vec<_>>() {
+                    sibling.detach();
+                }
+                for sibling in item.following_siblings().skip(1).collect::<Vec<_>>() {
+                    sibling.detach();
+                }
+                block = item.parent().unwrap();
+            }
+        }
+        let top = block
+            .ancestors()
+            .find(|n| n.parent().is_some_and(|p| p.same_node(root)))?;
+        for child in root.children().collect::<Vec<_>>() {
+            if !child.same_node(top) {
+                child.detach();
+            }
+        }
+        return Some(());
+    }
+
+    let anchor = resolver.anchorize(fragment);
+    let heading_level = |n: Node<'_>| match n.data().value {
+        NodeValue::Heading(ref nh) => Some(nh.level),
+        _ => None,
+    };
+    let heading = root.children().find(|n| {
+        heading_level(*n).is_some() && resolver.anchorize(n.data().content.trim()) == anchor
+    })?;
+    let level = heading_level(heading).unwrap();
+
+    let mut in_section = false;
+    for child in root.children().collect::<Vec<_>>() {
+        if child.same_node(heading) {
+            in_section = true;
+        } else if heading_level(child).is_some_and(|l| l <= level) {
+            in_section = false;
+        }
+        if !in_section {
+            child.detach();
+        }
+    }
+    Some(())
+}
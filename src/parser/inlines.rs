@@ -10,17 +10,19 @@ use rustc_hash::FxHashMap;
 use smallvec::SmallVec;
 
 use crate::Arena;
+use crate::crossref;
 use crate::ctype::{isdigit, ispunct, isspace};
 use crate::entity;
+use crate::html::Anchorizer;
 use crate::nodes::{
-    Ast, Node, NodeCode, NodeFootnoteDefinition, NodeFootnoteReference, NodeLink, NodeMath,
-    NodeValue, NodeWikiLink, Sourcepos,
+    Ast, Node, NodeCode, NodeEquation, NodeFootnoteDefinition, NodeFootnoteReference, NodeLink,
+    NodeMath, NodeValue, NodeWikiLink, Sourcepos,
 };
 use crate::parser::inlines::cjk::FlankingCheckHelper;
-use crate::parser::options::{BrokenLinkReference, WikiLinksMode};
+use crate::parser::options::{BrokenLinkReference, SmartPunctuation, WikiLinksMode};
 #[cfg(feature = "shortcodes")]
 use crate::parser::shortcodes::NodeShortCode;
-use crate::parser::{AutolinkType, Options, ResolvedReference, autolink};
+use crate::parser::{AutolinkType, Options, ResolvedReference, autolink, typographer};
 use crate::scanners;
 use crate::strings::{self, Case, count_newlines, is_blank};
 
@@ -51,6 +53,7 @@ pub struct Subject<'a: 'd, 'r, 'o, 'd, 'c, 'p> {
     special_char_bytes: [bool; 256],
     skip_char_bytes: [bool; 256],
     emph_delim_bytes: [bool; 256],
+    smart_punctuation: SmartPunctuation,
 }
 
 #[derive(Default)]
@@ -71,6 +74,7 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
         footnote_defs: &'p mut FootnoteDefs<'a>,
         delimiter_arena: &'d typed_arena::Arena<Delimiter<'a, 'd>>,
         inline_footnote_depth: usize,
+        smart_punctuation: SmartPunctuation,
     ) -> Self {
         let mut s = Subject {
             arena,
@@ -94,6 +98,7 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
             special_char_bytes: [false; 256],
             skip_char_bytes: [false; 256],
             emph_delim_bytes: [false; 256],
+            smart_punctuation,
         };
         for &b in b"\n\r_*\"`\\&<[]!$" {
             s.special_char_bytes[b as usize] = true;
@@ -212,6 +217,13 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
         let adjusted_line = self.line - ast.sourcepos.start.line;
         self.line_offset = ast.line_offsets[adjusted_line];
 
+        if self.options.parse.typographer && typographer::can_start(b) {
+            if let Some(inl) = self.handle_typographic() {
+                node.append(inl);
+                return true;
+            }
+        }
+
         let new_inl: Option<Node<'a>> = match b {
             b'\r' | b'\n' => Some(self.handle_newline()),
             b'`' => Some(self.handle_backticks(&ast.line_offsets)),
@@ -224,7 +236,14 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
             b'{' => {
                 let mut res = None;
 
-                if self.options.extension.phoenix_heex {
+                // A label directly following an image makes a figure of it.
+                let figure_label = self.options.extension.cross_references
+                    && self.input[self.scanner.pos..].starts_with("{#")
+                    && node
+                        .last_child()
+                        .is_some_and(|n| matches!(n.data().value, NodeValue::Image(..)));
+
+                if self.options.extension.phoenix_heex && !figure_label {
                     res = self.handle_heex_inline_expression(&ast.line_offsets);
                 }
 
@@ -287,7 +306,7 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
                     && !self.within_brackets
                     && self.peek_byte() == Some(b'[')
                 {
-                    wikilink_inl = self.handle_wikilink();
+                    wikilink_inl = self.handle_wikilink(false);
                 }
 
                 if wikilink_inl.is_none() {
@@ -310,7 +329,23 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
             }
             b'!' => {
                 self.scanner.pos += 1;
-                if self.peek_byte() == Some(b'[') && self.peek_byte_n(1) != Some(b'^') {
+
+                let mut embed_inl = None;
+                if self.options.extension.wikilinks().is_some()
+                    && !self.within_brackets
+                    && self.peek_byte() == Some(b'[')
+                    && self.peek_byte_n(1) == Some(b'[')
+                {
+                    self.scanner.pos += 1;
+                    embed_inl = self.handle_wikilink(true);
+                    if embed_inl.is_none() {
+                        self.scanner.pos -= 1;
+                    }
+                }
+
+                if embed_inl.is_some() {
+                    embed_inl
+                } else if self.peek_byte() == Some(b'[') && self.peek_byte_n(1) != Some(b'^') {
                     self.scanner.pos += 1;
                     let inl = self.make_inline(
                         NodeValue::Text("![".into()),
@@ -357,6 +392,14 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
             _ => {
                 let mut endpos = self.find_special_char();
                 let startpos = self.scanner.pos;
+                if self.options.parse.typographer {
+                    // Stop short of any replacement, which is handled by the
+                    // next call.
+                    if let Some(pos) = typographer::find_replacement(&self.input, startpos, endpos)
+                    {
+                        endpos = pos;
+                    }
+                }
                 self.scanner.pos = endpos;
 
                 let mut contents: Cow<str> = if endpos == self.input.len() {
@@ -515,8 +558,9 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
 
         if self.options.parse.smart && self.peek_byte() == Some(b'<') {
             self.scanner.pos += 1;
+            let (open, _) = self.smart_punctuation.guillemets();
             return self.make_inline(
-                NodeValue::Text("\u{ab}".into()),
+                NodeValue::Text(open.into()),
                 self.scanner.pos - 2,
                 self.scanner.pos - 1,
             );
@@ -730,11 +774,8 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
         let contents: Cow<'static, str> = if b == b'\'' && self.options.parse.smart {
             "’".into()
         } else if b == b'"' && self.options.parse.smart {
-            if can_close {
-                "”".into()
-            } else {
-                "“".into()
-            }
+            let (open, close) = self.smart_punctuation.double_quotes();
+            if can_close { close.into() } else { open.into() }
         } else {
             self.input[self.scanner.pos - numdelims..self.scanner.pos]
                 .to_string()
@@ -862,12 +903,23 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
         }
     }
 
+    fn handle_typographic(&mut self) -> Option<Node<'a>> {
+        let (len, typographic) = typographer::replacement_at(&self.input, self.scanner.pos)?;
+        self.scanner.pos += len;
+        Some(self.make_inline(
+            NodeValue::Typographic(typographic),
+            self.scanner.pos - len,
+            self.scanner.pos - 1,
+        ))
+    }
+
     fn handle_guillemet_close(&mut self) -> Node<'a> {
         self.scanner.pos += 1;
         if self.peek_byte() == Some(b'>') {
             self.scanner.pos += 1;
+            let (_, close) = self.smart_punctuation.guillemets();
             self.make_inline(
-                NodeValue::Text("\u{bb}".into()),
+                NodeValue::Text(close.into()),
                 self.scanner.pos - 2,
                 self.scanner.pos - 1,
             )
@@ -883,30 +935,99 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
     // Handles wikilink syntax
     //   [[link text|url]]
     //   [[url|link text]]
-    fn handle_wikilink(&mut self) -> Option<Node<'a>> {
+    // and embeds, whose link text may instead be a size
+    //   ![[url|300x200]]
+    fn handle_wikilink(&mut self, embed: bool) -> Option<Node<'a>> {
         let startpos = self.scanner.pos;
         let component = self.wikilink_url_link_label()?;
         let url_clean = strings::clean_url(&component.url);
-        let (link_label, link_label_start_column, _link_label_end_column): (Cow<'_, str>, _, _) =
-            match &component.link_label {
-                Some((label, sc, ec)) => (entity::unescape_html(label), *sc, *ec),
-                None => (
-                    entity::unescape_html(&component.url),
-                    startpos + 1,
-                    self.scanner.pos - 3,
-                ),
-            };
-
-        let nl = NodeWikiLink {
-            url: url_clean.into(),
+        let size = match &component.link_label {
+            Some((label, _, _)) if embed => parse_embed_size(label),
+            _ => None,
+        };
+        let (link_label, link_label_start_column): (Cow<'_, str>, _) = match &component.link_label {
+            Some((label, sc, _)) if size.is_none() => (entity::unescape_html(label), *sc),
+            _ => (entity::unescape_html(&component.url), component.url_start),
         };
-        let inl = self.make_inline(NodeValue::WikiLink(nl), startpos - 1, self.scanner.pos - 1);
+
+        let mut nl = self.resolve_wikilink(url_clean.into());
+        nl.embed = embed;
+        if let Some((width, height)) = size {
+            nl.width = Some(width);
+            nl.height = height;
+        }
+        let start = if embed { startpos - 2 } else { startpos - 1 };
+        let inl = self.make_inline(
+            NodeValue::WikiLink(Box::new(nl)),
+            start,
+            self.scanner.pos - 1,
+        );
 
         self.label_backslash_escapes(inl, &link_label, link_label_start_column);
 
         Some(inl)
     }
 
+    // Splits a wikilink's target into its page, and any heading or block, and
+    // resolves the page per the wikilink_resolver option.  Each part is
+    // percent-decoded, as the CommonMark formatter encodes spaces.
+    fn resolve_wikilink(&self, url: String) -> NodeWikiLink {
+        let decode = |s: &str| strings::percent_decode(s).into_owned();
+        let (page, fragment) = match url.split_once('#') {
+            Some((page, fragment)) => (decode(page), Some(fragment)),
+            None => (decode(&url), None),
+        };
+        let (heading, block) = match fragment {
+            Some(fragment) => match fragment.strip_prefix('^') {
+                Some(block) => (None, Some(decode(block))),
+                None => (Some(decode(fragment)), None),
+            },
+            None => (None, None),
+        };
+        let mut nl = NodeWikiLink {
+            url,
+            page,
+            heading,
+            block,
+            missing: false,
+            embed: false,
+            width: None,
+            height: None,
+        };
+
+        let resolver = self.options.parse.wikilink_resolver.as_ref();
+        let mut url = match resolver {
+            // Without a resolver, the page is linked to as written.
+            None => nl.url.split('#').next().unwrap_or_default().to_string(),
+            Some(_) if nl.page.is_empty() => String::new(),
+            Some(resolver) => match resolver.resolve(&nl.page) {
+                Some(resolved) => {
+                    nl.missing = !resolved.exists;
+                    resolved.url
+                }
+                None => return nl,
+            },
+        };
+
+        if let Some(block) = &nl.block {
+            url.push('#');
+            url.push_str(block);
+        } else if let Some(heading) = &nl.heading {
+            url.push('#');
+            if self.options.extension.header_id_prefix_in_href {
+                if let Some(prefix) = &self.options.extension.header_id_prefix {
+                    url.push_str(prefix);
+                }
+            }
+            match resolver {
+                Some(resolver) => url.push_str(&resolver.anchorize(heading)),
+                None => url.push_str(&Anchorizer::new().anchorize(heading)),
+            }
+        }
+        nl.url = url;
+        nl
+    }
+
     fn wikilink_url_link_label(&mut self) -> Option<WikilinkComponents> {
         let left_startpos = self.scanner.pos;
 
@@ -928,6 +1049,7 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
             self.scanner.pos += 2;
             return Some(WikilinkComponents {
                 url: left,
+                url_start: left_startpos + 1,
                 link_label: None,
             });
         } else if self.peek_byte() != Some(b'|') {
@@ -951,10 +1073,12 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
             match self.options.extension.wikilinks() {
                 Some(WikiLinksMode::UrlFirst) => Some(WikilinkComponents {
                     url: left,
+                    url_start: left_startpos + 1,
                     link_label: Some((right.into(), right_startpos + 1, self.scanner.pos - 3)),
                 }),
                 Some(WikiLinksMode::TitleFirst) => Some(WikilinkComponents {
                     url: right.into(),
+                    url_start: right_startpos + 1,
                     link_label: Some((left, left_startpos + 1, right_startpos - 1)),
                 }),
                 None => unreachable!(),
@@ -1162,6 +1286,7 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
             self.footnote_defs,
             &delimiter_arena,
             self.inline_footnote_depth + 1,
+            self.smart_punctuation,
         );
 
         while subj.parse_inline(para_node, &mut para_node.data_mut()) {}
@@ -1242,6 +1367,19 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
                 fence_length,
                 parent_line_offsets,
             );
+            if opendollars == 2 && self.options.extension.math_labels {
+                if let Some(label) = self.scan_math_label() {
+                    let mut sourcepos = node.data().sourcepos;
+                    sourcepos.end.column += self.scanner.pos - endpos;
+                    let equation = make_inline(
+                        self.arena,
+                        NodeValue::Equation(Box::new(NodeEquation { label, number: 0 })),
+                        sourcepos,
+                    );
+                    equation.append(node);
+                    return equation;
+                }
+            }
             node
         } else if code_math {
             self.scanner.pos = startpos + 1;
@@ -1260,6 +1398,20 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
         }
     }
 
+    // Scans a `{#label}` following display math, consuming it if found.
+    fn scan_math_label(&mut self) -> Option<String> {
+        let rest = &self.input[self.scanner.pos..];
+        let trimmed = rest.trim_start_matches([' ', '\t']);
+        let label = trimmed.strip_prefix("{#")?;
+        let len = label.find('}')?;
+        let label = &label[..len];
+        if label.is_empty() || !label.bytes().all(crossref::is_label_byte) {
+            return None;
+        }
+        self.scanner.pos += rest.len() - trimmed.len() + len + 3;
+        Some(label.to_string())
+    }
+
     /////////////////////////////////////
     // Emphasis and bracket processing //
     /////////////////////////////////////
@@ -1417,18 +1569,22 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
                         closer = c.next.get();
                     }
                 } else if c.delim_byte == b'\'' || c.delim_byte == b'"' {
-                    *c.inl.data_mut().value.text_mut().unwrap() =
-                        if c.delim_byte == b'\'' { "’" } else { "”" }.into();
+                    let (open, close) = if c.delim_byte == b'\'' {
+                        self.smart_punctuation.single_quotes()
+                    } else {
+                        self.smart_punctuation.double_quotes()
+                    };
+                    // An unpaired single quote is an apostrophe.
+                    let close = if c.delim_byte == b'\'' && !opener_found {
+                        "’"
+                    } else {
+                        close
+                    };
+                    *c.inl.data_mut().value.text_mut().unwrap() = close.into();
                     closer = c.next.get();
 
                     if opener_found {
-                        *opener.unwrap().inl.data_mut().value.text_mut().unwrap() =
-                            if old_c.delim_byte == b'\'' {
-                                "‘"
-                            } else {
-                                "“"
-                            }
-                            .into();
+                        *opener.unwrap().inl.data_mut().value.text_mut().unwrap() = open.into();
                         self.remove_delimiter(opener.unwrap());
                         self.remove_delimiter(old_c);
                     }
@@ -1795,7 +1951,12 @@ impl<'a, 'r, 'o, 'd, 'c, 'p> Subject<'a, 'r, 'o, 'd, 'c, 'p> {
 
         // Attempt to use the provided broken link callback if a reference cannot be resolved
         // Only clone the original label if we actually need to call the callback
-        if reff.is_none() {
+        // Cross-references are resolved after parsing, once all labels are known
+        let cross_reference = self.options.extension.cross_references
+            && lab.strip_prefix('@').is_some_and(|label| {
+                !label.is_empty() && label.bytes().all(crossref::is_label_byte)
+            });
+        if reff.is_none() && !cross_reference {
             if let Some(callback) = &self.options.parse.broken_link_callback {
                 reff = callback
                     .resolve(BrokenLinkReference {
@@ -2412,9 +2573,25 @@ struct Bracket<'a> {
 #[derive(Clone)]
 struct WikilinkComponents {
     url: String,
+    url_start: usize,
     link_label: Option<(String, usize, usize)>,
 }
 
+// Parses the size of an embed: a width, as in `300`, or a width and height, as
+// in `300x200`.
+fn parse_embed_size(size: &str) -> Option<(u32, Option<u32>)> {
+    let dimension = |d: &str| {
+        if d.is_empty() || !d.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        d.parse().ok()
+    };
+    match size.split_once('x') {
+        Some((width, height)) => Some((dimension(width)?, Some(dimension(height)?))),
+        None => Some((dimension(size)?, None)),
+    }
+}
+
 pub(crate) fn manual_scan_link_url(input: &str) -> Option<(&str, usize)> {
     let bytes = input.as_bytes();
     let len = input.len();
@@ -1,11 +1,14 @@
 mod autolink;
-mod inlines;
+mod grid_table;
+mod include;
+pub(crate) mod inlines;
 pub mod options;
 #[cfg(feature = "phoenix_heex")]
 pub mod phoenix_heex;
 #[cfg(feature = "shortcodes")]
 pub mod shortcodes;
 mod table;
+pub(crate) mod typographer;
 
 use std::borrow::Cow;
 use std::cmp::{Ordering, min};
@@ -21,10 +24,11 @@ use crate::node_matches;
 use crate::nodes::{
     self, AlertType, Ast, ListDelimType, ListType, Node, NodeAlert, NodeBlockDirective,
     NodeCodeBlock, NodeDescriptionItem, NodeFootnoteDefinition, NodeHeading, NodeHtmlBlock,
-    NodeList, NodeMultilineBlockQuote, NodeTaskItem, NodeValue, Sourcepos,
+    NodeList, NodeMultilineBlockQuote, NodeTable, NodeTaskItem, NodeValue, Sourcepos,
 };
 use crate::parser::inlines::RefMap;
 pub use crate::parser::options::Options;
+use crate::parser::options::SmartPunctuation;
 use crate::scanners;
 use crate::strings::{self, Case, split_off_front_matter};
 
@@ -53,18 +57,24 @@ pub fn parse_document<'a>(arena: &'a Arena<'a>, md: &str, options: &Options) ->
         }
         .into(),
     );
-    let document = Parser::new(arena, root, options).parse(md);
+    let mut parser = Parser::new(arena, root, options);
+    let document = parser.parse(md);
     if options.parse.sourcepos_chars {
-        convert_sourcepos_columns_to_chars(document, md);
+        convert_sourcepos_columns_to_chars(document, md, &parser.included);
     }
     document
 }
 
 /// Convert all byte-based column values in the AST's sourcepos to char-based.
-fn convert_sourcepos_columns_to_chars(document: Node<'_>, md: &str) {
-    let lines: Vec<&str> = md.lines().collect();
-
-    let convert = |lc: &mut nodes::LineColumn| {
+///
+/// The descendants of each of the `included` nodes are converted against the
+/// content of the document it includes.
+fn convert_sourcepos_columns_to_chars<'a>(
+    document: Node<'a>,
+    md: &str,
+    included: &[(Node<'a>, String)],
+) {
+    let convert = |lines: &[&str], lc: &mut nodes::LineColumn| {
         if lc.column == 0 {
             return;
         }
@@ -73,10 +83,23 @@ fn convert_sourcepos_columns_to_chars(document: Node<'_>, md: &str) {
         }
     };
 
-    for node in document.descendants() {
-        let mut ast = node.data_mut();
-        convert(&mut ast.sourcepos.start);
-        convert(&mut ast.sourcepos.end);
+    let lines: Vec<&str> = md.lines().collect();
+    let included: Vec<(Node<'a>, Vec<&str>)> = included
+        .iter()
+        .map(|(node, content)| (*node, content.lines().collect()))
+        .collect();
+
+    let mut stack = vec![(document, &lines)];
+    while let Some((node, mut lines)) = stack.pop() {
+        {
+            let mut ast = node.data_mut();
+            convert(lines, &mut ast.sourcepos.start);
+            convert(lines, &mut ast.sourcepos.end);
+        }
+        if let Some((_, included_lines)) = included.iter().find(|(n, _)| n.same_node(node)) {
+            lines = included_lines;
+        }
+        stack.extend(node.children().map(|child| (child, lines)));
     }
 }
 
@@ -128,6 +151,14 @@ pub struct Parser<'a, 'o, 'c> {
     curline_end_col: usize,
     last_line_length: usize,
     total_size: usize,
+    smart_punctuation: SmartPunctuation,
+    table_state: table::TableState<'a>,
+    /// The resolved paths of this document, if known, and of the documents
+    /// including it, innermost last.
+    include_stack: Vec<String>,
+    /// Include nodes and the content of the documents they include, for
+    /// converting source positions within them.
+    included: Vec<(Node<'a>, String)>,
     #[cfg(feature = "phoenix_heex")]
     heex_block_depth: usize,
 }
@@ -174,12 +205,16 @@ where
             curline_end_col: 0,
             last_line_length: 0,
             total_size: 0,
+            smart_punctuation: options.parse.smart_punctuation,
+            table_state: table::TableState::default(),
+            include_stack: options.parse.document_path.iter().cloned().collect(),
+            included: Vec::new(),
             #[cfg(feature = "phoenix_heex")]
             heex_block_depth: 0,
         }
     }
 
-    fn parse(mut self, mut s: &str) -> Node<'a> {
+    fn parse(&mut self, mut s: &str) -> Node<'a> {
         if let Some(delimiter) = &self.options.extension.front_matter_delimiter {
             if let Some((front_matter, rest)) = split_off_front_matter(s, delimiter) {
                 self.handle_front_matter(front_matter, delimiter);
@@ -219,10 +254,19 @@ where
 
         self.finalize_document();
         self.postprocess_text_nodes(self.root);
+        if self.options.extension.block_anchors {
+            self.process_block_anchors();
+        }
         self.root
     }
 
     fn handle_front_matter(&mut self, front_matter: &str, delimiter: &str) {
+        if let Some(locale) = strings::front_matter_value(front_matter, "lang") {
+            if let Some(smart_punctuation) = SmartPunctuation::from_locale(locale) {
+                self.smart_punctuation = smart_punctuation;
+            }
+        }
+
         let lines = front_matter
             .as_bytes()
             .iter()
@@ -358,15 +402,23 @@ where
                         break;
                     }
                 }
-                NodeValue::Table(..) => {
-                    if !table::matches(&line[self.first_nonspace..], self.options.extension.spoiler)
-                    {
+                NodeValue::Table(ref nt)
+                    if nt.grid && !grid_table::matches(&line[self.first_nonspace..]) =>
+                {
+                    break;
+                }
+                NodeValue::Table(ref nt) if !nt.grid => {
+                    if !table::matches(
+                        &line[self.first_nonspace..],
+                        self.options.extension.spoiler,
+                        self.options.extension.extended_tables,
+                    ) {
                         break;
                     }
                 }
                 NodeValue::Heading(..)
                 | NodeValue::TableRow(..)
-                | NodeValue::TableCell
+                | NodeValue::TableCell(..)
                 | NodeValue::Subtext => {
                     break;
                 }
@@ -741,16 +793,18 @@ where
         while !node_matches!(
             container,
             NodeValue::CodeBlock(..) | NodeValue::HtmlBlock(..)
-        ) && !{
-            #[cfg(feature = "phoenix_heex")]
-            {
-                node_matches!(container, NodeValue::HeexBlock(..))
-            }
-            #[cfg(not(feature = "phoenix_heex"))]
-            {
-                false
+        ) && !matches!(container.data().value, NodeValue::Table(ref nt) if nt.grid)
+            && !{
+                #[cfg(feature = "phoenix_heex")]
+                {
+                    node_matches!(container, NodeValue::HeexBlock(..))
+                }
+                #[cfg(not(feature = "phoenix_heex"))]
+                {
+                    false
+                }
             }
-        } {
+        {
             depth += 1;
             self.find_first_nonspace(line);
             let indented = self.indent >= CODE_INDENT;
@@ -770,7 +824,8 @@ where
                     || self.handle_setext_heading(container, line)
                     || self.handle_thematic_break(container, line, all_matched)
                     || self.handle_footnote(container, line, depth)
-                    || self.handle_description_list(container, line)))
+                    || self.handle_description_list(container, line)
+                    || self.handle_grid_table(container, line)))
                 || self.handle_list(container, line, indented, depth)
                 || self.handle_code_block(container, line, indented, maybe_lazy)
                 || self.handle_table(container, line, indented))
@@ -974,6 +1029,7 @@ where
             level,
             setext: false,
             closed: false,
+            id: None,
         });
 
         true
@@ -1372,6 +1428,7 @@ where
                 },
                 setext: true,
                 closed: false,
+                id: None,
             });
             let adv = line.len() - strings::newlines_of(line) - self.offset;
             self.advance_offset(line, adv, false);
@@ -1754,6 +1811,30 @@ where
         true
     }
 
+    fn handle_grid_table(&mut self, container: &mut Node<'a>, line: &str) -> bool {
+        if !self.detect_grid_table(container, line) {
+            return false;
+        }
+
+        let offset = self.first_nonspace + 1;
+        *container = self.add_child(
+            container,
+            NodeValue::Table(Box::new(NodeTable {
+                grid: true,
+                ..Default::default()
+            })),
+            offset,
+        );
+
+        true
+    }
+
+    fn detect_grid_table(&self, container: Node<'a>, line: &str) -> bool {
+        self.options.extension.grid_tables
+            && !node_matches!(container, NodeValue::Paragraph)
+            && grid_table::is_border(&line[self.first_nonspace..])
+    }
+
     fn detect_table(
         &mut self,
         container: Node<'a>,
@@ -2016,10 +2097,22 @@ where
 
         self.finalize(self.root);
 
+        if self.options.extension.grid_tables {
+            grid_table::process_tables(self, self.root);
+        }
+
+        if self.options.extension.table_captions {
+            table::process_captions(self.root);
+        }
+
         self.refmap.max_ref_size = self.total_size.min(100000);
 
         self.process_inlines();
 
+        if self.options.extension.math_labels || self.options.extension.cross_references {
+            crate::crossref::process(self.arena, self.root, self.options);
+        }
+
         if self.options.extension.footnotes {
             // Append auto-generated inline footnote definitions
             if self.options.extension.inline_footnotes {
@@ -2120,8 +2213,9 @@ where
             ast.sourcepos.end = (self.line_number, self.curline_end_col).into();
         } else if matches!(
             ast.value,
-            NodeValue::ThematicBreak | NodeValue::TableRow(..) | NodeValue::Table(..)
-        ) {
+            NodeValue::ThematicBreak | NodeValue::TableRow(..)
+        ) || matches!(ast.value, NodeValue::Table(ref nt) if !nt.grid)
+        {
             // sourcepos.end set by itself or managed below.
         } else {
             ast.sourcepos.end = (self.line_number - 1, self.last_line_length).into();
@@ -2138,8 +2232,21 @@ where
                 let has_content = self.resolve_reference_link_definitions(content);
                 if !has_content {
                     node.detach();
+                } else if self.options.extension.table_of_contents
+                    && matches!(strings::trim_slice(content), "[[_TOC_]]" | "[TOC]")
+                {
+                    content.clear();
+                    ast.value = NodeValue::TableOfContents;
+                } else if let Some(ni) = include::resolve(self, node, content) {
+                    content.clear();
+                    ast.value = NodeValue::Include(Box::new(ni));
                 }
             }
+            NodeValue::Table(ref nt) if !nt.grid && node.first_child().is_none() => {
+                // A headerless table's delimiter row with no rows following
+                // is just text.
+                ast.value = NodeValue::Paragraph;
+            }
             NodeValue::CodeBlock(ref mut ncb) => {
                 if !ncb.fenced {
                     strings::remove_trailing_blank_lines(content);
@@ -2157,16 +2264,6 @@ where
                     strings::trim_cow(&mut info);
                     let mut info = info.into_owned();
                     strings::unescape(&mut info);
-                    if info.is_empty() {
-                        ncb.info = self
-                            .options
-                            .parse
-                            .default_info_string
-                            .as_ref()
-                            .map_or(info, |s| s.clone());
-                    } else {
-                        ncb.info = info;
-                    }
 
                     if content.as_bytes().get(pos) == Some(&b'\r') {
                         pos += 1;
@@ -2176,6 +2273,19 @@ where
                     }
 
                     strings::remove_from_start(content, pos);
+
+                    if info.is_empty() {
+                        ncb.info = self
+                            .options
+                            .parse
+                            .language_guesser
+                            .as_ref()
+                            .and_then(|guesser| guesser.guess(content))
+                            .or_else(|| self.options.parse.default_info_string.clone())
+                            .unwrap_or(info);
+                    } else {
+                        ncb.info = info;
+                    }
                 }
                 mem::swap(&mut ncb.literal, content);
             }
@@ -2209,6 +2319,13 @@ where
                     ast.sourcepos.end = candidate_end;
                 }
             }
+            NodeValue::Heading(ref mut nh) if self.options.extension.header_custom_ids => {
+                if let Some((rest, id)) = strings::chop_trailing_custom_id(content) {
+                    nh.id = Some(id.to_string());
+                    let len = rest.len();
+                    content.truncate(len);
+                }
+            }
             _ => (),
         }
 
@@ -2265,6 +2382,7 @@ where
             &mut self.footnote_defs,
             &delimiter_arena,
             0,
+            self.smart_punctuation,
         );
 
         while subj.parse_inline(node, &mut node_data) {}
@@ -2393,6 +2511,47 @@ where
         }
     }
 
+    // Splits a ` ^block-id` off the end of each paragraph into a block anchor.
+    fn process_block_anchors(&mut self) {
+        let paragraphs = self
+            .root
+            .descendants()
+            .filter(|n| node_matches!(n, NodeValue::Paragraph))
+            .collect::<Vec<_>>();
+
+        for paragraph in paragraphs {
+            let Some(text) = paragraph.last_child() else {
+                continue;
+            };
+            let mut ast = text.data_mut();
+            let NodeValue::Text(ref mut literal) = ast.value else {
+                continue;
+            };
+            let Some((before, id)) = split_block_anchor(literal) else {
+                continue;
+            };
+            let (kept, id) = (before.len(), id.to_string());
+            let removed = literal.len() - kept;
+            literal.to_mut().truncate(kept);
+            let emptied = literal.is_empty();
+
+            let end = ast.sourcepos.end;
+            ast.sourcepos.end.column = end.column.saturating_sub(removed);
+            drop(ast);
+
+            let start_column = (end.column + 1).saturating_sub(id.len() + 1);
+            let sourcepos = (end.line, start_column, end.line, end.column).into();
+            paragraph.append(inlines::make_inline(
+                self.arena,
+                NodeValue::BlockAnchor(id),
+                sourcepos,
+            ));
+            if emptied {
+                text.detach();
+            }
+        }
+    }
+
     fn postprocess_text_nodes(&mut self, root: Node<'a>) {
         let mut stack = vec![(root, false)];
         let mut children = vec![];
@@ -2548,6 +2707,15 @@ where
                 &mut spx,
             );
         }
+
+        // Last, as this changes byte lengths, which the above rely on to
+        // compute sourcepos.  Any text split off by autolinks is processed
+        // separately.
+        if self.options.parse.smart && self.smart_punctuation.french_spacing() {
+            if let Some(spaced) = strings::french_spacing(text) {
+                *text = spaced.into();
+            }
+        }
     }
 
     // Processes tasklist items in a text node.  This function
@@ -2593,7 +2761,7 @@ where
 
         let parent = node.parent().unwrap();
 
-        if node_matches!(parent, NodeValue::TableCell) {
+        if node_matches!(parent, NodeValue::TableCell(..)) {
             if !self.options.parse.tasklist_in_table {
                 return;
             }
@@ -2859,6 +3027,17 @@ fn parse_list_marker(
     None
 }
 
+/// Splits text ending in a block anchor, like `Some text ^block-id`, into the
+/// text before the anchor, without trailing whitespace, and the anchor's ID.
+fn split_block_anchor(text: &str) -> Option<(&str, &str)> {
+    let (before, id) = text.rsplit_once(' ')?;
+    let id = id.strip_prefix('^')?;
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+        return None;
+    }
+    Some((before.trim_end(), id))
+}
+
 fn lists_match(list_data: &NodeList, item_data: &NodeList) -> bool {
     list_data.list_type == item_data.list_type
         && list_data.delimiter == item_data.delimiter
@@ -8,7 +8,12 @@ use std::panic::RefUnwindSafe;
 use std::str;
 use std::sync::Arc;
 
-use crate::adapters::{CodefenceRendererAdapter, HeadingAdapter, SyntaxHighlighterAdapter};
+use crate::adapters::{
+    AnchorizerAdapter, CodefenceRendererAdapter, FootnoteAdapter, HeadingAdapter,
+    MathRendererAdapter, SyntaxHighlighterAdapter,
+};
+use crate::crossref::{CrossReferences, Prefixes};
+use crate::html::Anchorizer;
 use crate::parser::ResolvedReference;
 
 #[derive(Default, Debug, Clone)]
@@ -73,6 +78,68 @@ pub struct Extension<'c> {
     #[cfg_attr(feature = "bon", builder(default))]
     pub table: bool,
 
+    /// Enables extended table syntax, as in MultiMarkdown.  Requires
+    /// [`table`](#structfield.table).
+    ///
+    /// * A cell followed directly by `||` spans the next column too.
+    /// * A cell containing only `^^` joins the cell above, which spans the
+    ///   row too.
+    /// * Every row before the delimiter row is a header row, and a table may
+    ///   start with the delimiter row to have no header at all.
+    /// * A row ending in a `\` after its last pipe continues on the next
+    ///   line, whose cells are joined to those above.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.table = true;
+    /// options.extension.extended_tables = true;
+    /// assert_eq!(markdown_to_html("| a | b |\n|---|---|\n| c ||\n| d | e |\n| ^^ | f |\n", &options),
+    ///            "<table>\n<thead>\n<tr>\n<th>a</th>\n<th>b</th>\n</tr>\n</thead>\n\
+    ///             <tbody>\n<tr>\n<td colspan=\"2\">c</td>\n</tr>\n\
+    ///             <tr>\n<td rowspan=\"2\">d</td>\n<td>e</td>\n</tr>\n\
+    ///             <tr>\n<td>f</td>\n</tr>\n</tbody>\n</table>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub extended_tables: bool,
+
+    /// Enables grid tables, as in Pandoc and reStructuredText.  Rows are
+    /// separated by `+---+` borders, a `+===+` border ends the header rows,
+    /// and each cell may contain any blocks, such as lists, code blocks and
+    /// several paragraphs.  Colons in the header border (or the top border
+    /// of a table without header rows) set the column alignments.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.grid_tables = true;
+    /// assert_eq!(markdown_to_html("+---+-----+\n| a | b   |\n+===+=====+\n| c | - d |\n|   | - e |\n+---+-----+\n", &options),
+    ///            "<table>\n<thead>\n<tr>\n<th>a</th>\n<th>b</th>\n</tr>\n</thead>\n\
+    ///             <tbody>\n<tr>\n<td>c</td>\n<td>\n<ul>\n<li>d</li>\n<li>e</li>\n</ul>\n</td>\n\
+    ///             </tr>\n</tbody>\n</table>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub grid_tables: bool,
+
+    /// Enables table captions.  A paragraph starting with `Table:` (or `:`)
+    /// just before or after a table, as in Pandoc, or a lone `[Caption]` line
+    /// directly above or below it, as in MultiMarkdown, becomes its caption.
+    /// An ID may follow the caption as `{#id}`, or as `[Caption][id]`.  The
+    /// caption is a [`TableCaption`](crate::nodes::NodeValue::TableCaption)
+    /// node holding its inlines, as the first child of the table.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.table = true;
+    /// options.extension.table_captions = true;
+    /// assert_eq!(markdown_to_html("| a |\n|---|\n\nTable: Letters {#tbl-letters}\n", &options),
+    ///            "<table id=\"tbl-letters\">\n<caption>Letters</caption>\n\
+    ///             <thead>\n<tr>\n<th>a</th>\n</tr>\n</thead>\n</table>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub table_captions: bool,
+
     /// Enables the [autolink extension](https://github.github.com/gfm/#autolinks-extension-)
     /// from the GFM spec.
     ///
@@ -155,6 +222,37 @@ pub struct Extension<'c> {
     #[cfg_attr(feature = "bon", builder(default))]
     pub header_id_prefix_in_href: bool,
 
+    /// Enables explicit heading IDs, given as a trailing `{#id}` on the
+    /// heading line.
+    ///
+    /// The explicit ID replaces the generated one, and an anchor is rendered
+    /// for such headings even if [`header_id_prefix`](#structfield.header_id_prefix)
+    /// is `None`.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.header_custom_ids = true;
+    /// assert_eq!(markdown_to_html("# Getting started {#setup}\n", &options),
+    ///            "<h1><a href=\"#setup\" aria-hidden=\"true\" class=\"anchor\" id=\"setup\"></a>Getting started</h1>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub header_custom_ids: bool,
+
+    /// Heading IDs which are already in use on the page the document is
+    /// rendered into.  Generated heading IDs are suffixed to avoid them.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.header_id_prefix = Some("".to_string());
+    /// options.extension.header_reserved_ids = vec!["main".to_string()];
+    /// assert_eq!(markdown_to_html("# Main\n", &options),
+    ///            "<h1><a href=\"#main-1\" aria-hidden=\"true\" class=\"anchor\" id=\"main-1\"></a>Main</h1>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub header_reserved_ids: Vec<String>,
+
     /// Enables the footnotes extension per `cmark-gfm`.
     ///
     /// For usage, see `src/tests.rs`.  The extension is modelled after
@@ -345,6 +443,90 @@ pub struct Extension<'c> {
     #[cfg_attr(feature = "bon", builder(default))]
     pub math_code: bool,
 
+    /// Enables labels on display math, which are numbered in document order
+    /// and can be referred to with `@label` or `\eqref{label}`.
+    ///
+    /// Labels follow the closing `$$`, or end the info string of a
+    /// ```` ```math ```` block, and references using `@` must start
+    /// with `eq:`.  `\eqref{energy}` refers to either `energy` or
+    /// `eq:energy`.  References to missing labels are passed to the
+    /// [`broken_link_callback`](Parse::broken_link_callback), if any.
+    ///
+    /// ```markdown
+    /// $$E = mc^2$$ {#eq:energy}
+    ///
+    /// As @eq:energy shows.
+    /// ```
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.math_dollars = true;
+    /// options.extension.math_labels = true;
+    /// assert_eq!(markdown_to_html("$$E = mc^2$$ {#eq:energy}\n\nAs @eq:energy shows.\n", &options),
+    ///            "<p><span class=\"math-equation\" id=\"eq:energy\">\
+    ///             <span data-math-style=\"display\">E = mc^2</span> \
+    ///             <span class=\"math-equation-number\">(1)</span></span></p>\n\
+    ///             <p>As <a href=\"#eq:energy\">(1)</a> shows.</p>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub math_labels: bool,
+
+    /// Enables numbering of labelled figures, tables, sections and code
+    /// listings, and references to them with `[@label]`.
+    ///
+    /// Images are labelled by a `{#label}` directly following them, tables
+    /// by the ID of their caption per
+    /// [`table_captions`](Self::table_captions), sections by the ID of their
+    /// heading per [`header_custom_ids`](Self::header_custom_ids), and fenced
+    /// code blocks by a `{#label}` ending their info string.  Each kind is
+    /// numbered on its own, in document order; sections are numbered as by
+    /// [`sections`](crate::sections::sections).  References read like
+    /// "Figure 1", using [`cross_reference_prefixes`](Self::cross_reference_prefixes),
+    /// and equations labelled per [`math_labels`](Self::math_labels) can be
+    /// referred to likewise.
+    ///
+    /// References to labels not in the document are looked up in
+    /// [`external_cross_references`](Parse::external_cross_references), and
+    /// then, if the label starts with `eq:`, `fig:`, `tbl:`, `sec:` or `lst:`,
+    /// passed to the [`broken_link_callback`](Parse::broken_link_callback).
+    /// Other references are left as text, so as not to catch citations.
+    ///
+    /// ```markdown
+    /// ![Architecture](arch.png){#fig:arch}
+    ///
+    /// See [@fig:arch].
+    /// ```
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.cross_references = true;
+    /// options.render.figure_with_caption = true;
+    /// assert_eq!(markdown_to_html("![Architecture](arch.png \"Overview\"){#fig:arch}\n\nSee [@fig:arch].\n", &options),
+    ///            "<p><figure id=\"fig:arch\"><img src=\"arch.png\" alt=\"Architecture\" title=\"Overview\" />\
+    ///             <figcaption>Figure 1: Overview</figcaption></figure></p>\n\
+    ///             <p>See <a href=\"#fig:arch\">Figure 1</a>.</p>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub cross_references: bool,
+
+    /// The words references to each kind of target are prefixed with, per
+    /// [`cross_references`](Self::cross_references).  Also used in figure
+    /// and table captions.  An empty prefix leaves just the number.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.cross_references = true;
+    /// options.extension.cross_reference_prefixes.figure = "Fig.".to_string();
+    /// assert_eq!(markdown_to_html("![Cat](cat.png){#fig:cat}\n\nSee [@fig:cat].\n", &options),
+    ///            "<p><img id=\"fig:cat\" src=\"cat.png\" alt=\"Cat\" /></p>\n\
+    ///             <p>See <a href=\"#fig:cat\">Fig. 1</a>.</p>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub cross_reference_prefixes: Prefixes,
+
     #[cfg(feature = "shortcodes")]
     #[cfg_attr(docsrs, doc(cfg(feature = "shortcodes")))]
     /// Phrases wrapped inside of ':' blocks will be replaced with emojis.
@@ -627,6 +809,46 @@ pub struct Extension<'c> {
     /// ```
     #[cfg_attr(feature = "bon", builder(default))]
     pub block_directive: bool,
+
+    /// Enables the table of contents placeholder extension.
+    ///
+    /// A paragraph consisting solely of `[[_TOC_]]` or `[TOC]` is replaced
+    /// with a nested list of links to the document's headings.  Anchors are
+    /// generated the same way as for [`header_id_prefix`](#structfield.header_id_prefix);
+    /// when a document has a table of contents, its headings get anchors
+    /// even if that option isn't set.  See
+    /// [`html::toc`](crate::html::toc) to collect the entries programmatically.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.table_of_contents = true;
+    ///
+    /// assert_eq!(markdown_to_html("[TOC]\n\n# Hello\n", &options),
+    ///            "<ul class=\"table-of-contents\">\n\
+    ///             <li><a href=\"#hello\">Hello</a></li>\n\
+    ///             </ul>\n\
+    ///             <h1><a href=\"#hello\" aria-hidden=\"true\" class=\"anchor\" id=\"hello\"></a>Hello</h1>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub table_of_contents: bool,
+
+    /// Enables block anchors, as in Obsidian.
+    ///
+    /// A paragraph or list item ending in ` ^block-id` takes the ID, for
+    /// wikilinks like `[[Page#^block-id]]` to link to.  The ID may contain
+    /// ASCII letters, digits and hyphens.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.block_anchors = true;
+    ///
+    /// assert_eq!(markdown_to_html("A paragraph. ^intro\n\n- An item ^first\n", &options),
+    ///            "<p id=\"intro\">A paragraph.</p>\n<ul>\n<li id=\"first\">An item</li>\n</ul>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub block_anchors: bool,
 }
 
 impl Extension<'_> {
@@ -686,6 +908,98 @@ where
     }
 }
 
+/// The typographic conventions of a locale, followed by
+/// [`smart`](Parse#structfield.smart) punctuation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum SmartPunctuation {
+    /// English: “double” and ‘single’ quotes.
+    #[default]
+    En,
+    /// German: „double“ and ‚single‘ quotes.
+    De,
+    /// French: « double » and ‹ single › quotes, with narrow no-break spaces
+    /// inside them.  An ordinary space before `;`, `!`, `?` or `»` becomes a
+    /// narrow no-break space, one before `:` a no-break space, and one after
+    /// `«` a narrow no-break space.
+    Fr,
+    /// Swiss: «double» and ‹single› quotes.
+    Ch,
+    /// Russian: «double» and „single“ quotes.
+    Ru,
+    /// Japanese: 「double」 and 『single』 quotes.
+    Ja,
+}
+
+impl SmartPunctuation {
+    /// Picks the conventions for a locale such as `de`, `fr-CH` or `ru_RU`,
+    /// returning `None` for unsupported languages.  German, French and Italian
+    /// in Switzerland follow the Swiss conventions.
+    ///
+    /// ```rust
+    /// # use comrak::options::SmartPunctuation;
+    /// assert_eq!(SmartPunctuation::from_locale("de-AT"), Some(SmartPunctuation::De));
+    /// assert_eq!(SmartPunctuation::from_locale("de_CH"), Some(SmartPunctuation::Ch));
+    /// assert_eq!(SmartPunctuation::from_locale("ch"), None);
+    /// assert_eq!(SmartPunctuation::from_locale("pt"), None);
+    /// ```
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let mut parts = locale.split(['-', '_']);
+        let language = parts.next()?.to_ascii_lowercase();
+        let swiss = parts.any(|region| region.eq_ignore_ascii_case("ch"));
+
+        match language.as_str() {
+            "de" | "fr" | "it" if swiss => Some(Self::Ch),
+            "en" => Some(Self::En),
+            "de" => Some(Self::De),
+            "fr" => Some(Self::Fr),
+            "ru" => Some(Self::Ru),
+            "ja" => Some(Self::Ja),
+            _ => None,
+        }
+    }
+
+    /// The opening and closing double quotes.
+    pub fn double_quotes(self) -> (&'static str, &'static str) {
+        match self {
+            Self::En => ("“", "”"),
+            Self::De => ("„", "“"),
+            Self::Fr => ("«\u{202f}", "\u{202f}»"),
+            Self::Ch | Self::Ru => ("«", "»"),
+            Self::Ja => ("「", "」"),
+        }
+    }
+
+    /// The opening and closing single quotes.  An unpaired `'` is always
+    /// rendered as an apostrophe, `’`.
+    pub fn single_quotes(self) -> (&'static str, &'static str) {
+        match self {
+            Self::En => ("‘", "’"),
+            Self::De => ("‚", "‘"),
+            Self::Fr => ("‹\u{202f}", "\u{202f}›"),
+            Self::Ch => ("‹", "›"),
+            Self::Ru => ("„", "“"),
+            Self::Ja => ("『", "』"),
+        }
+    }
+
+    /// The opening and closing guillemets written as `<<` and `>>`.  German
+    /// points them inwards, »like this«.
+    pub fn guillemets(self) -> (&'static str, &'static str) {
+        match self {
+            Self::De => ("»", "«"),
+            Self::Fr => ("«\u{202f}", "\u{202f}»"),
+            Self::En | Self::Ch | Self::Ru | Self::Ja => ("«", "»"),
+        }
+    }
+
+    /// Whether spaces around high punctuation and guillemets are made
+    /// non-breaking.
+    pub fn french_spacing(self) -> bool {
+        self == Self::Fr
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 #[cfg_attr(feature = "bon", derive(Builder))]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
@@ -706,6 +1020,48 @@ pub struct Parse<'c> {
     #[cfg_attr(feature = "bon", builder(default))]
     pub smart: bool,
 
+    /// The typographic conventions followed by [`smart`](#structfield.smart)
+    /// punctuation; see [`SmartPunctuation`].
+    ///
+    /// A document can override this with a `lang` key in its front matter,
+    /// when [`front_matter_delimiter`](Extension#structfield.front_matter_delimiter)
+    /// is set.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options, options::SmartPunctuation};
+    /// let mut options = Options::default();
+    /// options.parse.smart = true;
+    /// options.parse.smart_punctuation = SmartPunctuation::De;
+    /// assert_eq!(markdown_to_html("\"Hallo,\" sagte sie.\n", &options),
+    ///            "<p>„Hallo,“ sagte sie.</p>\n");
+    ///
+    /// options.extension.front_matter_delimiter = Some("---".into());
+    /// assert_eq!(markdown_to_html("---\nlang: fr\n---\n\"Bonjour\" !\n", &options),
+    ///            "<p>«\u{202f}Bonjour\u{202f}»\u{202f}!</p>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub smart_punctuation: SmartPunctuation,
+
+    /// Replace ASCII sequences with typographic symbols: `(c)`, `(r)` and
+    /// `(tm)` with `©`, `®` and `™`, `+-` with `±`, `->`, `<-`, `<->` and `=>`
+    /// with arrows, and common fractions such as `1/2` with `½`.  The suffixes
+    /// of ordinal numbers, such as `1st`, are rendered as superscript.
+    ///
+    /// Replacements are never made in code, math, link destinations or raw
+    /// HTML, nor when escaped.  See
+    /// [`typographer_source`](Render#structfield.typographer_source) for
+    /// CommonMark output.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.parse.typographer = true;
+    /// assert_eq!(markdown_to_html("(c) 2024 -> 1/2 off on the 3rd, `a->b`.\n", &options),
+    ///            "<p>© 2024 → ½ off on the 3<sup>rd</sup>, <code>a-&gt;b</code>.</p>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub typographer: bool,
+
     /// The default info string for fenced code blocks.
     ///
     /// ```rust
@@ -720,6 +1076,27 @@ pub struct Parse<'c> {
     /// ```
     pub default_info_string: Option<String>,
 
+    /// Guesses the language of fenced code blocks without an info string
+    /// from their content.  When it returns [`None`],
+    /// [`default_info_string`](Self::default_info_string) is used.
+    ///
+    /// [`FirstLineLanguageGuesser`] recognises shebangs and a few other
+    /// telling first lines.
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use comrak::{markdown_to_html, Options, options::FirstLineLanguageGuesser};
+    /// let mut options = Options::default();
+    /// options.parse.language_guesser = Some(Arc::new(FirstLineLanguageGuesser));
+    /// options.parse.default_info_string = Some("text".into());
+    ///
+    /// assert_eq!(markdown_to_html("```\n#!/usr/bin/env python3\n```\n\n```\nhi\n```\n", &options),
+    ///            "<pre><code class=\"language-python\">#!/usr/bin/env python3\n</code></pre>\n\
+    ///             <pre><code class=\"language-text\">hi\n</code></pre>\n");
+    /// ```
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    pub language_guesser: Option<Arc<dyn LanguageGuesser + 'c>>,
+
     /// Whether or not a simple `x` or `X` is used for tasklist or any other symbol is allowed.
     #[cfg_attr(feature = "bon", builder(default))]
     pub relaxed_tasklist_matching: bool,
@@ -811,6 +1188,131 @@ pub struct Parse<'c> {
     #[cfg_attr(feature = "arbitrary", arbitrary(default))]
     pub broken_link_callback: Option<Arc<dyn BrokenLinkCallback + 'c>>,
 
+    /// Maps the pages named by [wikilinks](Extension::wikilinks_title_after_pipe)
+    /// to URLs, and reports whether they exist.
+    ///
+    /// A heading in the target, as in `[[Page#Heading]]`, is appended to the
+    /// URL as an anchor, formed by [`WikiLinkResolver::anchorize`] and
+    /// prefixed as for [`header_id_prefix`](Extension::header_id_prefix);
+    /// a block, as in `[[Page#^block-id]]`, is appended as is.  Links to
+    /// missing pages are rendered with `class="new"`, to be styled apart.  A
+    /// link the resolver returns [`None`] for keeps its target as the URL.
+    ///
+    /// Without a resolver, the page is linked to as written, with any
+    /// heading or block appended likewise, so `[[#^block-id]]` links to a
+    /// [block anchor](Extension::block_anchors) in the same document.
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use comrak::{markdown_to_html, Options, options::ResolvedWikiLink};
+    /// let resolver = |page: &str| {
+    ///     Some(ResolvedWikiLink {
+    ///         url: format!("/wiki/{}.html", page.to_lowercase().replace(' ', "-")),
+    ///         exists: page != "New Page",
+    ///     })
+    /// };
+    ///
+    /// let mut options = Options::default();
+    /// options.extension.wikilinks_title_after_pipe = true;
+    /// options.parse.wikilink_resolver = Some(Arc::new(resolver));
+    ///
+    /// assert_eq!(markdown_to_html("[[My Page#Getting Started]] and [[New Page]]", &options),
+    ///            "<p><a href=\"/wiki/my-page.html#getting-started\" data-wikilink=\"true\">My Page#Getting Started</a> \
+    ///             and <a href=\"/wiki/new-page.html\" data-wikilink=\"true\" class=\"new\">New Page</a></p>\n");
+    /// ```
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    pub wikilink_resolver: Option<Arc<dyn WikiLinkResolver + 'c>>,
+
+    /// Cross-reference targets in other documents, for references not
+    /// resolved within the document per
+    /// [`cross_references`](Extension::cross_references).
+    ///
+    /// Gather them from each document with
+    /// [`CrossReferences::collect`], giving the URL of the document with
+    /// [`CrossReferences::with_base_url`].
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use comrak::{Arena, markdown_to_html, parse_document, Options, crossref::CrossReferences};
+    /// let mut options = Options::default();
+    /// options.extension.cross_references = true;
+    ///
+    /// let arena = Arena::new();
+    /// let chapter1 = parse_document(&arena, "![Cat](cat.png){#fig:cat}\n", &options);
+    /// let crossrefs = CrossReferences::collect(chapter1).with_base_url("chapter1.html");
+    ///
+    /// options.parse.external_cross_references = Some(Arc::new(crossrefs));
+    /// assert_eq!(markdown_to_html("As [@fig:cat] shows.\n", &options),
+    ///            "<p>As <a href=\"chapter1.html#fig:cat\">Figure 1</a> shows.</p>\n");
+    /// ```
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    pub external_cross_references: Option<Arc<CrossReferences>>,
+
+    /// Splices other documents into this one, where a paragraph consists
+    /// solely of an mdBook-style `{{#include path.md}}` or an Obsidian-style
+    /// `![[note]]` embed.
+    ///
+    /// The resolver is given the path as written and the path of the document
+    /// including it, and returns the included document's resolved path and
+    /// content.  An mdBook line range, as in `{{#include path.md:10:20}}`,
+    /// selects part of the content.  Included documents share the reference
+    /// link and footnote namespaces of the including one, and may include
+    /// others in turn; a document including itself, directly or not, is left
+    /// as written, as is one the resolver returns [`None`] for.
+    ///
+    /// The included content is parsed into a [`NodeValue::Include`] node,
+    /// whose descendants' source positions refer to the included document.
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use comrak::{markdown_to_html, Options, options::{IncludeReference, ResolvedInclude}};
+    /// let resolver = |include: IncludeReference| match include.target {
+    ///     "intro.md" => Some(ResolvedInclude {
+    ///         path: "intro.md".to_string(),
+    ///         content: "Hello, *world*.\n\nMore.\n".to_string(),
+    ///     }),
+    ///     _ => None,
+    /// };
+    ///
+    /// let mut options = Options::default();
+    /// options.parse.include_resolver = Some(Arc::new(resolver));
+    ///
+    /// assert_eq!(markdown_to_html("{{#include intro.md:1}}\n\n{{#include missing.md}}\n", &options),
+    ///            "<p>Hello, <em>world</em>.</p>\n<p>{{#include missing.md}}</p>\n");
+    /// ```
+    ///
+    /// [`NodeValue::Include`]: crate::nodes::NodeValue::Include
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    pub include_resolver: Option<Arc<dyn IncludeResolver + 'c>>,
+
+    /// The resolved path of the document being parsed, as an
+    /// [`include_resolver`](Self::include_resolver) would return it.  It's
+    /// given as the [`from`](IncludeReference::from) of the document's own
+    /// includes, and keeps documents including it from including it again.
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use comrak::{markdown_to_html, Options, options::{IncludeReference, ResolvedInclude}};
+    /// let resolver = |include: IncludeReference| match include.target {
+    ///     "a.md" => Some(ResolvedInclude {
+    ///         path: "a.md".to_string(),
+    ///         content: "A\n\n{{#include main.md}}\n".to_string(),
+    ///     }),
+    ///     _ => Some(ResolvedInclude {
+    ///         path: include.target.to_string(),
+    ///         content: "Main\n".to_string(),
+    ///     }),
+    /// };
+    ///
+    /// let mut options = Options::default();
+    /// options.parse.include_resolver = Some(Arc::new(resolver));
+    /// options.parse.document_path = Some("main.md".to_string());
+    ///
+    /// assert_eq!(markdown_to_html("{{#include a.md}}\n", &options),
+    ///            "<p>A</p>\n<p>{{#include main.md}}</p>\n");
+    /// ```
+    pub document_path: Option<String>,
+
     /// Leave footnote definitions in place in the document tree, rather than
     /// reordering them to the end.  This will also cause unreferenced footnote
     /// definitions to remain in the tree, rather than being removed.
@@ -919,6 +1421,174 @@ pub struct Parse<'c> {
     pub sourcepos_chars: bool,
 }
 
+/// Trait for guessing the language of a fenced code block from its content,
+/// per [`language_guesser`](Parse::language_guesser).
+pub trait LanguageGuesser: RefUnwindSafe + Send + Sync {
+    /// Returns the info string to give a code block with the given content,
+    /// if its language can be told.
+    fn guess(&self, code: &str) -> Option<String>;
+}
+
+impl Debug for dyn LanguageGuesser + '_ {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        formatter.write_str("<dyn LanguageGuesser>")
+    }
+}
+
+impl<F> LanguageGuesser for F
+where
+    F: for<'a> Fn(&'a str) -> Option<String>,
+    F: RefUnwindSafe + Send + Sync,
+{
+    fn guess(&self, code: &str) -> Option<String> {
+        self(code)
+    }
+}
+
+/// A [`LanguageGuesser`] which looks only at the first line of a code block:
+/// the interpreter of a shebang (`#!/usr/bin/env python3` is `python`),
+/// `<?php`, `<?xml`, an HTML doctype, `diff --git`, and a `$ ` shell prompt
+/// (`console`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FirstLineLanguageGuesser;
+
+impl LanguageGuesser for FirstLineLanguageGuesser {
+    fn guess(&self, code: &str) -> Option<String> {
+        let line = code.lines().next()?.trim_end();
+
+        if let Some(shebang) = line.strip_prefix("#!") {
+            let mut words = shebang.split_whitespace();
+            let mut interpreter = words.next()?.rsplit('/').next()?;
+            if interpreter == "env" {
+                interpreter = words.find(|word| !word.starts_with('-'))?;
+            }
+            let interpreter =
+                interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+            return match interpreter {
+                "" => None,
+                "node" | "nodejs" | "deno" => Some("javascript".into()),
+                "sh" | "zsh" | "dash" | "ksh" => Some("bash".into()),
+                _ => Some(interpreter.into()),
+            };
+        }
+
+        let lowercase = line.to_ascii_lowercase();
+        let language = if lowercase.starts_with("<?php") {
+            "php"
+        } else if lowercase.starts_with("<?xml") {
+            "xml"
+        } else if lowercase.starts_with("<!doctype html") || lowercase.starts_with("<html") {
+            "html"
+        } else if line.starts_with("diff --git ") {
+            "diff"
+        } else if line.starts_with("$ ") {
+            "console"
+        } else {
+            return None;
+        };
+        Some(language.into())
+    }
+}
+
+/// The type of the resolver used to find documents to splice in per
+/// [`include_resolver`](Parse::include_resolver).
+///
+/// Resolvers can read from the file system, or from an in-memory store.
+pub trait IncludeResolver: RefUnwindSafe + Send + Sync {
+    /// Resolve a single include, returning [`None`] if the document can't be
+    /// found.
+    fn resolve(&self, include: IncludeReference) -> Option<ResolvedInclude>;
+
+    /// Returns the anchor of a heading, for matching an embed of a section,
+    /// as in `![[Note#Heading]]`, against the headings of the note.  The
+    /// default forms it as Comrak's default anchorizer does; override it to
+    /// match an [`anchorizer_adapter`](RenderPlugins::anchorizer_adapter),
+    /// as render plugins aren't available while parsing.
+    fn anchorize(&self, heading: &str) -> String {
+        Anchorizer::new().anchorize(heading)
+    }
+}
+
+impl Debug for dyn IncludeResolver + '_ {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        formatter.write_str("<dyn IncludeResolver>")
+    }
+}
+
+impl<F> IncludeResolver for F
+where
+    F: Fn(IncludeReference) -> Option<ResolvedInclude>,
+    F: RefUnwindSafe + Send + Sync,
+{
+    fn resolve(&self, include: IncludeReference) -> Option<ResolvedInclude> {
+        self(include)
+    }
+}
+
+/// The details of an include passed to an [`IncludeResolver`].
+#[derive(Debug)]
+pub struct IncludeReference<'l> {
+    /// The path of the included document as written, without any line range.
+    pub target: &'l str,
+
+    /// The resolved path of the document containing the include, or, for the
+    /// document being parsed, its [`document_path`](Parse::document_path).
+    pub from: Option<&'l str>,
+}
+
+/// A document found by an [`IncludeResolver`].
+#[derive(Debug, Clone)]
+pub struct ResolvedInclude {
+    /// The resolved path of the document, identifying it for cycle detection
+    /// and as the [`from`](IncludeReference::from) of its own includes.
+    pub path: String,
+
+    /// The document's Markdown.
+    pub content: String,
+}
+
+/// The type of the resolver used to map wikilinked pages to URLs per
+/// [`wikilink_resolver`](Parse::wikilink_resolver).
+pub trait WikiLinkResolver: RefUnwindSafe + Send + Sync {
+    /// Resolve the page with the given name, as written in the wikilink.
+    fn resolve(&self, page: &str) -> Option<ResolvedWikiLink>;
+
+    /// Returns the anchor of a heading in a page, as in `[[Page#Heading]]`.
+    /// The default forms it as Comrak's default anchorizer does; override it
+    /// to match an [`anchorizer_adapter`](RenderPlugins::anchorizer_adapter),
+    /// as render plugins aren't available while parsing.
+    fn anchorize(&self, heading: &str) -> String {
+        Anchorizer::new().anchorize(heading)
+    }
+}
+
+impl Debug for dyn WikiLinkResolver + '_ {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        formatter.write_str("<dyn WikiLinkResolver>")
+    }
+}
+
+impl<F> WikiLinkResolver for F
+where
+    F: Fn(&str) -> Option<ResolvedWikiLink>,
+    F: RefUnwindSafe + Send + Sync,
+{
+    fn resolve(&self, page: &str) -> Option<ResolvedWikiLink> {
+        self(page)
+    }
+}
+
+/// A page found by a [`WikiLinkResolver`].
+#[derive(Debug, Clone)]
+pub struct ResolvedWikiLink {
+    /// The URL of the page.
+    pub url: String,
+
+    /// Whether the page exists.  Links to missing pages are rendered as "new"
+    /// links.
+    pub exists: bool,
+}
+
 /// The type of the callback used when a reference link is encountered with no
 /// matching reference.
 ///
@@ -960,7 +1630,7 @@ pub struct BrokenLinkReference<'l> {
     pub original: &'l str,
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "bon", derive(Builder))]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 /// Options for formatter functions.
@@ -1213,6 +1883,22 @@ pub struct Render {
     #[cfg_attr(feature = "bon", builder(default))]
     pub figure_with_caption: bool,
 
+    /// Render the relative widths of table columns as a `<colgroup>`.  The
+    /// width of a column comes from the number of dashes in its delimiter
+    /// cell, or from its width in a grid table.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.table = true;
+    /// options.render.table_column_widths = true;
+    /// assert_eq!(markdown_to_html("| a | b |\n|---|---------|\n", &options),
+    ///            "<table>\n<colgroup>\n<col style=\"width: 25%\" />\n<col style=\"width: 75%\" />\n\
+    ///             </colgroup>\n<thead>\n<tr>\n<th>a</th>\n<th>b</th>\n</tr>\n</thead>\n</table>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub table_column_widths: bool,
+
     /// Add classes to the output of the tasklist extension. This allows tasklists to be styled.
     ///
     /// ```rust
@@ -1286,6 +1972,125 @@ pub struct Render {
     /// ```
     #[cfg_attr(feature = "bon", builder(default))]
     pub compact_html: bool,
+
+    /// Prefix top-level headings with their hierarchical section number, as
+    /// per [`sections`](crate::sections::sections).
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.render.heading_numbers = true;
+    /// assert_eq!(markdown_to_html("# Scope\n\n## Terms\n", &options),
+    ///            "<h1><span class=\"heading-number\">1</span> Scope</h1>\n\
+    ///             <h2><span class=\"heading-number\">1.1</span> Terms</h2>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub heading_numbers: bool,
+
+    /// Where footnote definitions are rendered; see [`FootnotePlacement`].
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options, options::FootnotePlacement};
+    /// let mut options = Options::default();
+    /// options.extension.footnotes = true;
+    /// options.render.footnote_placement = FootnotePlacement::Inline;
+    /// assert_eq!(markdown_to_html("Hi[^x].\n\nBye.\n\n[^x]: A greeting.\n", &options),
+    ///            "<p>Hi<sup class=\"footnote-ref\"><a href=\"#fn-x\" id=\"fnref-x\" data-footnote-ref>1</a></sup>.</p>\n\
+    ///             <aside class=\"footnote\" id=\"fn-x\">\n\
+    ///             <p>A greeting. <a href=\"#fnref-x\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1\" aria-label=\"Back to reference 1\">↩</a></p>\n\
+    ///             </aside>\n\
+    ///             <p>Bye.</p>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub footnote_placement: FootnotePlacement,
+
+    /// A prefix for the IDs of footnotes and their references, for when
+    /// several documents are rendered into one page.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.footnotes = true;
+    /// options.render.footnote_id_prefix = Some("post-1-".to_string());
+    /// assert_eq!(markdown_to_html("Hi[^x].\n\n[^x]: A greeting.\n", &options),
+    ///            "<p>Hi<sup class=\"footnote-ref\"><a href=\"#post-1-fn-x\" id=\"post-1-fnref-x\" data-footnote-ref>1</a></sup>.</p>\n<section class=\"footnotes\" data-footnotes>\n<ol>\n<li id=\"post-1-fn-x\">\n<p>A greeting. <a href=\"#post-1-fnref-x\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1\" aria-label=\"Back to reference 1\">↩</a></p>\n</li>\n</ol>\n</section>\n");
+    /// ```
+    pub footnote_id_prefix: Option<String>,
+
+    /// The class of the section holding footnote definitions.  Defaults to
+    /// `footnotes`.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.footnotes = true;
+    /// options.render.footnote_section_class = Some("notes".to_string());
+    /// assert!(markdown_to_html("Hi[^x].\n\n[^x]: A greeting.\n", &options)
+    ///     .contains("<section class=\"notes\" data-footnotes>"));
+    /// ```
+    pub footnote_section_class: Option<String>,
+
+    /// The text of the link from a footnote back to its reference.  Defaults
+    /// to `↩`.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.footnotes = true;
+    /// options.render.footnote_backref_symbol = Some("^".to_string());
+    /// assert!(markdown_to_html("Hi[^x].\n\n[^x]: A greeting.\n", &options)
+    ///     .contains("aria-label=\"Back to reference 1\">^</a>"));
+    /// ```
+    pub footnote_backref_symbol: Option<String>,
+
+    /// The `aria-label` of the link from a footnote back to its reference,
+    /// which is followed by a space and the number of the reference.
+    /// Defaults to `Back to reference`.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_html, Options};
+    /// let mut options = Options::default();
+    /// options.extension.footnotes = true;
+    /// options.render.footnote_backref_label = Some("Zurück zum Verweis".to_string());
+    /// assert!(markdown_to_html("Hi[^x].\n\n[^x]: A greeting.\n", &options)
+    ///     .contains("aria-label=\"Zurück zum Verweis 1\">↩</a>"));
+    /// ```
+    pub footnote_backref_label: Option<String>,
+
+    /// Write [`typographer`](Parse#structfield.typographer) replacements in
+    /// their ASCII source form in CommonMark output, instead of the symbols
+    /// they were replaced with.
+    ///
+    /// ```rust
+    /// # use comrak::{markdown_to_commonmark, Options};
+    /// let mut options = Options::default();
+    /// options.parse.typographer = true;
+    /// assert_eq!(markdown_to_commonmark("(c) Acme -> 1st\n", &options),
+    ///            "© Acme → 1st\n");
+    ///
+    /// options.render.typographer_source = true;
+    /// assert_eq!(markdown_to_commonmark("(c) Acme -> 1st\n", &options),
+    ///            "(c) Acme -> 1st\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub typographer_source: bool,
+}
+
+/// Where footnote definitions are rendered in HTML output.  See
+/// [`footnote_placement`](Render#structfield.footnote_placement).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum FootnotePlacement {
+    /// In a section at the end of the document.
+    #[default]
+    End,
+    /// In a section at the end of each top-level section of the document,
+    /// i.e. before each heading of the highest level found at the top level,
+    /// holding the footnotes first referenced since the previous one.
+    Section,
+    /// In an `<aside>` following the top-level block which first references
+    /// each footnote, for rendering as sidenotes.
+    Inline,
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -1321,6 +2126,30 @@ pub struct RenderPlugins<'p> {
     #[cfg_attr(feature = "bon", builder(default))]
     pub codefence_renderers: HashMap<String, &'p dyn CodefenceRendererAdapter>,
 
+    /// Maps the languages of code blocks, the first word of their info
+    /// strings, to the names to use in their place: in the `language-*`
+    /// class or `lang` attribute, and when looking up
+    /// [`codefence_renderers`](Self::codefence_renderers) and the
+    /// [`codefence_syntax_highlighter`](Self::codefence_syntax_highlighter)'s
+    /// syntax.  A language not found as written is looked up in lowercase.
+    ///
+    /// [`COMMON_LANGUAGE_ALIASES`] holds the usual short forms.
+    ///
+    /// ```rust
+    /// # use comrak::{Options, options::{Plugins, COMMON_LANGUAGE_ALIASES}, markdown_to_html_with_plugins};
+    /// let options = Options::default();
+    /// let mut plugins = Plugins::default();
+    /// plugins.render.codefence_language_aliases = COMMON_LANGUAGE_ALIASES
+    ///     .iter()
+    ///     .map(|&(alias, name)| (alias.to_string(), name.to_string()))
+    ///     .collect();
+    ///
+    /// assert_eq!(markdown_to_html_with_plugins("```JS\nf();\n```\n", &options, &plugins),
+    ///            "<pre><code class=\"language-javascript\">f();\n</code></pre>\n");
+    /// ```
+    #[cfg_attr(feature = "bon", builder(default))]
+    pub codefence_language_aliases: HashMap<String, String>,
+
     /// Provide a syntax highlighter adapter implementation for syntax
     /// highlighting of codefence blocks.
     ///
@@ -1362,8 +2191,151 @@ pub struct RenderPlugins<'p> {
 
     /// Optional heading adapter
     pub heading_adapter: Option<&'p dyn HeadingAdapter>,
+
+    /// Provide an anchorizer adapter to change how heading anchors are
+    /// generated, both for rendered headings and for [`toc`](crate::html::toc_with_plugins).
+    ///
+    /// ```rust
+    /// # use comrak::{Options, options::Plugins, markdown_to_html_with_plugins};
+    /// # use comrak::adapters::AnchorizerAdapter;
+    /// struct Underscores;
+    /// impl AnchorizerAdapter for Underscores {
+    ///     fn slugify(&self, header: &str) -> String {
+    ///         header.to_lowercase().replace(' ', "_")
+    ///     }
+    ///
+    ///     fn unique(&self, slug: &str, n: usize) -> String {
+    ///         format!("{}_{}", slug, n)
+    ///     }
+    /// }
+    ///
+    /// let mut options = Options::default();
+    /// options.extension.header_id_prefix = Some("".to_string());
+    /// let mut plugins = Plugins::default();
+    /// plugins.render.anchorizer_adapter = Some(&Underscores);
+    ///
+    /// assert_eq!(markdown_to_html_with_plugins("# Read Me\n", &options, &plugins),
+    ///            "<h1><a href=\"#read_me\" aria-hidden=\"true\" class=\"anchor\" id=\"read_me\"></a>Read Me</h1>\n");
+    /// ```
+    pub anchorizer_adapter: Option<&'p dyn AnchorizerAdapter>,
+
+    /// Provide a footnote adapter to take over rendering of footnote
+    /// references, definitions and the sections holding them.
+    ///
+    /// ```rust
+    /// # use comrak::{Options, options::Plugins, markdown_to_html_with_plugins};
+    /// # use comrak::adapters::{FootnoteAdapter, FootnoteMeta, FootnoteReferenceMeta};
+    /// # use comrak::nodes::Sourcepos;
+    /// # use std::fmt;
+    /// struct Endnotes;
+    /// impl FootnoteAdapter for Endnotes {
+    ///     fn write_reference(
+    ///         &self,
+    ///         output: &mut dyn fmt::Write,
+    ///         reference: &FootnoteReferenceMeta,
+    ///         _sourcepos: Option<Sourcepos>,
+    ///     ) -> fmt::Result {
+    ///         write!(output, "<a href=\"{}\" id=\"{}\">[{}]</a>", reference.href, reference.id, reference.ix)
+    ///     }
+    ///
+    ///     fn enter_section(&self, output: &mut dyn fmt::Write, _start: u32, _sourcepos: Option<Sourcepos>) -> fmt::Result {
+    ///         output.write_str("<h2>Notes</h2>\n<dl>\n")
+    ///     }
+    ///
+    ///     fn exit_section(&self, output: &mut dyn fmt::Write) -> fmt::Result {
+    ///         output.write_str("</dl>\n")
+    ///     }
+    ///
+    ///     fn enter_definition(
+    ///         &self,
+    ///         output: &mut dyn fmt::Write,
+    ///         footnote: &FootnoteMeta,
+    ///         _sourcepos: Option<Sourcepos>,
+    ///     ) -> fmt::Result {
+    ///         write!(output, "<dt id=\"{}\">{}</dt>\n<dd>", footnote.id, footnote.ix)
+    ///     }
+    ///
+    ///     fn exit_definition(&self, output: &mut dyn fmt::Write, _footnote: &FootnoteMeta) -> fmt::Result {
+    ///         output.write_str("</dd>\n")
+    ///     }
+    /// }
+    ///
+    /// let mut options = Options::default();
+    /// options.extension.footnotes = true;
+    /// let mut plugins = Plugins::default();
+    /// plugins.render.footnote_adapter = Some(&Endnotes);
+    ///
+    /// assert_eq!(markdown_to_html_with_plugins("Hi[^x].\n\n[^x]: A greeting.\n", &options, &plugins),
+    ///            "<p>Hi<a href=\"#fn-x\" id=\"fnref-x\">[1]</a>.</p>\n\
+    ///             <h2>Notes</h2>\n<dl>\n<dt id=\"fn-x\">1</dt>\n<dd>\n<p>A greeting.</p>\n</dd>\n</dl>\n");
+    /// ```
+    pub footnote_adapter: Option<&'p dyn FootnoteAdapter>,
+
+    /// Provide a math renderer adapter to render math spans and
+    /// ```` ```math ```` code blocks on the server, such as into MathML.
+    ///
+    /// ```rust
+    /// # use comrak::{Options, options::Plugins, markdown_to_html_with_plugins};
+    /// # use comrak::adapters::{MathRendererAdapter, MathRendering};
+    /// # use comrak::nodes::Sourcepos;
+    /// # use std::fmt;
+    /// struct MathML;
+    /// impl MathRendererAdapter for MathML {
+    ///     fn write(
+    ///         &self,
+    ///         output: &mut dyn fmt::Write,
+    ///         literal: &str,
+    ///         display_math: bool,
+    ///         _sourcepos: Option<Sourcepos>,
+    ///     ) -> Result<MathRendering, fmt::Error> {
+    ///         let display = if display_math { "block" } else { "inline" };
+    ///         write!(output, "<math display=\"{}\"><mi>{}</mi></math>", display, literal)?;
+    ///         Ok(MathRendering::Written)
+    ///     }
+    /// }
+    ///
+    /// let mut options = Options::default();
+    /// options.extension.math_dollars = true;
+    /// let mut plugins = Plugins::default();
+    /// plugins.render.math_renderer_adapter = Some(&MathML);
+    ///
+    /// assert_eq!(markdown_to_html_with_plugins("Let $x$ be\n\n$$y$$\n", &options, &plugins),
+    ///            "<p>Let <math display=\"inline\"><mi>x</mi></math> be</p>\n\
+    ///             <p><math display=\"block\"><mi>y</mi></math></p>\n");
+    /// ```
+    pub math_renderer_adapter: Option<&'p dyn MathRendererAdapter>,
 }
 
+/// Common short and alternative names of languages, and the names
+/// Syntect and most highlighters know them by, for
+/// [`codefence_language_aliases`](RenderPlugins::codefence_language_aliases).
+pub const COMMON_LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("c++", "cpp"),
+    ("cjs", "javascript"),
+    ("cxx", "cpp"),
+    ("golang", "go"),
+    ("hs", "haskell"),
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("md", "markdown"),
+    ("mjs", "javascript"),
+    ("objc", "objective-c"),
+    ("py", "python"),
+    ("py3", "python"),
+    ("python3", "python"),
+    ("rb", "ruby"),
+    ("rs", "rust"),
+    ("sh", "bash"),
+    ("sh-session", "console"),
+    ("shell", "bash"),
+    ("shell-session", "console"),
+    ("tex", "latex"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("yml", "yaml"),
+    ("zsh", "bash"),
+];
+
 impl Debug for RenderPlugins<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RenderPlugins")
@@ -1371,10 +2343,17 @@ impl Debug for RenderPlugins<'_> {
                 "codefence_renderers",
                 &"HashMap<String, impl CodefenceRendererAdapter>",
             )
+            .field(
+                "codefence_language_aliases",
+                &self.codefence_language_aliases,
+            )
             .field(
                 "codefence_syntax_highlighter",
                 &"impl SyntaxHighlighterAdapter",
             )
+            .field("anchorizer_adapter", &"impl AnchorizerAdapter")
+            .field("footnote_adapter", &"impl FootnoteAdapter")
+            .field("math_renderer_adapter", &"impl MathRendererAdapter")
             .finish()
     }
 }
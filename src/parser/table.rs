@@ -1,11 +1,15 @@
 use std::borrow::Cow;
 use std::cmp::min;
 use std::mem;
+use std::ops::Range;
 
-use crate::nodes::{Ast, LineColumn, Node, NodeTable, NodeValue, TableAlignment};
+use crate::node_matches;
+use crate::nodes::{Ast, LineColumn, Node, NodeTable, NodeTableCell, NodeValue, TableAlignment};
 use crate::parser::Parser;
 use crate::scanners;
-use crate::strings::{count_newlines, is_line_end_char, newlines_of, trim_cow};
+use crate::strings::{
+    chop_trailing_custom_id, count_newlines, is_line_end_char, newlines_of, rtrim_slice, trim_cow,
+};
 
 // Limit to prevent a malicious input from causing a denial of service.
 // See get_num_autocompleted_cells.
@@ -19,6 +23,10 @@ pub fn try_opening_block<'a>(
     let aligns = match &container.data().value {
         NodeValue::Paragraph => None,
         NodeValue::Table(nt) => Some(nt.alignments.clone()),
+        NodeValue::TableRow(..) | NodeValue::TableCell(..) => return None,
+        _ if parser.options.extension.extended_tables => {
+            return try_opening_headerless(parser, container, line);
+        }
         _ => return None,
     };
 
@@ -42,108 +50,126 @@ fn try_opening_header<'a>(
     }
 
     let spoiler = parser.options.extension.spoiler;
+    let extended = parser.options.extension.extended_tables;
 
-    let delimiter_row = match row(&line[parser.first_nonspace..], spoiler) {
+    let delimiter_row = match row(&line[parser.first_nonspace..], spoiler, false) {
         Some(delimiter_row) => delimiter_row,
         None => return Some((container, false, true)),
     };
 
     let mut container_content = mem::take(&mut container.data_mut().content);
-    let mut header_row = match row(&container_content, spoiler) {
-        Some(header_row) => header_row,
+    let (paragraph_offset, header_rows) = match header_rows(
+        &container_content,
+        spoiler,
+        extended,
+        delimiter_row.cells.len(),
+    ) {
+        Some(header_rows) => header_rows,
         None => {
             mem::swap(&mut container.data_mut().content, &mut container_content);
             return Some((container, false, true));
         }
     };
 
-    if header_row.cells.len() != delimiter_row.cells.len() {
-        mem::swap(&mut container.data_mut().content, &mut container_content);
-        return Some((container, false, true));
-    }
-
-    if header_row.paragraph_offset > 0 {
+    if paragraph_offset > 0 {
         try_inserting_table_header_paragraph(
             parser,
             container,
             &container_content,
-            header_row.paragraph_offset,
+            paragraph_offset,
         );
     }
 
-    let mut alignments = vec![];
-    for cell in delimiter_row.cells {
-        let cell_content = cell.content.as_bytes();
-        let left = !cell_content.is_empty() && cell_content[0] == b':';
-        let right = !cell_content.is_empty() && cell_content[cell_content.len() - 1] == b':';
-        alignments.push(if left && right {
-            TableAlignment::Center
-        } else if left {
-            TableAlignment::Left
-        } else if right {
-            TableAlignment::Right
-        } else {
-            TableAlignment::None
-        });
-    }
-
     let start = container.data().sourcepos.start;
     let child = Ast::new(
         NodeValue::Table(Box::new(NodeTable {
-            alignments,
-            num_columns: header_row.cells.len(),
+            alignments: alignments(&delimiter_row),
+            num_columns: delimiter_row.cells.len(),
             num_rows: 0,
             num_nonempty_cells: 0,
+            column_widths: column_widths(&delimiter_row),
+            ..Default::default()
         })),
         start,
     );
     let table = parser.arena.alloc(child.into());
     container.append(table);
 
-    let header = parser.add_child(table, NodeValue::TableRow(true), start.column);
-    {
-        let header_ast = &mut header.data_mut();
-        header_ast.sourcepos.start.line = start.line;
-        header_ast.sourcepos.end = start.column_add(
-            (container_content.len()
-                - newlines_of(&container_content)
-                - 1
-                - header_row.paragraph_offset) as isize,
-        );
-    }
+    parser.table_state = TableState::default();
+    let preface_lines = count_newlines(&container_content[..paragraph_offset]).0;
+    let mut num_cells = 0;
 
-    let mut i = 0;
-
-    while i < header_row.cells.len() {
-        let cell = &mut header_row.cells[i];
-        let ast_cell = parser.add_child(
-            header,
-            NodeValue::TableCell,
-            start.column + cell.start_offset - header_row.paragraph_offset,
-        );
-        let ast = &mut ast_cell.data_mut();
-        ast.sourcepos.start.line = start.line;
-        ast.sourcepos.end =
-            start.column_add((cell.end_offset - header_row.paragraph_offset) as isize);
-        mem::swap(&mut ast.content, cell.content.to_mut());
-        ast.line_offsets.push(
-            start.column + cell.start_offset - 1 + cell.internal_offset
-                - header_row.paragraph_offset,
-        );
+    for (i, (mut header_row, len)) in header_rows.into_iter().enumerate() {
+        // The first header row starts where the table does.
+        let line_start = if i == 0 {
+            start
+        } else {
+            let line_offset = container.data().line_offsets[preface_lines + i];
+            (start.line + i, line_offset + 1).into()
+        };
+
+        let header = parser.add_child(table, NodeValue::TableRow(true), line_start.column);
+        {
+            let header_ast = &mut header.data_mut();
+            header_ast.sourcepos.start.line = line_start.line;
+            header_ast.sourcepos.end = line_start.column_add(len as isize - 1);
+        }
 
-        i += 1;
+        num_cells += add_cells(parser, header, &mut header_row.cells, line_start).0;
     }
 
+    parser.table_state = TableState::default();
     mem::swap(&mut container.data_mut().content, &mut container_content);
 
     let offset = line.len() - newlines_of(line) - parser.offset;
     parser.advance_offset(line, offset, false);
 
-    adjust_table_counters(table, i, (parser.line_number, offset).into());
+    adjust_table_counters(table, true, num_cells, (parser.line_number, offset).into());
 
     Some((table, true, false))
 }
 
+fn try_opening_headerless<'a>(
+    parser: &mut Parser<'a, '_, '_>,
+    container: Node<'a>,
+    line: &str,
+) -> Option<(Node<'a>, bool, bool)> {
+    scanners::table_start(&line[parser.first_nonspace..])?;
+    let delimiter_row = row(
+        &line[parser.first_nonspace..],
+        parser.options.extension.spoiler,
+        false,
+    )?;
+
+    let table = parser.add_child(
+        container,
+        NodeValue::Table(Box::new(NodeTable {
+            alignments: alignments(&delimiter_row),
+            num_columns: delimiter_row.cells.len(),
+            num_rows: 0,
+            num_nonempty_cells: 0,
+            column_widths: column_widths(&delimiter_row),
+            ..Default::default()
+        })),
+        parser.first_nonspace + 1,
+    );
+    {
+        // Kept so that a delimiter row no body row follows can be turned back
+        // into a paragraph when the table is finalized.
+        let mut ast = table.data_mut();
+        ast.sourcepos.end.column = parser.curline_end_col;
+        ast.content = line[parser.first_nonspace..line.len() - newlines_of(line)].to_string();
+        ast.line_offsets.push(parser.first_nonspace);
+    }
+
+    parser.table_state = TableState::default();
+
+    let offset = line.len() - newlines_of(line) - parser.offset;
+    parser.advance_offset(line, offset, false);
+
+    Some((table, false, false))
+}
+
 fn try_opening_row<'a>(
     parser: &mut Parser<'a, '_, '_>,
     container: Node<'a>,
@@ -160,7 +186,34 @@ fn try_opening_row<'a>(
 
     let sourcepos = container.data().sourcepos;
     let spoiler = parser.options.extension.spoiler;
-    let mut this_row = row(&line[parser.first_nonspace..], spoiler)?;
+    let extended = parser.options.extension.extended_tables;
+
+    let (line_content, continues) = if extended {
+        strip_continuation(&line[parser.first_nonspace..])
+    } else {
+        (Cow::Borrowed(&line[parser.first_nonspace..]), false)
+    };
+    let mut this_row = row(&line_content, spoiler, extended)?;
+    let line_start: LineColumn = (parser.line_number, sourcepos.start.column).into();
+
+    if mem::replace(&mut parser.table_state.row_continues, continues) {
+        if let Some(last_row) = container.last_child() {
+            let num_cells = join_cells(last_row, &mut this_row.cells, line_start);
+            last_row.data_mut().sourcepos.end = (parser.line_number, parser.curline_end_col).into();
+
+            let offset = line.len() - parser.offset - newlines_of(line);
+            parser.advance_offset(line, offset, false);
+
+            adjust_table_counters(
+                container,
+                false,
+                num_cells,
+                (parser.line_number, offset).into(),
+            );
+
+            return Some((container, false, false));
+        }
+    }
 
     let new_row = parser.add_child(
         container,
@@ -169,42 +222,255 @@ fn try_opening_row<'a>(
     );
     new_row.data_mut().sourcepos.end.column = parser.curline_end_col;
 
-    let mut i = 0;
-    let mut last_column = sourcepos.start.column;
+    let (num_cells, mut column, last_column) =
+        add_cells(parser, new_row, &mut this_row.cells, line_start);
 
-    while i < min(alignments.len(), this_row.cells.len()) {
-        let cell = &mut this_row.cells[i];
+    while column < alignments.len() {
         let cell_node = parser.add_child(
             new_row,
-            NodeValue::TableCell,
-            sourcepos.start.column + cell.start_offset,
+            NodeValue::TableCell(NodeTableCell {
+                column,
+                ..NodeTableCell::default()
+            }),
+            last_column + 1,
         );
+        // for autocompleted (empty) cells, set end column equal to start
         let cell_ast = &mut cell_node.data_mut();
-        cell_ast.sourcepos.end.column = sourcepos.start.column + cell.end_offset;
+        cell_ast.sourcepos.end.column = last_column + 1;
+        if extended {
+            parser.table_state.cells_above[column] = Some(cell_node);
+        }
+        column += 1;
+    }
+
+    let offset = line.len() - parser.offset - newlines_of(line);
+    parser.advance_offset(line, offset, false);
+
+    adjust_table_counters(
+        container,
+        true,
+        num_cells,
+        (parser.line_number, offset).into(),
+    );
+
+    Some((new_row, false, false))
+}
+
+/// Parse state of the open table, used by extended tables.
+#[derive(Default)]
+pub(crate) struct TableState<'a> {
+    /// The cell occupying each column in the last row of the current section,
+    /// which a `^^` cell below it joins.
+    cells_above: Vec<Option<Node<'a>>>,
+
+    /// Whether the last row ended in a backslash, and so continues on the next
+    /// line.
+    row_continues: bool,
+}
+
+/// Adds the cells of a row to `row_node`, on the line starting at `line_start`.
+/// In extended tables, `^^` cells join the cell above them instead of being
+/// added.  Returns the number of cells added, the index of the first column
+/// not covered, and the column the last cell added ends at.
+fn add_cells<'a>(
+    parser: &mut Parser<'a, '_, '_>,
+    row_node: Node<'a>,
+    cells: &mut [Cell],
+    line_start: LineColumn,
+) -> (usize, usize, usize) {
+    let extended = parser.options.extension.extended_tables;
+    let num_columns = match row_node.parent().unwrap().data().value {
+        NodeValue::Table(ref nt) => nt.num_columns,
+        _ => unreachable!(),
+    };
+    if extended {
+        parser.table_state.cells_above.resize(num_columns, None);
+    }
+
+    let mut num_cells = 0;
+    let mut column = 0;
+    let mut last_column = line_start.column;
+    let mut joined: Vec<Node<'a>> = vec![];
+
+    for cell in cells {
+        if column >= num_columns {
+            break;
+        }
+        let colspan = min(cell.colspan, num_columns - column);
+
+        if extended && cell.content == "^^" && parser.table_state.cells_above[column].is_some() {
+            for above in parser.table_state.cells_above[column..column + colspan]
+                .iter()
+                .flatten()
+            {
+                if joined.iter().any(|n| n.same_node(above)) {
+                    continue;
+                }
+                if let NodeValue::TableCell(ref mut ntc) = above.data_mut().value {
+                    ntc.rowspan += 1;
+                }
+                joined.push(above);
+            }
+            column += colspan;
+            continue;
+        }
+
+        let cell_node = parser.add_child(
+            row_node,
+            NodeValue::TableCell(NodeTableCell {
+                column,
+                colspan,
+                rowspan: 1,
+            }),
+            line_start.column + cell.start_offset,
+        );
+        let cell_ast = &mut cell_node.data_mut();
+        cell_ast.sourcepos.start.line = line_start.line;
+        cell_ast.sourcepos.end = (line_start.line, line_start.column + cell.end_offset).into();
         mem::swap(&mut cell_ast.content, cell.content.to_mut());
         cell_ast
             .line_offsets
-            .push(sourcepos.start.column + cell.start_offset - 1 + cell.internal_offset);
+            .push(line_start.column + cell.start_offset - 1 + cell.internal_offset);
+
+        if extended {
+            for above in &mut parser.table_state.cells_above[column..column + colspan] {
+                *above = Some(cell_node);
+            }
+        }
 
         last_column = cell_ast.sourcepos.end.column;
+        num_cells += 1;
+        column += colspan;
+    }
+
+    (num_cells, column, last_column)
+}
+
+/// Joins the cells of a continuation line to those of `row_node`, by column.
+/// Returns the number of cells joined.
+fn join_cells(row_node: Node<'_>, cells: &mut [Cell], line_start: LineColumn) -> usize {
+    let mut num_cells = 0;
+    let mut column = 0;
 
-        i += 1;
+    for cell in cells {
+        let target = row_node.children().find(|n| match n.data().value {
+            NodeValue::TableCell(ref ntc) => {
+                ntc.column <= column && column < ntc.column + ntc.colspan
+            }
+            _ => false,
+        });
+        column += cell.colspan;
+
+        let Some(target) = target else {
+            continue;
+        };
+        if cell.content.is_empty() {
+            continue;
+        }
+
+        let ast = &mut target.data_mut();
+        let line_offset = line_start.column + cell.start_offset - 1 + cell.internal_offset;
+        if ast.content.is_empty() {
+            ast.sourcepos.start = (line_start.line, line_start.column + cell.start_offset).into();
+            ast.line_offsets.clear();
+        } else {
+            ast.content.push('\n');
+        }
+        ast.content.push_str(&cell.content);
+        ast.line_offsets.push(line_offset);
+        ast.sourcepos.end = (line_start.line, line_start.column + cell.end_offset).into();
+        num_cells += 1;
     }
 
-    while i < alignments.len() {
-        let cell_node = parser.add_child(new_row, NodeValue::TableCell, last_column + 1);
-        // for autocompleted (empty) cells, set end column equal to start
-        let cell_ast = &mut cell_node.data_mut();
-        cell_ast.sourcepos.end.column = last_column + 1;
-        i += 1;
+    num_cells
+}
+
+/// Finds the header rows at the end of a paragraph's content, returning the
+/// offset they start at, and each row with the length of its line.  Only
+/// extended tables have more than one.
+fn header_rows(
+    content: &str,
+    spoiler: bool,
+    extended: bool,
+    num_columns: usize,
+) -> Option<(usize, Vec<(Row<'_>, usize)>)> {
+    let mut last_row = row(content, spoiler, extended)?;
+    if last_row.width() != num_columns {
+        return None;
     }
 
-    let offset = line.len() - parser.offset - newlines_of(line);
-    parser.advance_offset(line, offset, false);
+    let mut paragraph_offset = last_row.paragraph_offset;
+    for cell in &mut last_row.cells {
+        cell.start_offset -= paragraph_offset;
+        cell.end_offset -= paragraph_offset;
+    }
+    let len = content.len() - newlines_of(content) - paragraph_offset;
+    let mut rows = vec![(last_row, len)];
+
+    if extended {
+        while paragraph_offset > 0 {
+            let line_end = paragraph_offset - newlines_of(&content[..paragraph_offset]);
+            let line_start = content[..line_end].rfind(['\r', '\n']).map_or(0, |i| i + 1);
+            let line = &content[line_start..paragraph_offset];
+            match row(line, spoiler, true) {
+                Some(r) if r.paragraph_offset == 0 && r.width() == num_columns => {
+                    rows.push((r, line_end - line_start));
+                    paragraph_offset = line_start;
+                }
+                _ => break,
+            }
+        }
+        rows.reverse();
+    }
+
+    Some((paragraph_offset, rows))
+}
 
-    adjust_table_counters(container, i, (parser.line_number, offset).into());
+fn alignments(delimiter_row: &Row) -> Vec<TableAlignment> {
+    delimiter_row
+        .cells
+        .iter()
+        .map(|cell| {
+            let cell_content = cell.content.as_bytes();
+            let left = !cell_content.is_empty() && cell_content[0] == b':';
+            let right = !cell_content.is_empty() && cell_content[cell_content.len() - 1] == b':';
+            if left && right {
+                TableAlignment::Center
+            } else if left {
+                TableAlignment::Left
+            } else if right {
+                TableAlignment::Right
+            } else {
+                TableAlignment::None
+            }
+        })
+        .collect()
+}
 
-    Some((new_row, false, false))
+fn column_widths(delimiter_row: &Row) -> Vec<usize> {
+    delimiter_row
+        .cells
+        .iter()
+        .map(|cell| cell.content.bytes().filter(|&b| b == b'-').count())
+        .collect()
+}
+
+/// Strips a backslash following the last pipe of a row, which marks that the
+/// row continues on the next line.  The backslash is replaced with a space so
+/// that offsets into the line are unchanged.
+fn strip_continuation(line: &str) -> (Cow<'_, str>, bool) {
+    let content = line[..line.len() - newlines_of(line)].trim_end_matches([' ', '\t']);
+    if content.ends_with('\\')
+        && content[..content.len() - 1]
+            .trim_end_matches([' ', '\t'])
+            .ends_with('|')
+    {
+        let mut stripped = line.to_string();
+        stripped.replace_range(content.len() - 1..content.len(), " ");
+        (stripped.into(), true)
+    } else {
+        (line.into(), false)
+    }
 }
 
 struct Row<'t> {
@@ -212,14 +478,24 @@ struct Row<'t> {
     cells: Vec<Cell<'t>>,
 }
 
+impl Row<'_> {
+    /// The number of columns the row's cells span.
+    fn width(&self) -> usize {
+        self.cells.iter().map(|cell| cell.colspan).sum()
+    }
+}
+
 struct Cell<'t> {
     start_offset: usize,
     end_offset: usize,
     internal_offset: usize,
+    colspan: usize,
     content: Cow<'t, str>,
 }
 
-fn row(string: &str, spoiler: bool) -> Option<Row<'_>> {
+/// Parses a row.  With `spans`, an empty cell directly between two pipes makes
+/// the cell before it span its column.
+fn row(string: &str, spoiler: bool, spans: bool) -> Option<Row<'_>> {
     let bytes = string.as_bytes();
     let len = string.len();
     let mut cells: Vec<Cell> = vec![];
@@ -232,7 +508,14 @@ fn row(string: &str, spoiler: bool) -> Option<Row<'_>> {
         let cell_matched = scanners::table_cell(&string[offset..], spoiler).unwrap_or(0);
         let pipe_matched = scanners::table_cell_end(&string[offset + cell_matched..]).unwrap_or(0);
 
-        if cell_matched > 0 || pipe_matched > 0 {
+        if spans
+            && cell_matched == 0
+            && pipe_matched > 0
+            && bytes[offset - 1] == b'|'
+            && !cells.is_empty()
+        {
+            cells.last_mut().unwrap().colspan += 1;
+        } else if cell_matched > 0 || pipe_matched > 0 {
             let mut cell = unescape_pipes(&string[offset..offset + cell_matched]);
             trim_cow(&mut cell);
 
@@ -252,6 +535,7 @@ fn row(string: &str, spoiler: bool) -> Option<Row<'_>> {
                 start_offset,
                 end_offset: offset + cell_matched - 1,
                 internal_offset,
+                colspan: 1,
                 content: cell,
             });
         }
@@ -361,12 +645,14 @@ fn unescape_pipes(string: &str) -> Cow<'_, str> {
 // The purpose of this is to prevent a malicious input from generating a very
 // large number of autocompleted cells, which could cause a denial of service
 // vulnerability.
-fn adjust_table_counters(container: Node<'_>, i: usize, end: LineColumn) {
+fn adjust_table_counters(container: Node<'_>, new_row: bool, i: usize, end: LineColumn) {
     let mut ast = container.data_mut();
     let NodeValue::Table(ref mut nt) = ast.value else {
         unreachable!();
     };
-    nt.num_rows += 1;
+    if new_row {
+        nt.num_rows += 1;
+    }
     nt.num_nonempty_cells += i;
     ast.sourcepos.end = end;
 }
@@ -387,6 +673,102 @@ fn get_num_autocompleted_cells(container: Node<'_>) -> usize {
     };
 }
 
-pub fn matches(line: &str, spoiler: bool) -> bool {
-    row(line, spoiler).is_some()
+pub fn matches(line: &str, spoiler: bool, extended: bool) -> bool {
+    if extended {
+        row(&strip_continuation(line).0, spoiler, true).is_some()
+    } else {
+        row(line, spoiler, false).is_some()
+    }
+}
+
+/// Takes the caption of each table below `root` from a paragraph next to it:
+/// `Table: Caption {#id}` (or `: Caption`) before or after the table, as in
+/// Pandoc, or `[Caption]` (or `[Caption][id]`) on the line directly before
+/// or after it, as in MultiMarkdown.  The paragraph becomes a
+/// [`NodeValue::TableCaption`] holding just the caption text, and is moved
+/// into the table as its first child; its inlines are parsed as usual.
+pub fn process_captions(root: Node<'_>) {
+    let tables = root
+        .descendants()
+        .filter(|n| node_matches!(n, NodeValue::Table(..)))
+        .collect::<Vec<_>>();
+
+    for table in tables {
+        let sourcepos = table.data().sourcepos;
+        let candidates = [
+            table
+                .next_sibling()
+                .map(|p| (p, p.data().sourcepos.start.line == sourcepos.end.line + 1)),
+            table
+                .previous_sibling()
+                .map(|p| (p, p.data().sourcepos.end.line + 1 == sourcepos.start.line)),
+        ];
+
+        for (paragraph, adjacent) in candidates.into_iter().flatten() {
+            let Some((range, id)) = (match paragraph.data().value {
+                NodeValue::Paragraph => caption(&paragraph.data().content, adjacent),
+                _ => None,
+            }) else {
+                continue;
+            };
+
+            if let NodeValue::Table(ref mut nt) = table.data_mut().value {
+                nt.id = id;
+            }
+
+            let mut ast = paragraph.data_mut();
+            ast.value = NodeValue::TableCaption;
+            ast.content = ast.content[range.clone()].to_string();
+            // The caption text starts `range.start` bytes into the first line.
+            ast.line_offsets[0] += range.start;
+            let caption_sourcepos = ast.sourcepos;
+            drop(ast);
+
+            // The table spans its caption, wherever it's written.
+            let mut table_ast = table.data_mut();
+            table_ast.sourcepos.start = table_ast.sourcepos.start.min(caption_sourcepos.start);
+            table_ast.sourcepos.end = table_ast.sourcepos.end.max(caption_sourcepos.end);
+            drop(table_ast);
+
+            paragraph.detach();
+            table.prepend(paragraph);
+            break;
+        }
+    }
+}
+
+/// The byte range of the caption text in the paragraph `content`, and the ID
+/// given with it, if `content` is a caption.
+fn caption(content: &str, adjacent: bool) -> Option<(Range<usize>, Option<String>)> {
+    let content = rtrim_slice(content);
+
+    let pandoc = ["Table:", "table:", ": "]
+        .iter()
+        .find_map(|prefix| content.strip_prefix(prefix));
+    if let Some(text) = pandoc {
+        let start = content.len() - text.trim_start_matches([' ', '\t']).len();
+        let (range, id) = match chop_trailing_custom_id(&content[start..]) {
+            Some((rest, id)) => (start..start + rest.len(), Some(id.to_string())),
+            None => (start..content.len(), None),
+        };
+        return Some((range, id)).filter(|(range, _)| !range.is_empty());
+    }
+
+    if !adjacent || content.contains('\n') {
+        return None;
+    }
+    let inner = content.strip_prefix('[')?.strip_suffix(']')?;
+    let (caption, id) = match inner.split_once("][") {
+        Some((caption, id)) => (caption, Some(id.trim())),
+        None => (inner, None),
+    };
+    let start = 1 + caption.len() - caption.trim_start().len();
+    let caption = caption.trim();
+    if caption.is_empty()
+        || caption.contains(['[', ']'])
+        || id.is_some_and(|id| id.is_empty() || id.contains(['[', ']', ' ']))
+    {
+        return None;
+    }
+    Some((start..start + caption.len(), id.map(str::to_string)))
 }
@@ -0,0 +1,208 @@
+//! Typographic replacements, per the `typographer` parse option.
+
+use crate::ctype::isalnum;
+use crate::nodes::NodeTypographic;
+
+/// Symbol replacements, tried in order at each position.
+const SYMBOLS: [(&str, &str); 11] = [
+    ("(c)", "©"),
+    ("(C)", "©"),
+    ("(r)", "®"),
+    ("(R)", "®"),
+    ("(tm)", "™"),
+    ("(TM)", "™"),
+    ("+-", "±"),
+    ("<->", "↔"),
+    ("->", "→"),
+    ("<-", "←"),
+    ("=>", "⇒"),
+];
+
+/// Fraction replacements, which must stand alone as a word.
+const FRACTIONS: [(&str, &str); 5] = [
+    ("1/2", "½"),
+    ("1/3", "⅓"),
+    ("2/3", "⅔"),
+    ("1/4", "¼"),
+    ("3/4", "¾"),
+];
+
+const ORDINAL_SUFFIXES: [&str; 4] = ["st", "nd", "rd", "th"];
+
+/// Bytes a replacement can start with.
+pub(crate) fn can_start(b: u8) -> bool {
+    matches!(
+        b,
+        b'(' | b'+' | b'<' | b'-' | b'=' | b'1' | b'2' | b'3' | b's' | b'n' | b'r' | b't'
+    )
+}
+
+/// The replacement starting at byte `pos` of `input`, if any, and its length.
+/// Context on either side is taken into account: arrows must not be part of
+/// longer runs of arrow characters, fractions must stand alone, and ordinal
+/// suffixes must follow a number with the matching ending.
+pub(crate) fn replacement_at(input: &str, pos: usize) -> Option<(usize, NodeTypographic)> {
+    let bytes = input.as_bytes();
+    if !can_start(bytes[pos]) {
+        return None;
+    }
+    let rest = &input[pos..];
+    let before = pos.checked_sub(1).map(|i| bytes[i]);
+
+    for (source, replacement) in SYMBOLS {
+        if !rest.starts_with(source) {
+            continue;
+        }
+        let after = bytes.get(pos + source.len()).copied();
+        if is_arrow(source)
+            && (before.is_some_and(is_arrow_byte) || after.is_some_and(is_arrow_byte))
+        {
+            continue;
+        }
+        return Some((source.len(), symbol(source, replacement)));
+    }
+
+    for (source, replacement) in FRACTIONS {
+        if rest.starts_with(source)
+            && !before.is_some_and(|b| isalnum(b) || b == b'/' || b == b'.')
+            && !bytes
+                .get(pos + source.len())
+                .is_some_and(|&b| isalnum(b) || b == b'/')
+        {
+            return Some((source.len(), symbol(source, replacement)));
+        }
+    }
+
+    ordinal_suffix_at(bytes, pos).map(|source| {
+        (
+            source.len(),
+            NodeTypographic {
+                source,
+                replacement: None,
+            },
+        )
+    })
+}
+
+/// The position of the first replacement starting within `input[start..end]`.
+pub(crate) fn find_replacement(input: &str, start: usize, end: usize) -> Option<usize> {
+    (start..end).find(|&pos| {
+        input.is_char_boundary(pos)
+            && can_start(input.as_bytes()[pos])
+            && replacement_at(input, pos).is_some()
+    })
+}
+
+/// The byte positions in `input` at which a backslash prevents a replacement
+/// from being made, for writing text that wasn't replaced as CommonMark.
+/// Ordinal suffixes can't be escaped, and arrows never need to be, as their
+/// `<` or `>` is always escaped.
+pub(crate) fn escape_positions(input: &str) -> Vec<usize> {
+    let mut positions = vec![];
+    let mut pos = 0;
+    while let Some(start) = find_replacement(input, pos, input.len()) {
+        let (len, typographic) = replacement_at(input, start).unwrap();
+        if typographic.replacement.is_some() && !is_arrow(typographic.source) {
+            if input.as_bytes()[start].is_ascii_digit() {
+                // Fractions are escaped at the slash.
+                positions.push(start + 1);
+            } else {
+                positions.push(start);
+            }
+        }
+        pos = start + len;
+    }
+    positions
+}
+
+fn symbol(source: &'static str, replacement: &'static str) -> NodeTypographic {
+    NodeTypographic {
+        source,
+        replacement: Some(replacement),
+    }
+}
+
+fn is_arrow(source: &str) -> bool {
+    source.contains(['<', '>'])
+}
+
+fn is_arrow_byte(b: u8) -> bool {
+    matches!(b, b'-' | b'=' | b'<' | b'>')
+}
+
+fn ordinal_suffix_at(bytes: &[u8], pos: usize) -> Option<&'static str> {
+    let suffix = ORDINAL_SUFFIXES
+        .into_iter()
+        .find(|suffix| bytes[pos..].starts_with(suffix.as_bytes()))?;
+    if bytes.get(pos + 2).is_some_and(|&b| isalnum(b)) {
+        return None;
+    }
+
+    let digits = bytes[..pos]
+        .iter()
+        .rev()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    if digits == 0 || (pos > digits && isalnum(bytes[pos - digits - 1])) {
+        return None;
+    }
+
+    let tens = if digits > 1 { bytes[pos - 2] } else { b'0' };
+    let expected = match (tens, bytes[pos - 1]) {
+        (b'1', _) => "th",
+        (_, b'1') => "st",
+        (_, b'2') => "nd",
+        (_, b'3') => "rd",
+        _ => "th",
+    };
+    (suffix == expected).then_some(suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replaced(input: &str, pos: usize) -> Option<&'static str> {
+        replacement_at(input, pos).map(|(_, t)| t.text())
+    }
+
+    #[test]
+    fn symbols() {
+        assert_eq!(replaced("(c) 2024", 0), Some("©"));
+        assert_eq!(replaced("a -> b", 2), Some("→"));
+        assert_eq!(replaced("a <-> b", 2), Some("↔"));
+        assert_eq!(replaced("a --> b", 3), None);
+        assert_eq!(replaced("a ->> b", 2), None);
+    }
+
+    #[test]
+    fn fractions() {
+        assert_eq!(replaced("1/2 cup", 0), Some("½"));
+        assert_eq!(replaced("11/2", 1), None);
+        assert_eq!(replaced("1/2/2024", 0), None);
+        assert_eq!(replaced("a1/2", 1), None);
+    }
+
+    #[test]
+    fn ordinals() {
+        assert_eq!(replaced("1st", 1), Some("st"));
+        assert_eq!(replaced("22nd", 2), Some("nd"));
+        assert_eq!(replaced("113th", 3), Some("th"));
+        assert_eq!(replaced("11st", 2), None);
+        assert_eq!(replaced("1stly", 1), None);
+        assert_eq!(replaced("a1st", 2), None);
+        assert_eq!(replaced("first", 3), None);
+    }
+
+    #[test]
+    fn find() {
+        assert_eq!(find_replacement("x (c) y", 0, 7), Some(2));
+        assert_eq!(find_replacement("5th -> 6th", 0, 3), Some(1));
+        assert_eq!(find_replacement("plain", 0, 5), None);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(escape_positions("(c) 1/2 <- -> 1st +-"), vec![0, 5, 18]);
+    }
+}
@@ -0,0 +1,227 @@
+//! Code fence renderers for diagrams: Mermaid, Graphviz and PlantUML.
+//!
+//! [`DiagramAdapter`] leaves a diagram's source in the page for a client-side
+//! library to draw, and [`CommandDiagramAdapter`] draws it while rendering,
+//! by running a local command such as `dot -Tsvg`.
+
+use std::fmt::{self, Write};
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+
+use crate::adapters::{CodefenceRendererAdapter, FenceMeta};
+use crate::html;
+use crate::nodes::Sourcepos;
+use crate::options::RenderPlugins;
+
+/// The languages of diagram code blocks: `mermaid`, `dot`, `graphviz`,
+/// `plantuml` and `puml`.
+pub const DIAGRAM_LANGUAGES: &[&str] = &["mermaid", "dot", "graphviz", "plantuml", "puml"];
+
+/// How [`DiagramAdapter`] wraps a diagram's source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiagramWrapper {
+    /// `<pre class="mermaid">`, named for the language, as Mermaid expects.
+    #[default]
+    Pre,
+
+    /// `<figure class="diagram">`, holding the `<pre>` and a `<figcaption>`
+    /// with the fence's `title`, if any.
+    Figure,
+}
+
+/// Renders diagram code blocks for drawing by a client-side library, such as
+/// Mermaid's.
+///
+/// The source is escaped, which those libraries undo as they read the
+/// element's text.  [`unescaped`](Self::unescaped) writes it as is instead,
+/// which, like [`unsafe`](crate::options::Render#structfield.unsafe), lets
+/// the source inject HTML into the page.
+///
+/// ```rust
+/// # use comrak::{Options, options::Plugins, markdown_to_html_with_plugins};
+/// # use comrak::plugins::diagrams::DiagramAdapter;
+/// let adapter = DiagramAdapter::new();
+/// let mut plugins = Plugins::default();
+/// adapter.register(&mut plugins.render);
+///
+/// assert_eq!(markdown_to_html_with_plugins("```mermaid\nA --> B\n```\n", &Options::default(), &plugins),
+///            "<pre class=\"mermaid\">A --&gt; B\n</pre>\n");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiagramAdapter {
+    wrapper: DiagramWrapper,
+    unescaped: bool,
+}
+
+impl DiagramAdapter {
+    /// Constructs an adapter writing diagrams in a `<pre>`, escaped.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wraps diagrams as given.
+    pub fn with_wrapper(mut self, wrapper: DiagramWrapper) -> Self {
+        self.wrapper = wrapper;
+        self
+    }
+
+    /// Writes the source of diagrams without escaping.  Only use this for
+    /// trusted input.
+    pub fn unescaped(mut self) -> Self {
+        self.unescaped = true;
+        self
+    }
+
+    /// Registers the adapter for each of [`DIAGRAM_LANGUAGES`].
+    pub fn register<'p>(&'p self, plugins: &mut RenderPlugins<'p>) {
+        for lang in DIAGRAM_LANGUAGES {
+            plugins.codefence_renderers.insert(lang.to_string(), self);
+        }
+    }
+}
+
+impl CodefenceRendererAdapter for DiagramAdapter {
+    fn write(
+        &self,
+        output: &mut dyn Write,
+        lang: &str,
+        meta: &str,
+        code: &str,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        let title = FenceMeta::parse(meta).title().map(String::from);
+        let mut attributes = vec![];
+        if let Some(sourcepos) = sourcepos {
+            attributes.push(("data-sourcepos", sourcepos.to_string()));
+        }
+
+        match self.wrapper {
+            DiagramWrapper::Pre => {
+                attributes.insert(0, ("class", lang.to_string()));
+                html::write_opening_tag(output, "pre", attributes)?;
+            }
+            DiagramWrapper::Figure => {
+                attributes.insert(0, ("class", "diagram".to_string()));
+                html::write_opening_tag(output, "figure", attributes)?;
+                html::write_opening_tag(output, "pre", [("class", lang)])?;
+            }
+        }
+
+        if self.unescaped {
+            output.write_str(code)?;
+        } else {
+            html::escape(output, code)?;
+        }
+        output.write_str("</pre>")?;
+
+        if self.wrapper == DiagramWrapper::Figure {
+            if let Some(title) = title {
+                output.write_str("<figcaption>")?;
+                html::escape(output, &title)?;
+                output.write_str("</figcaption>")?;
+            }
+            output.write_str("</figure>")?;
+        }
+        output.write_str("\n")
+    }
+}
+
+/// Renders diagram code blocks by piping their source through a local
+/// command which writes SVG, and inlining the SVG in a
+/// `<figure class="diagram">`.
+///
+/// If the command can't be run or fails, the block is written as an ordinary
+/// code block.  The command's output is trusted, as it is inlined unescaped.
+///
+/// ```rust,no_run
+/// # use comrak::{Options, options::Plugins, markdown_to_html_with_plugins};
+/// # use comrak::plugins::diagrams::CommandDiagramAdapter;
+/// let adapter = CommandDiagramAdapter::graphviz();
+/// let mut plugins = Plugins::default();
+/// plugins.render.codefence_renderers.insert("dot".to_string(), &adapter);
+///
+/// let html = markdown_to_html_with_plugins("```dot\ndigraph { a -> b }\n```\n", &Options::default(), &plugins);
+/// assert!(html.starts_with("<figure class=\"diagram\"><svg"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CommandDiagramAdapter {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandDiagramAdapter {
+    /// Constructs an adapter running `program` with `args`.
+    pub fn new<S: AsRef<str>>(program: &str, args: &[S]) -> Self {
+        CommandDiagramAdapter {
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.as_ref().to_string()).collect(),
+        }
+    }
+
+    /// Constructs an adapter running Graphviz's `dot -Tsvg`.
+    pub fn graphviz() -> Self {
+        Self::new("dot", &["-Tsvg"])
+    }
+
+    /// Constructs an adapter running `plantuml -tsvg -pipe`.
+    pub fn plantuml() -> Self {
+        Self::new("plantuml", &["-tsvg", "-pipe"])
+    }
+
+    fn run(&self, code: &str) -> Option<String> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+
+        // Write from another thread, so a command writing as it reads can't
+        // deadlock on full pipes.
+        let mut stdin = child.stdin.take()?;
+        let code = code.to_string();
+        let writer = std::thread::spawn(move || stdin.write_all(code.as_bytes()));
+        let result = child.wait_with_output().ok()?;
+        writer.join().ok()?.ok()?;
+        if !result.status.success() {
+            return None;
+        }
+
+        // Drop any XML declaration, doctype or comments before the `<svg>`.
+        let svg = String::from_utf8(result.stdout).ok()?;
+        let start = svg.find("<svg")?;
+        Some(svg[start..].trim_end().to_string())
+    }
+}
+
+impl CodefenceRendererAdapter for CommandDiagramAdapter {
+    fn write(
+        &self,
+        output: &mut dyn Write,
+        lang: &str,
+        _meta: &str,
+        code: &str,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        let mut attributes = vec![];
+        if let Some(sourcepos) = sourcepos {
+            attributes.push(("data-sourcepos", sourcepos.to_string()));
+        }
+
+        match self.run(code) {
+            Some(svg) => {
+                attributes.insert(0, ("class", "diagram".to_string()));
+                html::write_opening_tag(output, "figure", attributes)?;
+                output.write_str(&svg)?;
+                output.write_str("</figure>\n")
+            }
+            None => {
+                html::write_opening_tag(output, "pre", attributes)?;
+                html::write_opening_tag(output, "code", [("class", format!("language-{}", lang))])?;
+                html::escape(output, code)?;
+                output.write_str("</code></pre>\n")
+            }
+        }
+    }
+}
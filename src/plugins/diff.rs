@@ -0,0 +1,140 @@
+//! Code fence renderer for diffs, highlighting the language being changed.
+
+use std::fmt::{self, Write};
+
+use crate::adapters::{CodefenceRendererAdapter, FenceMeta, SyntaxHighlighterAdapter};
+use crate::html;
+use crate::nodes::Sourcepos;
+
+/// Renders unified diffs, such as ```` ```diff ```` code blocks.
+///
+/// Added and removed lines are wrapped in `<span class="inserted">` and
+/// `<span class="deleted">`, with their `+` and `-` markers kept out of text
+/// selections.  The first word of the rest of the info string names the
+/// language of the diffed code, as in ```` ```diff rust ````; with a
+/// [`SyntaxHighlighterAdapter`], each line's code is highlighted as it.
+///
+/// Lines are highlighted one at a time, as a line's old and new neighbours
+/// differ.  Constructs spanning lines, such as block comments or multi-line
+/// strings, are therefore highlighted only on their first line.
+///
+/// ```rust
+/// # use comrak::{Options, options::Plugins, markdown_to_html_with_plugins};
+/// # use comrak::plugins::diff::DiffAdapter;
+/// let adapter = DiffAdapter::new();
+/// let mut plugins = Plugins::default();
+/// plugins.render.codefence_renderers.insert("diff".to_string(), &adapter);
+///
+/// assert_eq!(markdown_to_html_with_plugins("```diff\n-a\n+b\n```\n", &Options::default(), &plugins),
+///            "<pre class=\"diff\"><code class=\"language-diff\">\
+///             <span class=\"deleted\"><span class=\"marker\" style=\"user-select: none\">-</span>a</span>\n\
+///             <span class=\"inserted\"><span class=\"marker\" style=\"user-select: none\">+</span>b</span>\n\
+///             </code></pre>\n");
+/// ```
+#[derive(Clone, Default)]
+pub struct DiffAdapter<'a> {
+    highlighter: Option<&'a dyn SyntaxHighlighterAdapter>,
+}
+
+impl<'a> DiffAdapter<'a> {
+    /// Constructs an adapter which doesn't highlight the diffed code.
+    pub fn new() -> Self {
+        DiffAdapter { highlighter: None }
+    }
+
+    /// Highlights the diffed code with `highlighter`, when its language is
+    /// given.
+    pub fn with_highlighter(mut self, highlighter: &'a dyn SyntaxHighlighterAdapter) -> Self {
+        self.highlighter = Some(highlighter);
+        self
+    }
+
+    fn write_code(&self, output: &mut dyn Write, lang: Option<&str>, code: &str) -> fmt::Result {
+        match (self.highlighter, lang) {
+            (Some(highlighter), Some(_)) => highlighter.write_highlighted(output, lang, code),
+            _ => html::escape(output, code),
+        }
+    }
+}
+
+impl fmt::Debug for DiffAdapter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiffAdapter")
+            .field("highlighter", &self.highlighter.is_some())
+            .finish()
+    }
+}
+
+impl CodefenceRendererAdapter for DiffAdapter<'_> {
+    fn write(
+        &self,
+        output: &mut dyn Write,
+        lang: &str,
+        meta: &str,
+        code: &str,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        let meta = FenceMeta::parse(meta);
+        let code_lang = meta.flags.first().map(String::as_str);
+
+        let mut pre_attributes = vec![("class", "diff".to_string())];
+        if let Some(sourcepos) = sourcepos {
+            pre_attributes.push(("data-sourcepos", sourcepos.to_string()));
+        }
+        html::write_opening_tag(output, "pre", pre_attributes)?;
+        let mut code_attributes = vec![("class", format!("language-{}", lang))];
+        if let Some(code_lang) = code_lang {
+            code_attributes.push(("data-lang", code_lang.to_string()));
+        }
+        html::write_opening_tag(output, "code", code_attributes)?;
+
+        // "--- a/file" is a header before the first hunk, but a removed
+        // "-- comment" within one.
+        let mut in_hunk = false;
+        for line in code.lines() {
+            let class = if line.starts_with("diff ") {
+                in_hunk = false;
+                Some("header")
+            } else if line.starts_with("@@") {
+                in_hunk = true;
+                Some("hunk")
+            } else if !in_hunk
+                && ["index ", "--- ", "+++ "]
+                    .iter()
+                    .any(|prefix| line.starts_with(prefix))
+            {
+                Some("header")
+            } else {
+                None
+            };
+
+            if let Some(class) = class {
+                write!(output, "<span class=\"{}\">", class)?;
+                html::escape(output, line)?;
+                output.write_str("</span>\n")?;
+                continue;
+            }
+
+            let class = match line.as_bytes().first() {
+                Some(b'+') => "inserted",
+                Some(b'-') => "deleted",
+                Some(b' ') => "unchanged",
+                _ => {
+                    html::escape(output, line)?;
+                    output.write_str("\n")?;
+                    continue;
+                }
+            };
+            let (marker, rest) = line.split_at(1);
+            write!(
+                output,
+                "<span class=\"{}\"><span class=\"marker\" style=\"user-select: none\">{}</span>",
+                class, marker
+            )?;
+            self.write_code(output, code_lang, rest)?;
+            output.write_str("</span>\n")?;
+        }
+
+        output.write_str("</code></pre>\n")
+    }
+}
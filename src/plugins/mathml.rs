@@ -0,0 +1,1110 @@
+//! Adapter converting TeX math into MathML Core.
+//!
+//! Supports the common subset of LaTeX math: fractions, roots, scripts,
+//! Greek letters, operators and relations, functions, accents, font
+//! commands, stretchy delimiters, matrices and `cases`, and the `align`
+//! family of environments.  Math using anything else is rendered as it is
+//! without an adapter, for a client-side library, with the reason in a
+//! `data-math-error` attribute, and a [`MathDiagnostic`] is recorded.
+
+use std::fmt::{self, Write};
+use std::sync::Mutex;
+
+use crate::adapters::{MathRendererAdapter, MathRendering};
+use crate::html;
+use crate::nodes::Sourcepos;
+
+/// Math renderer plugin producing MathML Core.
+///
+/// ```rust
+/// # use comrak::{Options, options::Plugins, markdown_to_html_with_plugins};
+/// # use comrak::plugins::mathml::MathMLAdapter;
+/// let mut options = Options::default();
+/// options.extension.math_dollars = true;
+/// let adapter = MathMLAdapter::new();
+/// let mut plugins = Plugins::default();
+/// plugins.render.math_renderer_adapter = Some(&adapter);
+///
+/// assert_eq!(markdown_to_html_with_plugins("$\\frac{1}{x^2}$\n", &options, &plugins),
+///            "<p><math><mfrac><mn>1</mn><msup><mi>x</mi><mn>2</mn></msup></mfrac></math></p>\n");
+/// ```
+#[derive(Debug, Default)]
+pub struct MathMLAdapter {
+    diagnostics: Mutex<Vec<MathDiagnostic>>,
+}
+
+/// A math span or block that couldn't be converted to MathML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MathDiagnostic {
+    /// The TeX source of the math.
+    pub literal: String,
+
+    /// Why the math couldn't be converted.
+    pub error: Error,
+
+    /// The source position of the math, when
+    /// [`sourcepos`](crate::options::Render#structfield.sourcepos) is enabled.
+    pub sourcepos: Option<Sourcepos>,
+}
+
+/// An error converting TeX to MathML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A command that isn't supported, such as `\boxed`.
+    UnsupportedCommand(String),
+
+    /// An environment that isn't supported, such as `tikzcd`.
+    UnsupportedEnvironment(String),
+
+    /// Malformed TeX, such as unbalanced braces.
+    Syntax(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnsupportedCommand(name) => write!(f, "unsupported command \\{}", name),
+            Error::UnsupportedEnvironment(name) => write!(f, "unsupported environment {}", name),
+            Error::Syntax(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl MathMLAdapter {
+    /// Construct a new `MathMLAdapter`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the math that couldn't be converted so far, in the order it
+    /// was rendered, and clears the list.
+    pub fn take_diagnostics(&self) -> Vec<MathDiagnostic> {
+        std::mem::take(&mut self.diagnostics.lock().unwrap())
+    }
+}
+
+impl MathRendererAdapter for MathMLAdapter {
+    fn write(
+        &self,
+        output: &mut dyn Write,
+        literal: &str,
+        display_math: bool,
+        sourcepos: Option<Sourcepos>,
+    ) -> Result<MathRendering, fmt::Error> {
+        match tex_to_mathml(literal, display_math) {
+            Ok(mathml) => {
+                let attrs = mathml.strip_prefix("<math").unwrap();
+                output.write_str("<math")?;
+                if let Some(sourcepos) = sourcepos {
+                    write!(output, " data-sourcepos=\"{}\"", sourcepos)?;
+                }
+                output.write_str(attrs)?;
+                Ok(MathRendering::Written)
+            }
+            Err(error) => {
+                let message = error.to_string();
+                self.diagnostics.lock().unwrap().push(MathDiagnostic {
+                    literal: literal.to_string(),
+                    error,
+                    sourcepos,
+                });
+                Ok(MathRendering::Failed(message))
+            }
+        }
+    }
+}
+
+/// Converts TeX math to a MathML Core `<math>` element.
+///
+/// ```rust
+/// # use comrak::plugins::mathml::tex_to_mathml;
+/// assert_eq!(tex_to_mathml("\\alpha_i \\leq 1", false).unwrap(),
+///            "<math><msub><mi>α</mi><mi>i</mi></msub><mo>≤</mo><mn>1</mn></math>");
+/// assert!(tex_to_mathml("\\boxed{x}", true).is_err());
+/// ```
+pub fn tex_to_mathml(tex: &str, display: bool) -> Result<String, Error> {
+    let mut parser = TexParser {
+        tex,
+        pos: 0,
+        depth: 0,
+        display,
+    };
+    let list = parser.parse_list()?;
+    if parser.pos < tex.len() {
+        return Err(parser.unexpected());
+    }
+
+    let mut out = String::from(if display {
+        "<math display=\"block\">"
+    } else {
+        "<math>"
+    });
+    for elem in list {
+        out.push_str(&elem.markup);
+    }
+    out.push_str("</math>");
+    Ok(out)
+}
+
+struct Elem {
+    markup: String,
+    /// Whether scripts go under and over the element in display style, as for
+    /// `\sum` and `\lim`.
+    limits: bool,
+}
+
+impl Elem {
+    fn new(markup: String) -> Self {
+        Elem {
+            markup,
+            limits: false,
+        }
+    }
+
+    fn token(tag: &str, text: &str) -> Self {
+        Elem::new(format!("<{tag}>{}</{tag}>", escape(text)))
+    }
+}
+
+fn row(list: Vec<Elem>) -> String {
+    if list.len() == 1 {
+        return list.into_iter().next().unwrap().markup;
+    }
+    let mut out = String::from("<mrow>");
+    for elem in list {
+        out.push_str(&elem.markup);
+    }
+    out.push_str("</mrow>");
+    out
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::new();
+    html::escape(&mut out, text).unwrap();
+    out
+}
+
+/// How deeply groups and commands may nest, so that pathological input can't
+/// overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+struct TexParser<'t> {
+    tex: &'t str,
+    pos: usize,
+    /// The number of lists, atoms and commands being parsed.
+    depth: usize,
+    display: bool,
+}
+
+impl TexParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.tex[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn unexpected(&self) -> Error {
+        Error::Syntax(match (self.peek_command(), self.peek()) {
+            (Some(name), _) => format!("unexpected \\{}", name),
+            (None, Some(c)) => format!("unexpected {}", c),
+            (None, None) => "unexpected end of math".to_string(),
+        })
+    }
+
+    /// Returns the name of the command at the current position, if any.
+    fn peek_command(&self) -> Option<&str> {
+        let rest = self.tex[self.pos..].strip_prefix('\\')?;
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        if len > 0 {
+            Some(&rest[..len])
+        } else {
+            rest.chars().next().map(|c| &rest[..c.len_utf8()])
+        }
+    }
+
+    fn command(&mut self) -> Option<String> {
+        let name = self.peek_command()?.to_string();
+        self.pos += 1 + name.len();
+        Some(name)
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(Error::Syntax(format!("expected {}", c)))
+        }
+    }
+
+    /// Runs `parse` one level deeper, failing if the nesting is too deep.
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::Syntax("nested too deeply".to_string()));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Parses elements up to the end of the input or of the enclosing group,
+    /// cell, row or `\left` ... `\right` pair.
+    fn parse_list(&mut self) -> Result<Vec<Elem>, Error> {
+        self.nested(Self::parse_list_items)
+    }
+
+    fn parse_list_items(&mut self) -> Result<Vec<Elem>, Error> {
+        let mut list = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some('}' | '&' | ']') => break,
+                Some('\\')
+                    if matches!(self.peek_command(), Some("\\" | "cr" | "end" | "right")) =>
+                {
+                    break;
+                }
+                Some('^' | '_' | '\'') => self.parse_scripts(&mut list)?,
+                _ => {
+                    if matches!(self.peek_command(), Some("displaystyle" | "textstyle")) {
+                        let display = self.command() == Some("displaystyle".to_string());
+                        let rest = self.parse_list()?;
+                        list.push(Elem::new(format!(
+                            "<mstyle displaystyle=\"{}\">{}</mstyle>",
+                            display,
+                            row(rest)
+                        )));
+                        break;
+                    }
+                    let elem = self.parse_atom()?;
+                    list.push(elem);
+                }
+            }
+        }
+        Ok(list)
+    }
+
+    fn parse_scripts(&mut self, list: &mut Vec<Elem>) -> Result<(), Error> {
+        let base = list
+            .pop()
+            .unwrap_or_else(|| Elem::new("<mrow></mrow>".to_string()));
+        let mut primes = String::new();
+        while self.peek() == Some('\'') {
+            self.bump();
+            primes.push('′');
+        }
+
+        let (mut sub, mut sup) = (None, None);
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('^') if sup.is_none() => {
+                    self.bump();
+                    sup = Some(self.parse_arg()?);
+                }
+                Some('_') if sub.is_none() => {
+                    self.bump();
+                    sub = Some(self.parse_arg()?);
+                }
+                Some('^' | '_') => return Err(Error::Syntax("double script".to_string())),
+                _ => break,
+            }
+        }
+
+        let sup = match (primes.is_empty(), sup) {
+            (true, sup) => sup,
+            (false, None) => Some(format!("<mo>{}</mo>", primes)),
+            (false, Some(sup)) => Some(format!("<mrow><mo>{}</mo>{}</mrow>", primes, sup)),
+        };
+
+        let limits = base.limits && self.display;
+        let markup = match (sub, sup) {
+            (Some(sub), Some(sup)) if limits => {
+                format!("<munderover>{}{}{}</munderover>", base.markup, sub, sup)
+            }
+            (Some(sub), Some(sup)) => format!("<msubsup>{}{}{}</msubsup>", base.markup, sub, sup),
+            (Some(sub), None) if limits => format!("<munder>{}{}</munder>", base.markup, sub),
+            (Some(sub), None) => format!("<msub>{}{}</msub>", base.markup, sub),
+            (None, Some(sup)) if limits => format!("<mover>{}{}</mover>", base.markup, sup),
+            (None, Some(sup)) => format!("<msup>{}{}</msup>", base.markup, sup),
+            (None, None) => base.markup,
+        };
+        list.push(Elem::new(markup));
+        Ok(())
+    }
+
+    /// Parses the argument of a command or script: a group, a command or a
+    /// single character.
+    fn parse_arg(&mut self) -> Result<String, Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                let list = self.parse_list()?;
+                self.expect('}')?;
+                Ok(row(list))
+            }
+            Some('\\') => Ok(self.parse_command()?.markup),
+            Some(c) if c.is_ascii_digit() => {
+                self.bump();
+                Ok(Elem::token("mn", &c.to_string()).markup)
+            }
+            Some(_) => Ok(self.parse_atom()?.markup),
+            None => Err(Error::Syntax("missing argument".to_string())),
+        }
+    }
+
+    /// Reads a braced argument as raw text.
+    fn raw_arg(&mut self) -> Result<&str, Error> {
+        self.expect('{')?;
+        let start = self.pos;
+        let mut depth = 0;
+        while let Some(c) = self.bump() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => return Ok(&self.tex[start..self.pos - 1]),
+                '}' => depth -= 1,
+                '\\' => {
+                    self.bump();
+                }
+                _ => {}
+            }
+        }
+        Err(Error::Syntax("unbalanced braces".to_string()))
+    }
+
+    fn parse_atom(&mut self) -> Result<Elem, Error> {
+        self.nested(Self::parse_atom_body)
+    }
+
+    fn parse_atom_body(&mut self) -> Result<Elem, Error> {
+        let Some(c) = self.peek() else {
+            return Err(self.unexpected());
+        };
+        match c {
+            '{' => {
+                self.bump();
+                let list = self.parse_list()?;
+                self.expect('}')?;
+                Ok(Elem::new(row(list)))
+            }
+            '\\' => self.parse_command(),
+            '0'..='9' | '.' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.bump();
+                }
+                let number = &self.tex[start..self.pos];
+                if number == "." {
+                    Ok(Elem::token("mo", "."))
+                } else {
+                    Ok(Elem::token("mn", number))
+                }
+            }
+            '~' => {
+                self.bump();
+                Ok(Elem::new(
+                    "<mspace width=\"0.3333em\"></mspace>".to_string(),
+                ))
+            }
+            '#' | '$' | '%' | '}' | '&' | '^' | '_' => Err(self.unexpected()),
+            _ => {
+                self.bump();
+                if c.is_alphabetic() {
+                    Ok(Elem::token("mi", &c.to_string()))
+                } else {
+                    let c = match c {
+                        '-' => '−',
+                        '*' => '∗',
+                        c => c,
+                    };
+                    Ok(Elem::token("mo", &c.to_string()))
+                }
+            }
+        }
+    }
+
+    fn parse_command(&mut self) -> Result<Elem, Error> {
+        self.nested(Self::parse_command_body)
+    }
+
+    fn parse_command_body(&mut self) -> Result<Elem, Error> {
+        let Some(name) = self.command() else {
+            return Err(Error::Syntax("trailing \\".to_string()));
+        };
+        let name = name.as_str();
+
+        if let Some(c) = greek(name) {
+            return Ok(if c.is_uppercase() {
+                Elem::new(format!("<mi mathvariant=\"normal\">{}</mi>", c))
+            } else {
+                Elem::token("mi", &c.to_string())
+            });
+        }
+        if let Some(c) = large_operator(name) {
+            return Ok(Elem {
+                markup: format!("<mo>{}</mo>", c),
+                limits: !matches!(name, "int" | "iint" | "iiint" | "oint"),
+            });
+        }
+        if let Some(c) = operator(name) {
+            return Ok(Elem::token("mo", c));
+        }
+        if let Some(c) = identifier(name) {
+            return Ok(Elem::token("mi", c));
+        }
+        if FUNCTIONS.contains(&name) {
+            return Ok(Elem::token("mi", name));
+        }
+        if LIMIT_FUNCTIONS.contains(&name) {
+            return Ok(Elem {
+                markup: format!("<mi>{}</mi>", name),
+                limits: true,
+            });
+        }
+        if let Some(width) = space(name) {
+            return Ok(Elem::new(format!("<mspace width=\"{}\"></mspace>", width)));
+        }
+        if let Some((accent, over)) = accent(name) {
+            let base = self.parse_arg()?;
+            return Ok(Elem::new(if over {
+                format!("<mover accent=\"true\">{}<mo>{}</mo></mover>", base, accent)
+            } else {
+                format!(
+                    "<munder accentunder=\"true\">{}<mo>{}</mo></munder>",
+                    base, accent
+                )
+            }));
+        }
+        if let Some(variant) = Variant::from_command(name) {
+            let text = self.raw_arg()?;
+            return Ok(Elem::new(variant.apply(text)?));
+        }
+        if let Some(size) = big(name) {
+            let delimiter = self.parse_delimiter()?;
+            return Ok(Elem::new(format!(
+                "<mo minsize=\"{size}\" maxsize=\"{size}\">{}</mo>",
+                escape(delimiter)
+            )));
+        }
+
+        match name {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let num = self.parse_arg()?;
+                let den = self.parse_arg()?;
+                let frac = format!("<mfrac>{}{}</mfrac>", num, den);
+                Ok(Elem::new(match name {
+                    "dfrac" | "cfrac" => {
+                        format!("<mstyle displaystyle=\"true\">{}</mstyle>", frac)
+                    }
+                    "tfrac" => format!("<mstyle displaystyle=\"false\">{}</mstyle>", frac),
+                    _ => frac,
+                }))
+            }
+            "binom" => {
+                let n = self.parse_arg()?;
+                let k = self.parse_arg()?;
+                Ok(Elem::new(format!(
+                    "<mrow><mo>(</mo><mfrac linethickness=\"0\">{}{}</mfrac><mo>)</mo></mrow>",
+                    n, k
+                )))
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                let index = if self.peek() == Some('[') {
+                    self.bump();
+                    let list = self.parse_list()?;
+                    self.expect(']')?;
+                    Some(row(list))
+                } else {
+                    None
+                };
+                let radicand = self.parse_arg()?;
+                Ok(Elem::new(match index {
+                    Some(index) => format!("<mroot>{}{}</mroot>", radicand, index),
+                    None => format!("<msqrt>{}</msqrt>", radicand),
+                }))
+            }
+            "overset" | "stackrel" | "underset" => {
+                let script = self.parse_arg()?;
+                let base = self.parse_arg()?;
+                Ok(Elem::new(if name == "underset" {
+                    format!("<munder>{}{}</munder>", base, script)
+                } else {
+                    format!("<mover>{}{}</mover>", base, script)
+                }))
+            }
+            "overbrace" | "underbrace" => {
+                let base = self.parse_arg()?;
+                Ok(Elem {
+                    markup: if name == "overbrace" {
+                        format!("<mover>{}<mo stretchy=\"true\">⏞</mo></mover>", base)
+                    } else {
+                        format!("<munder>{}<mo stretchy=\"true\">⏟</mo></munder>", base)
+                    },
+                    limits: true,
+                })
+            }
+            "text" | "textrm" | "textnormal" | "mbox" | "textit" | "textbf" => {
+                let text = self.raw_arg()?;
+                Ok(Elem::token("mtext", text))
+            }
+            "operatorname" => {
+                let text = self.raw_arg()?;
+                Ok(Elem::token("mi", text))
+            }
+            "left" => {
+                let open = self.parse_delimiter()?;
+                let inner = self.parse_list()?;
+                if self.command().as_deref() != Some("right") {
+                    return Err(Error::Syntax("\\left without \\right".to_string()));
+                }
+                let close = self.parse_delimiter()?;
+                let mut out = String::from("<mrow>");
+                if open != "." {
+                    write!(
+                        out,
+                        "<mo fence=\"true\" form=\"prefix\">{}</mo>",
+                        escape(open)
+                    )
+                    .unwrap();
+                }
+                for elem in inner {
+                    out.push_str(&elem.markup);
+                }
+                if close != "." {
+                    write!(
+                        out,
+                        "<mo fence=\"true\" form=\"postfix\">{}</mo>",
+                        escape(close)
+                    )
+                    .unwrap();
+                }
+                out.push_str("</mrow>");
+                Ok(Elem::new(out))
+            }
+            "middle" => {
+                let delimiter = self.parse_delimiter()?;
+                Ok(Elem::new(format!(
+                    "<mo stretchy=\"true\">{}</mo>",
+                    escape(delimiter)
+                )))
+            }
+            "not" => {
+                let next = self.parse_atom()?;
+                let negated = match next.markup.strip_prefix("<mo>") {
+                    Some(rest) => format!("<mo>{}", rest.replacen("</mo>", "\u{338}</mo>", 1)),
+                    None => return Err(Error::Syntax("\\not before a non-operator".to_string())),
+                };
+                Ok(Elem::new(negated))
+            }
+            "bmod" => Ok(Elem::token("mo", "mod")),
+            "pmod" => {
+                let arg = self.parse_arg()?;
+                Ok(Elem::new(format!(
+                    "<mrow><mspace width=\"1em\"></mspace><mo>(</mo><mi>mod</mi>\
+                     <mspace width=\"0.3333em\"></mspace>{}<mo>)</mo></mrow>",
+                    arg
+                )))
+            }
+            "nonumber" | "notag" => Ok(Elem::new(String::new())),
+            "begin" => self.parse_environment(),
+            _ => Err(Error::UnsupportedCommand(name.to_string())),
+        }
+    }
+
+    /// Parses the delimiter following `\left`, `\right`, `\big` and the like.
+    fn parse_delimiter(&mut self) -> Result<&'static str, Error> {
+        self.skip_whitespace();
+        if self.peek() == Some('\\') {
+            let name = self.command().unwrap_or_default();
+            return match name.as_str() {
+                "{" | "lbrace" => Ok("{"),
+                "}" | "rbrace" => Ok("}"),
+                "|" | "Vert" | "lVert" | "rVert" => Ok("‖"),
+                "vert" | "lvert" | "rvert" => Ok("|"),
+                "langle" => Ok("⟨"),
+                "rangle" => Ok("⟩"),
+                "lfloor" => Ok("⌊"),
+                "rfloor" => Ok("⌋"),
+                "lceil" => Ok("⌈"),
+                "rceil" => Ok("⌉"),
+                _ => Err(Error::Syntax(format!("bad delimiter \\{}", name))),
+            };
+        }
+        let delimiter = match self.bump() {
+            Some('(') => "(",
+            Some(')') => ")",
+            Some('[') => "[",
+            Some(']') => "]",
+            Some('|') => "|",
+            Some('/') => "/",
+            Some('.') => ".",
+            Some('<') => "⟨",
+            Some('>') => "⟩",
+            _ => return Err(Error::Syntax("missing delimiter".to_string())),
+        };
+        Ok(delimiter)
+    }
+
+    fn parse_environment(&mut self) -> Result<Elem, Error> {
+        let name = self.raw_arg()?.to_string();
+        let (open, close, align) = match name.as_str() {
+            "matrix" | "smallmatrix" => ("", "", Align::Center),
+            "pmatrix" => ("(", ")", Align::Center),
+            "bmatrix" => ("[", "]", Align::Center),
+            "Bmatrix" => ("{", "}", Align::Center),
+            "vmatrix" => ("|", "|", Align::Center),
+            "Vmatrix" => ("‖", "‖", Align::Center),
+            "cases" => ("{", "", Align::Left),
+            "array" => {
+                self.raw_arg()?;
+                ("", "", Align::Center)
+            }
+            "align" | "align*" | "aligned" | "split" | "alignat" | "alignat*" | "alignedat" => {
+                if name.starts_with("alignat") || name == "alignedat" {
+                    self.raw_arg()?;
+                }
+                ("", "", Align::RightLeft)
+            }
+            "gather" | "gather*" | "gathered" | "equation" | "equation*" => ("", "", Align::Center),
+            _ => return Err(Error::UnsupportedEnvironment(name)),
+        };
+
+        let mut rows: Vec<Vec<String>> = vec![vec![]];
+        loop {
+            let cell = self.parse_list()?;
+            rows.last_mut().unwrap().push(row(cell));
+            match self.peek() {
+                Some('&') => {
+                    self.bump();
+                }
+                Some('\\') => match self.command().as_deref() {
+                    Some("\\" | "cr") => {
+                        self.skip_whitespace();
+                        if self.peek() == Some('[') {
+                            // Skip an explicit row spacing like `\\[2pt]`.
+                            while self.bump().is_some_and(|c| c != ']') {}
+                        }
+                        rows.push(vec![]);
+                    }
+                    Some("end") => {
+                        if self.raw_arg()? != name {
+                            return Err(Error::Syntax(format!(
+                                "\\begin{{{}}} ended by another \\end",
+                                name
+                            )));
+                        }
+                        break;
+                    }
+                    _ => return Err(Error::Syntax("\\right without \\left".to_string())),
+                },
+                _ => return Err(Error::Syntax(format!("\\begin{{{}}} without \\end", name))),
+            }
+        }
+        // A trailing `\\` doesn't start another row.
+        if rows
+            .last()
+            .is_some_and(|r| r.len() == 1 && r[0] == "<mrow></mrow>")
+        {
+            rows.pop();
+        }
+
+        let display = matches!(align, Align::RightLeft) || name.starts_with("gather");
+        let mut out = String::new();
+        if !open.is_empty() || !close.is_empty() {
+            out.push_str("<mrow>");
+        }
+        if !open.is_empty() {
+            write!(out, "<mo fence=\"true\" form=\"prefix\">{}</mo>", open).unwrap();
+        }
+        if display {
+            out.push_str("<mtable displaystyle=\"true\">");
+        } else {
+            out.push_str("<mtable>");
+        }
+        for cells in rows {
+            out.push_str("<mtr>");
+            for (i, cell) in cells.into_iter().enumerate() {
+                match align {
+                    Align::Center => out.push_str("<mtd>"),
+                    Align::Left => out.push_str("<mtd style=\"text-align: left\">"),
+                    Align::RightLeft if i % 2 == 0 => {
+                        out.push_str("<mtd style=\"text-align: right\">")
+                    }
+                    Align::RightLeft => out.push_str("<mtd style=\"text-align: left\">"),
+                }
+                out.push_str(&cell);
+                out.push_str("</mtd>");
+            }
+            out.push_str("</mtr>");
+        }
+        out.push_str("</mtable>");
+        if !close.is_empty() {
+            write!(out, "<mo fence=\"true\" form=\"postfix\">{}</mo>", close).unwrap();
+        }
+        if !open.is_empty() || !close.is_empty() {
+            out.push_str("</mrow>");
+        }
+        Ok(Elem::new(out))
+    }
+}
+
+enum Align {
+    Center,
+    Left,
+    /// Alternating right- and left-aligned columns, as in `align`.
+    RightLeft,
+}
+
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "coth", "log", "ln", "lg", "exp", "deg", "dim", "ker", "arg", "hom",
+];
+
+const LIMIT_FUNCTIONS: &[&str] = &[
+    "lim", "liminf", "limsup", "max", "min", "sup", "inf", "det", "gcd", "Pr",
+];
+
+fn greek(name: &str) -> Option<char> {
+    Some(match name {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ϵ',
+        "varepsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "vartheta" => 'ϑ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "omicron" => 'ο',
+        "pi" => 'π',
+        "varpi" => 'ϖ',
+        "rho" => 'ρ',
+        "varrho" => 'ϱ',
+        "sigma" => 'σ',
+        "varsigma" => 'ς',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'ϕ',
+        "varphi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        _ => return None,
+    })
+}
+
+fn large_operator(name: &str) -> Option<char> {
+    Some(match name {
+        "sum" => '∑',
+        "prod" => '∏',
+        "coprod" => '∐',
+        "bigcup" => '⋃',
+        "bigcap" => '⋂',
+        "bigvee" => '⋁',
+        "bigwedge" => '⋀',
+        "bigoplus" => '⨁',
+        "bigotimes" => '⨂',
+        "bigodot" => '⨀',
+        "biguplus" => '⨄',
+        "int" => '∫',
+        "iint" => '∬',
+        "iiint" => '∭',
+        "oint" => '∮',
+        _ => return None,
+    })
+}
+
+fn operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "{" | "lbrace" => "{",
+        "}" | "rbrace" => "}",
+        "|" | "Vert" => "‖",
+        "vert" | "mid" => "|",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "%" => "%",
+        "&" => "&",
+        "#" => "#",
+        "$" => "$",
+        "_" => "_",
+        "times" => "×",
+        "cdot" => "⋅",
+        "pm" => "±",
+        "mp" => "∓",
+        "div" => "÷",
+        "ast" => "∗",
+        "star" => "⋆",
+        "circ" => "∘",
+        "bullet" => "∙",
+        "oplus" => "⊕",
+        "ominus" => "⊖",
+        "otimes" => "⊗",
+        "oslash" => "⊘",
+        "odot" => "⊙",
+        "cup" => "∪",
+        "cap" => "∩",
+        "setminus" => "∖",
+        "wedge" | "land" => "∧",
+        "vee" | "lor" => "∨",
+        "neg" | "lnot" => "¬",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "ll" => "≪",
+        "gg" => "≫",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "supset" => "⊃",
+        "subseteq" => "⊆",
+        "supseteq" => "⊇",
+        "perp" => "⊥",
+        "parallel" => "∥",
+        "forall" => "∀",
+        "exists" => "∃",
+        "nexists" => "∄",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        "longrightarrow" => "⟶",
+        "longleftarrow" => "⟵",
+        "Longrightarrow" => "⟹",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "colon" => ":",
+        "prime" => "′",
+        _ => return None,
+    })
+}
+
+fn identifier(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "emptyset" | "varnothing" => "∅",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "aleph" => "ℵ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "wp" => "℘",
+        "angle" => "∠",
+        "triangle" => "△",
+        "top" => "⊤",
+        "bot" => "⊥",
+        _ => return None,
+    })
+}
+
+fn space(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "," | "thinspace" => "0.1667em",
+        ":" | ">" | "medspace" => "0.2222em",
+        ";" | "thickspace" => "0.2778em",
+        " " => "0.3333em",
+        "quad" => "1em",
+        "qquad" => "2em",
+        "!" | "negthinspace" => "-0.1667em",
+        _ => return None,
+    })
+}
+
+/// Returns the accent of a command, and whether it goes over its argument.
+fn accent(name: &str) -> Option<(&'static str, bool)> {
+    Some(match name {
+        "hat" | "widehat" => ("^", true),
+        "check" => ("ˇ", true),
+        "tilde" | "widetilde" => ("~", true),
+        "acute" => ("´", true),
+        "grave" => ("`", true),
+        "breve" => ("˘", true),
+        "bar" | "overline" => ("‾", true),
+        "vec" | "overrightarrow" => ("→", true),
+        "overleftarrow" => ("←", true),
+        "dot" => ("˙", true),
+        "ddot" => ("¨", true),
+        "underline" => ("‾", false),
+        _ => return None,
+    })
+}
+
+fn big(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "big" | "bigl" | "bigr" | "bigm" => "1.2em",
+        "Big" | "Bigl" | "Bigr" | "Bigm" => "1.8em",
+        "bigg" | "biggl" | "biggr" | "biggm" => "2.4em",
+        "Bigg" | "Biggl" | "Biggr" | "Biggm" => "3em",
+        _ => return None,
+    })
+}
+
+/// A font command, applied by mapping letters and digits to the Unicode
+/// mathematical alphanumeric symbols.
+#[derive(Clone, Copy)]
+enum Variant {
+    Normal,
+    Bold,
+    Italic,
+    DoubleStruck,
+    Script,
+    Fraktur,
+    SansSerif,
+    Monospace,
+}
+
+impl Variant {
+    fn from_command(name: &str) -> Option<Self> {
+        Some(match name {
+            "mathrm" => Variant::Normal,
+            "mathbf" | "boldsymbol" | "bm" => Variant::Bold,
+            "mathit" => Variant::Italic,
+            "mathbb" => Variant::DoubleStruck,
+            "mathcal" | "mathscr" => Variant::Script,
+            "mathfrak" => Variant::Fraktur,
+            "mathsf" => Variant::SansSerif,
+            "mathtt" => Variant::Monospace,
+            _ => return None,
+        })
+    }
+
+    fn apply(self, text: &str) -> Result<String, Error> {
+        let text = text.trim();
+        if text.is_empty() || !text.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ') {
+            return Err(Error::Syntax(format!(
+                "unsupported font argument {:?}",
+                text
+            )));
+        }
+
+        if let Variant::Normal = self {
+            return Ok(if text.chars().count() == 1 {
+                format!("<mi mathvariant=\"normal\">{}</mi>", escape(text))
+            } else {
+                format!("<mi>{}</mi>", escape(text))
+            });
+        }
+
+        let mapped = text
+            .chars()
+            .filter(|c| *c != ' ')
+            .map(|c| self.map(c))
+            .collect::<String>();
+        if text.chars().all(|c| c.is_ascii_digit()) {
+            Ok(format!("<mn>{}</mn>", mapped))
+        } else {
+            Ok(format!("<mi>{}</mi>", mapped))
+        }
+    }
+
+    fn map(self, c: char) -> char {
+        let exception = match (self, c) {
+            (Variant::Italic, 'h') => Some('ℎ'),
+            (Variant::DoubleStruck, 'C') => Some('ℂ'),
+            (Variant::DoubleStruck, 'H') => Some('ℍ'),
+            (Variant::DoubleStruck, 'N') => Some('ℕ'),
+            (Variant::DoubleStruck, 'P') => Some('ℙ'),
+            (Variant::DoubleStruck, 'Q') => Some('ℚ'),
+            (Variant::DoubleStruck, 'R') => Some('ℝ'),
+            (Variant::DoubleStruck, 'Z') => Some('ℤ'),
+            (Variant::Script, 'B') => Some('ℬ'),
+            (Variant::Script, 'E') => Some('ℰ'),
+            (Variant::Script, 'F') => Some('ℱ'),
+            (Variant::Script, 'H') => Some('ℋ'),
+            (Variant::Script, 'I') => Some('ℐ'),
+            (Variant::Script, 'L') => Some('ℒ'),
+            (Variant::Script, 'M') => Some('ℳ'),
+            (Variant::Script, 'R') => Some('ℛ'),
+            (Variant::Script, 'e') => Some('ℯ'),
+            (Variant::Script, 'g') => Some('ℊ'),
+            (Variant::Script, 'o') => Some('ℴ'),
+            (Variant::Fraktur, 'C') => Some('ℭ'),
+            (Variant::Fraktur, 'H') => Some('ℌ'),
+            (Variant::Fraktur, 'I') => Some('ℑ'),
+            (Variant::Fraktur, 'R') => Some('ℜ'),
+            (Variant::Fraktur, 'Z') => Some('ℨ'),
+            _ => None,
+        };
+        if let Some(c) = exception {
+            return c;
+        }
+
+        // The first upper case letter, lower case letter and digit of each
+        // alphabet; not every alphabet has digits.
+        let (upper, lower, digit) = match self {
+            Variant::Normal => return c,
+            Variant::Bold => (0x1D400, 0x1D41A, Some(0x1D7CE)),
+            Variant::Italic => (0x1D434, 0x1D44E, None),
+            Variant::DoubleStruck => (0x1D538, 0x1D552, Some(0x1D7D8)),
+            Variant::Script => (0x1D49C, 0x1D4B6, None),
+            Variant::Fraktur => (0x1D504, 0x1D51E, None),
+            Variant::SansSerif => (0x1D5A0, 0x1D5BA, Some(0x1D7E2)),
+            Variant::Monospace => (0x1D670, 0x1D68A, Some(0x1D7F6)),
+        };
+        let code = match c {
+            'A'..='Z' => upper + (c as u32 - 'A' as u32),
+            'a'..='z' => lower + (c as u32 - 'a' as u32),
+            '0'..='9' => match digit {
+                Some(digit) => digit + (c as u32 - '0' as u32),
+                None => return c,
+            },
+            _ => return c,
+        };
+        char::from_u32(code).unwrap_or(c)
+    }
+}
@@ -1,5 +1,19 @@
 //! Plugin definitions.
 
+pub mod diagrams;
+
+pub mod diff;
+
+#[cfg(feature = "mathml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mathml")))]
+pub mod mathml;
+
+pub mod shell_session;
+
 #[cfg(feature = "syntect")]
 #[cfg_attr(docsrs, doc(cfg(feature = "syntect")))]
 pub mod syntect;
+
+#[cfg(feature = "tree-sitter")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tree-sitter")))]
+pub mod tree_sitter;
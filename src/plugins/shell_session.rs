@@ -0,0 +1,144 @@
+//! Code fence renderer for shell sessions: commands at a prompt, and their
+//! output.
+//!
+//! Prompts are kept out of text selections, so commands copied from the
+//! rendered page can be pasted straight into a shell.
+
+use std::fmt::{self, Write};
+
+use crate::adapters::{CodefenceRendererAdapter, SyntaxHighlighterAdapter};
+use crate::html;
+use crate::nodes::Sourcepos;
+
+/// Renders shell sessions, such as ```` ```console ```` code blocks.
+///
+/// A line starting with one of the prompts is a command, as is each line
+/// after a command ending with a backslash; every other line is output.
+/// Commands can be highlighted as the shell language with a
+/// [`SyntaxHighlighterAdapter`].
+///
+/// ```rust
+/// # use comrak::{Options, options::Plugins, markdown_to_html_with_plugins};
+/// # use comrak::plugins::shell_session::ShellSessionAdapter;
+/// let adapter = ShellSessionAdapter::new();
+/// let mut plugins = Plugins::default();
+/// plugins.render.codefence_renderers.insert("console".to_string(), &adapter);
+///
+/// assert_eq!(markdown_to_html_with_plugins("```console\n$ echo hi\nhi\n```\n", &Options::default(), &plugins),
+///            "<pre class=\"shell-session\"><code class=\"language-console\">\
+///             <span class=\"prompt\" style=\"user-select: none\">$ </span>\
+///             <span class=\"command\">echo hi</span>\n\
+///             <span class=\"output\">hi</span>\n\
+///             </code></pre>\n");
+/// ```
+#[derive(Clone)]
+pub struct ShellSessionAdapter<'a> {
+    prompts: Vec<String>,
+    shell: String,
+    highlighter: Option<&'a dyn SyntaxHighlighterAdapter>,
+}
+
+impl<'a> ShellSessionAdapter<'a> {
+    /// Constructs an adapter recognising the prompt `$ `, which doesn't
+    /// highlight commands.
+    pub fn new() -> Self {
+        ShellSessionAdapter {
+            prompts: vec!["$ ".to_string()],
+            shell: "bash".to_string(),
+            highlighter: None,
+        }
+    }
+
+    /// Recognises the given prompts in place of the defaults.  The longest
+    /// matching prompt is used.
+    pub fn with_prompts<S: AsRef<str>>(mut self, prompts: &[S]) -> Self {
+        self.prompts = prompts.iter().map(|p| p.as_ref().to_string()).collect();
+        self.prompts.sort_by_key(|p| std::cmp::Reverse(p.len()));
+        self
+    }
+
+    /// Highlights commands with `highlighter`, as the language `shell`, such
+    /// as `bash` or `powershell`.
+    pub fn with_highlighter(
+        mut self,
+        highlighter: &'a dyn SyntaxHighlighterAdapter,
+        shell: &str,
+    ) -> Self {
+        self.highlighter = Some(highlighter);
+        self.shell = shell.to_string();
+        self
+    }
+
+    fn prompt<'l>(&self, line: &'l str) -> Option<(&'l str, &'l str)> {
+        self.prompts
+            .iter()
+            .find(|prompt| line.starts_with(prompt.as_str()))
+            .map(|prompt| line.split_at(prompt.len()))
+    }
+
+    fn write_command(&self, output: &mut dyn Write, command: &str) -> fmt::Result {
+        output.write_str("<span class=\"command\">")?;
+        match self.highlighter {
+            Some(highlighter) => {
+                highlighter.write_highlighted(output, Some(&self.shell), command)?
+            }
+            None => html::escape(output, command)?,
+        }
+        output.write_str("</span>")
+    }
+}
+
+impl Default for ShellSessionAdapter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ShellSessionAdapter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShellSessionAdapter")
+            .field("prompts", &self.prompts)
+            .field("shell", &self.shell)
+            .field("highlighter", &self.highlighter.is_some())
+            .finish()
+    }
+}
+
+impl CodefenceRendererAdapter for ShellSessionAdapter<'_> {
+    fn write(
+        &self,
+        output: &mut dyn Write,
+        lang: &str,
+        _meta: &str,
+        code: &str,
+        sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        let mut pre_attributes = vec![("class", "shell-session".to_string())];
+        if let Some(sourcepos) = sourcepos {
+            pre_attributes.push(("data-sourcepos", sourcepos.to_string()));
+        }
+        html::write_opening_tag(output, "pre", pre_attributes)?;
+        html::write_opening_tag(output, "code", [("class", format!("language-{}", lang))])?;
+
+        let mut continuation = false;
+        for line in code.lines() {
+            if continuation {
+                self.write_command(output, line)?;
+                continuation = line.ends_with('\\');
+            } else if let Some((prompt, command)) = self.prompt(line) {
+                output.write_str("<span class=\"prompt\" style=\"user-select: none\">")?;
+                html::escape(output, prompt)?;
+                output.write_str("</span>")?;
+                self.write_command(output, command)?;
+                continuation = line.ends_with('\\');
+            } else if !line.is_empty() {
+                output.write_str("<span class=\"output\">")?;
+                html::escape(output, line)?;
+                output.write_str("</span>")?;
+            }
+            output.write_str("\n")?;
+        }
+
+        output.write_str("</code></pre>\n")
+    }
+}
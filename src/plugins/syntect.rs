@@ -3,17 +3,20 @@
 use std::borrow::Cow;
 use std::collections::{HashMap, hash_map};
 use std::fmt::{self, Write};
-use syntect::Error;
+use std::path::Path;
 use syntect::easy::HighlightLines;
 use syntect::highlighting::{Color, ThemeSet};
 use syntect::html::{
     ClassStyle, ClassedHTMLGenerator, IncludeBackground, append_highlighted_html_for_styled_line,
+    css_for_theme_with_class_style, line_tokens_to_classed_spans,
 };
-use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet};
 use syntect::util::LinesWithEndings;
+use syntect::{Error, LoadingError};
 
-use crate::adapters::SyntaxHighlighterAdapter;
+use crate::adapters::{FenceMeta, SyntaxHighlighterAdapter};
 use crate::html;
+use crate::nodes::Sourcepos;
 
 #[derive(Debug)]
 /// Syntect syntax highlighter plugin.
@@ -24,6 +27,36 @@ pub struct SyntectAdapter {
     css_class_prefix: Option<&'static str>, // syntect::html::ClassStyle::SpacePrefixed requires prefix to be &'static str
 }
 
+/// An error generating a stylesheet with [`SyntectAdapter::stylesheet`].
+#[derive(Debug)]
+pub enum StylesheetError {
+    /// There is no theme of the given name in the adapter's theme set.
+    UnknownTheme(String),
+
+    /// Syntect couldn't generate the CSS for the theme.
+    Syntect(Error),
+}
+
+impl fmt::Display for StylesheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StylesheetError::UnknownTheme(name) => {
+                write!(f, "unknown syntax highlighting theme: {}", name)
+            }
+            StylesheetError::Syntect(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for StylesheetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StylesheetError::UnknownTheme(_) => None,
+            StylesheetError::Syntect(error) => Some(error),
+        }
+    }
+}
+
 impl SyntectAdapter {
     /// Construct a new `SyntectAdapter` object and set the syntax highlighting theme.
     /// If `None` is specified, apply CSS classes instead.
@@ -74,15 +107,80 @@ impl SyntectAdapter {
             }
         }
     }
-}
 
-impl SyntaxHighlighterAdapter for SyntectAdapter {
-    fn write_highlighted(
+    /// The names of the themes in the adapter's theme set, in order.
+    pub fn theme_names(&self) -> impl Iterator<Item = &str> {
+        self.theme_set.themes.keys().map(String::as_str)
+    }
+
+    /// The syntaxes in the adapter's syntax set.
+    pub fn syntaxes(&self) -> &[SyntaxReference] {
+        self.syntax_set.syntaxes()
+    }
+
+    /// Generates a stylesheet for the classes written in CSS class mode, with
+    /// any prefix, coloured per the named theme of the adapter's theme set.
+    ///
+    /// ```rust
+    /// # use comrak::plugins::syntect::{StylesheetError, SyntectAdapter};
+    /// let adapter = SyntectAdapter::new(None);
+    /// let css = adapter.stylesheet("base16-ocean.dark").unwrap();
+    /// assert!(css.starts_with("pre.syntax-highlighting {\n color: #c0c5ce;\n background-color: #2b303b;\n}\n"));
+    /// assert!(matches!(adapter.stylesheet("nope"), Err(StylesheetError::UnknownTheme(_))));
+    /// ```
+    pub fn stylesheet(&self, theme: &str) -> Result<String, StylesheetError> {
+        let theme = self
+            .theme_set
+            .themes
+            .get(theme)
+            .ok_or_else(|| StylesheetError::UnknownTheme(theme.to_string()))?;
+        let class_style = match &self.css_class_prefix {
+            None => ClassStyle::Spaced,
+            Some(prefix) => ClassStyle::SpacedPrefixed { prefix },
+        };
+
+        // The theme's colours for the `<pre>` tag and highlighted lines,
+        // which Syntect's stylesheet doesn't cover.
+        let mut css = String::from("pre.syntax-highlighting {\n");
+        if let Some(fg) = theme.settings.foreground {
+            css.push_str(&format!(" color: {};\n", hex(fg)));
+        }
+        if let Some(bg) = theme.settings.background {
+            css.push_str(&format!(" background-color: {};\n", hex(bg)));
+        }
+        css.push_str("}\n");
+        if let Some(line_highlight) = theme.settings.line_highlight {
+            css.push_str(&format!(
+                "pre.syntax-highlighting .line.highlighted {{\n background-color: {};\n}}\n",
+                hex(line_highlight)
+            ));
+        }
+        css.push('\n');
+
+        css.push_str(
+            &css_for_theme_with_class_style(theme, class_style)
+                .map_err(StylesheetError::Syntect)?,
+        );
+        Ok(css)
+    }
+
+    /// Generates a stylesheet as [`stylesheet`](Self::stylesheet) does, using
+    /// the `dark` theme when the reader prefers a dark colour scheme, and the
+    /// `light` theme otherwise.
+    pub fn stylesheet_with_dark_theme(
         &self,
-        output: &mut dyn Write,
-        lang: Option<&str>,
-        code: &str,
-    ) -> fmt::Result {
+        light: &str,
+        dark: &str,
+    ) -> Result<String, StylesheetError> {
+        let mut css = self.stylesheet(light)?;
+        let dark = self.stylesheet(dark)?;
+        css.push_str("\n@media (prefers-color-scheme: dark) {\n");
+        css.push_str(&dark);
+        css.push_str("}\n");
+        Ok(css)
+    }
+
+    fn find_syntax(&self, lang: Option<&str>, code: &str) -> &SyntaxReference {
         let fallback_syntax = "Plain Text";
 
         let lang: &str = match lang {
@@ -90,14 +188,98 @@ impl SyntaxHighlighterAdapter for SyntectAdapter {
             _ => fallback_syntax,
         };
 
-        let syntax = self
-            .syntax_set
+        self.syntax_set
             .find_syntax_by_token(lang)
             .unwrap_or_else(|| {
                 self.syntax_set
                     .find_syntax_by_first_line(code)
                     .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text())
-            });
+            })
+    }
+
+    /// Highlights each line on its own, so each can be wrapped in a span.
+    fn highlight_lines(&self, code: &str, syntax: &SyntaxReference) -> Result<Vec<String>, Error> {
+        let mut lines = vec![];
+        match &self.theme {
+            Some(theme) => {
+                let theme = &self.theme_set.themes[theme];
+                let mut highlighter = HighlightLines::new(syntax, theme);
+                let bg = theme.settings.background.unwrap_or(Color::WHITE);
+
+                for line in LinesWithEndings::from(code) {
+                    let regions = highlighter.highlight_line(line, &self.syntax_set)?;
+                    let mut output = String::new();
+                    append_highlighted_html_for_styled_line(
+                        &regions[..],
+                        IncludeBackground::IfDifferent(bg),
+                        &mut output,
+                    )?;
+                    lines.push(output);
+                }
+            }
+            None => {
+                let class_style = match &self.css_class_prefix {
+                    None => ClassStyle::Spaced,
+                    Some(prefix) => ClassStyle::SpacedPrefixed { prefix },
+                };
+                let mut parse_state = ParseState::new(syntax);
+                let mut stack = ScopeStack::new();
+
+                for line in LinesWithEndings::from(code) {
+                    let ops = parse_state.parse_line(line, &self.syntax_set)?;
+                    // Reopen the scopes left open by the previous line, and
+                    // close those left open by this one, to keep the line's
+                    // spans balanced.
+                    let mut output = String::new();
+                    for &scope in stack.as_slice() {
+                        output.push_str("<span class=\"");
+                        push_scope_classes(&mut output, scope, class_style);
+                        output.push_str("\">");
+                    }
+                    let (spans, _) =
+                        line_tokens_to_classed_spans(line, &ops, class_style, &mut stack)?;
+                    output.push_str(&spans);
+                    for _ in stack.as_slice() {
+                        output.push_str("</span>");
+                    }
+                    lines.push(output);
+                }
+            }
+        }
+
+        // Line endings go between the lines' spans.
+        for line in &mut lines {
+            line.retain(|c| c != '\n' && c != '\r');
+        }
+        Ok(lines)
+    }
+}
+
+fn hex(colour: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour.r, colour.g, colour.b)
+}
+
+/// The classes of a scope, per `syntect::html`.
+fn push_scope_classes(output: &mut String, scope: Scope, class_style: ClassStyle) {
+    for (i, atom) in scope.build_string().split('.').enumerate() {
+        if i != 0 {
+            output.push(' ');
+        }
+        if let ClassStyle::SpacedPrefixed { prefix } = class_style {
+            output.push_str(prefix);
+        }
+        output.push_str(atom);
+    }
+}
+
+impl SyntaxHighlighterAdapter for SyntectAdapter {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn Write,
+        lang: Option<&str>,
+        code: &str,
+    ) -> fmt::Result {
+        let syntax = self.find_syntax(lang, code);
 
         match self.highlight_html(code, syntax) {
             Ok(highlighted_code) => output.write_str(&highlighted_code),
@@ -105,6 +287,51 @@ impl SyntaxHighlighterAdapter for SyntectAdapter {
         }
     }
 
+    /// Wraps each line in a `<span class="line">` when the meta highlights
+    /// any lines, adding the class `highlighted` to those, or numbers them
+    /// per `showLineNumbers`, giving each a `data-line-number` attribute.
+    fn write_highlighted_with_meta(
+        &self,
+        output: &mut dyn Write,
+        lang: Option<&str>,
+        meta: &FenceMeta,
+        code: &str,
+        _sourcepos: Option<Sourcepos>,
+    ) -> fmt::Result {
+        let line_numbers = meta.line_numbers();
+        if line_numbers.is_none() && meta.highlighted_lines.is_empty() {
+            return self.write_highlighted(output, lang, code);
+        }
+
+        let syntax = self.find_syntax(lang, code);
+        let lines = match self.highlight_lines(code, syntax) {
+            Ok(lines) => lines,
+            Err(_) => return self.write_highlighted(output, lang, code),
+        };
+
+        for (i, line) in lines.iter().enumerate() {
+            output.write_str("<span class=\"line")?;
+            if meta.is_highlighted(i + 1) {
+                output.write_str(" highlighted")?;
+            }
+            output.write_str("\"")?;
+            if let Some(start) = line_numbers {
+                write!(output, " data-line-number=\"{}\"", start + i)?;
+            }
+            output.write_str(">")?;
+            output.write_str(line)?;
+            output.write_str("</span>\n")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the title in a `<div class="code-title">`.
+    fn write_title(&self, output: &mut dyn Write, title: &str) -> fmt::Result {
+        output.write_str("<div class=\"code-title\">")?;
+        html::escape(output, title)?;
+        output.write_str("</div>\n")
+    }
+
     fn write_pre_tag(
         &self,
         output: &mut dyn Write,
@@ -123,7 +350,15 @@ impl SyntaxHighlighterAdapter for SyntectAdapter {
                 let mut pre_attributes = SyntectPreAttributes::new(attributes, &style);
                 html::write_opening_tag(output, "pre", pre_attributes.iter_mut())
             }
-            None => html::write_opening_tag(output, "pre", vec![("class", "syntax-highlighting")]),
+            None => {
+                // Other attributes are left to the stylesheet, but an ID is
+                // what cross-references to the block link to.
+                let mut pre_attributes = vec![("class", Cow::Borrowed("syntax-highlighting"))];
+                if let Some(id) = attributes.get("id") {
+                    pre_attributes.push(("id", id.clone()));
+                }
+                html::write_opening_tag(output, "pre", pre_attributes)
+            }
         }
     }
 
@@ -232,6 +467,51 @@ impl SyntectAdapterBuilder {
         self
     }
 
+    /// Adds the `.sublime-syntax` files in a directory, and its
+    /// subdirectories, to the syntax set; the default one if none is set.
+    pub fn add_syntaxes_from_folder<P: AsRef<Path>>(
+        mut self,
+        path: P,
+    ) -> Result<Self, LoadingError> {
+        let mut builder = self
+            .syntax_set
+            .take()
+            .unwrap_or_else(SyntaxSet::load_defaults_newlines)
+            .into_builder();
+        builder.add_from_folder(path, true)?;
+        self.syntax_set = Some(builder.build());
+        Ok(self)
+    }
+
+    /// Adds the `.tmTheme` files in a directory, and its subdirectories, to
+    /// the theme set; the default one if none is set.  Each theme is named
+    /// for its file, without the extension.
+    pub fn add_themes_from_folder<P: AsRef<Path>>(mut self, path: P) -> Result<Self, LoadingError> {
+        let mut theme_set = self
+            .theme_set
+            .take()
+            .unwrap_or_else(ThemeSet::load_defaults);
+        theme_set.add_from_folder(path)?;
+        self.theme_set = Some(theme_set);
+        Ok(self)
+    }
+
+    /// Adds a `.tmTheme` file to the theme set; the default one if none is
+    /// set.  The theme is named for the file, without the extension.
+    pub fn add_theme_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self, LoadingError> {
+        let path = path.as_ref();
+        let theme = ThemeSet::get_theme(path)?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or(LoadingError::BadPath)?;
+        self.theme_set
+            .get_or_insert_with(ThemeSet::load_defaults)
+            .themes
+            .insert(name, theme);
+        Ok(self)
+    }
+
     /// Builds the [`SyntectAdapter`]. Default values:
     /// - `theme`: `None` (uses CSS classes)
     /// - `syntax_set`: [`SyntaxSet::load_defaults_newlines()`]
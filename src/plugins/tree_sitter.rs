@@ -0,0 +1,232 @@
+//! Adapter for syntax highlighting with Tree-sitter grammars.
+//!
+//! Grammars and their highlight queries are registered at runtime, usually
+//! from the `tree-sitter-<language>` crates.  Highlights are written as
+//! class-based spans named like the scopes [`syntect`](super::syntect) uses,
+//! so CSS generated for a Syntect theme applies to them too.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+use ::tree_sitter::{Language, QueryError};
+use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
+
+use crate::adapters::SyntaxHighlighterAdapter;
+use crate::html;
+
+/// Tree-sitter highlight names, and the classes of the Syntect scopes they
+/// correspond to.  A capture is given the classes of the most specific name
+/// it matches; `function.method` matches `function`, for one.
+const HIGHLIGHT_CLASSES: &[(&str, &str)] = &[
+    ("attribute", "entity other attribute-name"),
+    ("comment", "comment"),
+    ("constant", "constant"),
+    ("constant.builtin", "constant language"),
+    ("constructor", "entity name type"),
+    ("embedded", "meta embedded"),
+    ("escape", "constant character escape"),
+    ("function", "entity name function"),
+    ("function.builtin", "support function"),
+    ("function.macro", "entity name function macro"),
+    ("keyword", "keyword"),
+    ("label", "entity name label"),
+    ("module", "entity name namespace"),
+    ("namespace", "entity name namespace"),
+    ("number", "constant numeric"),
+    ("operator", "keyword operator"),
+    ("property", "variable other member"),
+    ("punctuation", "punctuation"),
+    ("punctuation.bracket", "punctuation section"),
+    ("punctuation.delimiter", "punctuation separator"),
+    ("punctuation.special", "punctuation definition"),
+    ("string", "string"),
+    ("string.special", "string other"),
+    ("tag", "entity name tag"),
+    ("type", "entity name type"),
+    ("type.builtin", "storage type"),
+    ("variable", "variable"),
+    ("variable.builtin", "variable language"),
+    ("variable.parameter", "variable parameter"),
+];
+
+/// Tree-sitter syntax highlighter plugin.
+///
+/// ```rust
+/// # use comrak::{Options, options::Plugins, markdown_to_html_with_plugins};
+/// # use comrak::plugins::tree_sitter::TreeSitterAdapter;
+/// let mut adapter = TreeSitterAdapter::new();
+/// adapter
+///     .register("json", tree_sitter_json::LANGUAGE, "(string) @string", "", "")
+///     .unwrap();
+/// let mut plugins = Plugins::default();
+/// plugins.render.codefence_syntax_highlighter = Some(&adapter);
+///
+/// assert_eq!(markdown_to_html_with_plugins("```json\n[\"a\"]\n```\n", &Options::default(), &plugins),
+///            "<pre class=\"syntax-highlighting\"><code class=\"language-json\">\
+///             [<span class=\"string\">&quot;a&quot;</span>]\n</code></pre>\n");
+/// ```
+pub struct TreeSitterAdapter {
+    configurations: Vec<HighlightConfiguration>,
+    languages: HashMap<String, usize>,
+    classes: Vec<String>,
+}
+
+impl TreeSitterAdapter {
+    /// Constructs an adapter with no languages registered.
+    pub fn new() -> Self {
+        Self::with_class_prefix("")
+    }
+
+    /// Constructs an adapter with no languages registered, which prefixes
+    /// each class with `prefix`, as
+    /// [`css_with_class_prefix`](super::syntect::SyntectAdapterBuilder::css_with_class_prefix)
+    /// does.
+    pub fn with_class_prefix(prefix: &str) -> Self {
+        let classes = HIGHLIGHT_CLASSES
+            .iter()
+            .map(|(_, classes)| {
+                classes
+                    .split(' ')
+                    .map(|class| format!("{}{}", prefix, class))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+
+        TreeSitterAdapter {
+            configurations: vec![],
+            languages: HashMap::new(),
+            classes,
+        }
+    }
+
+    /// Registers a grammar and its queries for code blocks of the language
+    /// `name`.  `language` is usually the `LANGUAGE` constant of a grammar
+    /// crate.  The injections query may refer to other registered languages;
+    /// it and the locals query may be empty.
+    pub fn register(
+        &mut self,
+        name: &str,
+        language: impl Into<Language>,
+        highlights_query: &str,
+        injections_query: &str,
+        locals_query: &str,
+    ) -> Result<(), QueryError> {
+        let mut configuration = HighlightConfiguration::new(
+            language.into(),
+            name,
+            highlights_query,
+            injections_query,
+            locals_query,
+        )?;
+        let names: Vec<&str> = HIGHLIGHT_CLASSES.iter().map(|(name, _)| *name).collect();
+        configuration.configure(&names);
+
+        self.languages
+            .insert(name.to_string(), self.configurations.len());
+        self.configurations.push(configuration);
+        Ok(())
+    }
+
+    /// Makes `alias` another name for the registered language `name`, such
+    /// as `rs` for `rust`.  Returns whether `name` is registered.
+    pub fn alias(&mut self, alias: &str, name: &str) -> bool {
+        match self.languages.get(name) {
+            Some(&index) => {
+                self.languages.insert(alias.to_string(), index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn configuration(&self, lang: &str) -> Option<&HighlightConfiguration> {
+        let index = self
+            .languages
+            .get(lang)
+            .or_else(|| self.languages.get(&lang.to_lowercase()))?;
+        Some(&self.configurations[*index])
+    }
+
+    fn highlight_html(
+        &self,
+        configuration: &HighlightConfiguration,
+        code: &str,
+    ) -> Result<String, tree_sitter_highlight::Error> {
+        let mut highlighter = Highlighter::new();
+        let events = highlighter.highlight(configuration, code.as_bytes(), None, |lang| {
+            self.configuration(lang)
+        })?;
+
+        let mut output = String::new();
+        for event in events {
+            match event? {
+                HighlightEvent::Source { start, end } => {
+                    html::escape(&mut output, &code[start..end]).unwrap();
+                }
+                HighlightEvent::HighlightStart(highlight) => {
+                    output.push_str("<span class=\"");
+                    output.push_str(&self.classes[highlight.0]);
+                    output.push_str("\">");
+                }
+                HighlightEvent::HighlightEnd => output.push_str("</span>"),
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl Default for TreeSitterAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for TreeSitterAdapter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut languages: Vec<&String> = self.languages.keys().collect();
+        languages.sort();
+        f.debug_struct("TreeSitterAdapter")
+            .field("languages", &languages)
+            .finish()
+    }
+}
+
+impl SyntaxHighlighterAdapter for TreeSitterAdapter {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn Write,
+        lang: Option<&str>,
+        code: &str,
+    ) -> fmt::Result {
+        let lang = lang
+            .map(|l| l.split_once(',').map(|(left, _)| left).unwrap_or(l))
+            .unwrap_or_default();
+
+        let highlighted = self
+            .configuration(lang)
+            .and_then(|configuration| self.highlight_html(configuration, code).ok());
+        match highlighted {
+            Some(highlighted) => output.write_str(&highlighted),
+            None => html::escape(output, code),
+        }
+    }
+
+    fn write_pre_tag(
+        &self,
+        output: &mut dyn Write,
+        mut attributes: HashMap<&'static str, Cow<'_, str>>,
+    ) -> fmt::Result {
+        attributes.insert("class", "syntax-highlighting".into());
+        html::write_opening_tag(output, "pre", attributes)
+    }
+
+    fn write_code_tag(
+        &self,
+        output: &mut dyn Write,
+        attributes: HashMap<&'static str, Cow<'_, str>>,
+    ) -> fmt::Result {
+        html::write_opening_tag(output, "code", attributes)
+    }
+}
@@ -0,0 +1,155 @@
+//! A hierarchical view of a document's sections.
+//!
+//! The AST keeps headings flat, as siblings of the blocks that follow them;
+//! [`sections`] nests each top-level heading and its content under the nearest
+//! preceding heading of a lower level.
+
+use crate::nodes::{Node, NodeValue};
+
+/// A section of a document, as returned by [`sections`].
+#[derive(Debug, Clone)]
+pub struct Section<'a> {
+    /// The heading opening this section; `None` for the root section, which
+    /// holds any content preceding the first heading.
+    pub heading: Option<Node<'a>>,
+
+    /// The level of the heading opening this section; 0 for the root section.
+    pub level: u8,
+
+    /// The hierarchical number of this section, e.g. `[1, 2]` for the second
+    /// subsection of the first section.  Empty for the root section.
+    pub number: Vec<u32>,
+
+    /// The blocks following the heading, up to the first subsection or the
+    /// end of this section.
+    pub content: Vec<Node<'a>>,
+
+    /// Sections nested within this one.
+    pub subsections: Vec<Section<'a>>,
+}
+
+impl<'a> Section<'a> {
+    fn new(heading: Option<Node<'a>>, level: u8, number: Vec<u32>) -> Self {
+        Section {
+            heading,
+            level,
+            number,
+            content: vec![],
+            subsections: vec![],
+        }
+    }
+
+    /// The section number formatted as rendered, e.g. `"1.2"`.  `None` for the
+    /// root section.
+    pub fn number_string(&self) -> Option<String> {
+        format_section_number(&self.number)
+    }
+
+    /// All blocks making up this section in document order: its heading, its
+    /// content, and those of all its subsections.
+    pub fn nodes(&self) -> Vec<Node<'a>> {
+        let mut nodes = vec![];
+        self.collect_nodes(&mut nodes);
+        nodes
+    }
+
+    fn collect_nodes(&self, nodes: &mut Vec<Node<'a>>) {
+        nodes.extend(self.heading);
+        nodes.extend(&self.content);
+        for subsection in &self.subsections {
+            subsection.collect_nodes(nodes);
+        }
+    }
+}
+
+/// Builds the section tree of a document from its top-level headings.
+///
+/// Headings nested in other blocks, such as block quotes or list items, don't
+/// open sections.  A heading which skips levels is nested directly under the
+/// preceding heading of a lower level, and numbered accordingly.
+///
+/// ```rust
+/// # use comrak::{Arena, parse_document, Options, sections::sections};
+/// let arena = Arena::new();
+/// let root = parse_document(
+///     &arena,
+///     "Preamble.\n\n# One\n\n## One A\n\nText.\n\n# Two\n",
+///     &Options::default(),
+/// );
+///
+/// let tree = sections(root);
+/// assert_eq!(tree.content.len(), 1);
+/// assert_eq!(tree.subsections.len(), 2);
+/// assert_eq!(tree.subsections[0].subsections[0].number_string().unwrap(), "1.1");
+/// assert_eq!(tree.subsections[0].subsections[0].content.len(), 1);
+/// assert_eq!(tree.subsections[1].number, [2]);
+/// ```
+pub fn sections<'a>(root: Node<'a>) -> Section<'a> {
+    let mut numberer = SectionNumberer::default();
+    let mut stack = vec![Section::new(None, 0, vec![])];
+
+    for node in root.children() {
+        let level = match node.data().value {
+            NodeValue::Heading(ref nh) => nh.level,
+            _ => {
+                stack.last_mut().unwrap().content.push(node);
+                continue;
+            }
+        };
+
+        while stack.len() > 1 && stack.last().unwrap().level >= level {
+            let section = stack.pop().unwrap();
+            stack.last_mut().unwrap().subsections.push(section);
+        }
+        stack.push(Section::new(Some(node), level, numberer.next(level)));
+    }
+
+    while stack.len() > 1 {
+        let section = stack.pop().unwrap();
+        stack.last_mut().unwrap().subsections.push(section);
+    }
+    stack.pop().unwrap()
+}
+
+/// Whether a heading opens a section, i.e. whether it's a direct child of
+/// the document.
+pub(crate) fn is_section_heading(node: Node<'_>) -> bool {
+    node.parent()
+        .is_some_and(|parent| matches!(parent.data().value, NodeValue::Document))
+}
+
+pub(crate) fn format_section_number(number: &[u32]) -> Option<String> {
+    if number.is_empty() {
+        return None;
+    }
+    let parts: Vec<String> = number.iter().map(|n| n.to_string()).collect();
+    Some(parts.join("."))
+}
+
+/// Numbers section headings in document order, as [`sections`] does.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SectionNumberer {
+    /// The level and ordinal of each open section.
+    stack: Vec<(u8, u32)>,
+}
+
+impl SectionNumberer {
+    /// Opens a section at the given heading level, returning its number.
+    pub(crate) fn next(&mut self, level: u8) -> Vec<u32> {
+        let mut ordinal = 1;
+        while let Some(&(open_level, open_ordinal)) = self.stack.last() {
+            if open_level < level {
+                break;
+            }
+            self.stack.pop();
+            ordinal = open_ordinal + 1;
+        }
+        self.stack.push((level, ordinal));
+        self.current()
+    }
+
+    /// The number of the most recently opened section.
+    pub(crate) fn current(&self) -> Vec<u32> {
+        self.stack.iter().map(|&(_, ordinal)| ordinal).collect()
+    }
+}
@@ -190,6 +190,30 @@ pub fn chop_trailing_hashes(mut line: &str) -> (&str, bool) {
     }
 }
 
+/// Chop a trailing explicit heading ID of the form `{#id}` from heading
+/// content.
+///
+/// Returns the remaining content and the ID, if one was present.  The ID must
+/// be non-empty, must not contain whitespace or braces, and must be preceded
+/// by whitespace unless it makes up the entire content.
+pub fn chop_trailing_custom_id(line: &str) -> Option<(&str, &str)> {
+    let line = rtrim_slice(line);
+    let inner = line.strip_suffix('}')?;
+    let start = inner.rfind("{#")?;
+    let id = &inner[start + 2..];
+
+    if id.is_empty() || id.contains(|c: char| c.is_whitespace() || c == '{' || c == '}') {
+        return None;
+    }
+
+    let rest = &line[..start];
+    if !rest.is_empty() && !rest.ends_with(|c: char| c.is_whitespace()) {
+        return None;
+    }
+
+    Some((rtrim_slice(rest), id))
+}
+
 pub fn rtrim(line: &mut String) -> usize {
     let spaces = line
         .as_bytes()
@@ -302,6 +326,37 @@ pub fn clean_url(url: &str) -> Cow<'static, str> {
     b.into()
 }
 
+/// Decodes `%XX` escapes, as the CommonMark formatter writes for spaces in
+/// URLs.  Returns the input unchanged if the result isn't UTF-8.
+pub fn percent_decode(s: &str) -> Cow<'_, str> {
+    if !s.contains('%') {
+        return s.into();
+    }
+
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        // from_str_radix alone would take a sign, as in "%+f".
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_or(s.into(), Cow::Owned)
+}
+
 pub fn clean_title(title: &str) -> Cow<'static, str> {
     let title_len = title.len();
     if title_len == 0 {
@@ -416,6 +471,62 @@ pub fn split_off_front_matter<'s>(mut s: &'s str, delimiter: &str) -> Option<(&'
     Some((&s[..start], &s[start..]))
 }
 
+/// Finds the value of a top-level `key: value` (YAML) or `key = value` (TOML)
+/// line in front matter, stripped of any surrounding quotes.
+pub fn front_matter_value<'s>(front_matter: &'s str, key: &str) -> Option<&'s str> {
+    front_matter.lines().find_map(|line| {
+        let rest = line.strip_prefix(key)?.trim_start();
+        let value = rest
+            .strip_prefix(':')
+            .or_else(|| rest.strip_prefix('='))?
+            .trim();
+        let unquoted = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')));
+        Some(unquoted.unwrap_or(value))
+    })
+}
+
+/// Makes the spaces around high punctuation and guillemets non-breaking, per
+/// French typography.  A space next to a guillemet that already has its
+/// narrow no-break space, as produced by smart punctuation, is dropped.
+/// Returns `None` if there were none.
+pub fn french_spacing(text: &str) -> Option<String> {
+    if !text.contains(' ') {
+        return None;
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut changed = false;
+    for (i, c) in text.char_indices() {
+        if c != ' ' {
+            result.push(c);
+            continue;
+        }
+
+        let rest = &text[i + 1..];
+        if result.ends_with("\u{ab}\u{202f}") || rest.starts_with("\u{202f}\u{bb}") {
+            changed = true;
+            continue;
+        }
+
+        let replacement = if result.ends_with('\u{ab}') {
+            Some('\u{202f}')
+        } else {
+            match rest.chars().next() {
+                Some(';' | '!' | '?' | '\u{bb}') => Some('\u{202f}'),
+                Some(':') => Some('\u{a0}'),
+                _ => None,
+            }
+        };
+        changed |= replacement.is_some();
+        result.push(replacement.unwrap_or(c));
+    }
+
+    changed.then_some(result)
+}
+
 pub fn trim_start_match<'s>(s: &'s str, pat: &str) -> &'s str {
     s.strip_prefix(pat).unwrap_or(s)
 }
@@ -539,8 +650,8 @@ pub fn phoenix_inline_expression(s: &str) -> Option<usize> {
 #[cfg(test)]
 pub mod tests {
     use super::{
-        count_newlines, ltrim, normalize_code, normalize_label, shift_buf_left,
-        split_off_front_matter,
+        chop_trailing_custom_id, count_newlines, french_spacing, front_matter_value, ltrim,
+        normalize_code, normalize_label, percent_decode, shift_buf_left, split_off_front_matter,
     };
     use crate::strings::Case;
 
@@ -616,10 +727,54 @@ pub mod tests {
         assert_eq!(s, "okay");
     }
 
+    #[test]
+    fn chop_trailing_custom_id_ok() {
+        assert_eq!(
+            chop_trailing_custom_id("Title {#my-id}\n"),
+            Some(("Title", "my-id"))
+        );
+        assert_eq!(chop_trailing_custom_id("{#only}"), Some(("", "only")));
+        assert_eq!(chop_trailing_custom_id("Title{#my-id}"), None);
+        assert_eq!(chop_trailing_custom_id("Title {#}"), None);
+        assert_eq!(chop_trailing_custom_id("Title {#a b}"), None);
+        assert_eq!(chop_trailing_custom_id("Title {#a} more"), None);
+    }
+
     #[test]
     fn count_newlines_ok() {
         assert_eq!((0, 7), count_newlines("abcdefg"));
         assert_eq!((2, 0), count_newlines("abc\ndefg\n"));
         assert_eq!((3, 2), count_newlines("abc\rde\nfg\nhi"));
     }
+
+    #[test]
+    fn front_matter_value_ok() {
+        let front_matter = "---\ntitle: Notes\nlang: \"de-CH\"\n---\n";
+        assert_eq!(front_matter_value(front_matter, "lang"), Some("de-CH"));
+        assert_eq!(
+            front_matter_value("+++\nlang = 'fr'\n+++\n", "lang"),
+            Some("fr")
+        );
+        assert_eq!(front_matter_value("---\nlanguage: fr\n---\n", "lang"), None);
+    }
+
+    #[test]
+    fn french_spacing_ok() {
+        assert_eq!(
+            french_spacing("Quoi ? Oui : « non » ; ah !").as_deref(),
+            Some("Quoi\u{202f}? Oui\u{a0}: «\u{202f}non\u{202f}»\u{202f}; ah\u{202f}!")
+        );
+        assert_eq!(
+            french_spacing("«\u{202f} non \u{202f}»").as_deref(),
+            Some("«\u{202f}non\u{202f}»")
+        );
+        assert_eq!(french_spacing("a b"), None);
+    }
+
+    #[test]
+    fn percent_decode_ok() {
+        assert_eq!(percent_decode("My%20Page%2f"), "My Page/");
+        assert_eq!(percent_decode("%+f%-1%2"), "%+f%-1%2");
+        assert_eq!(percent_decode("%ff"), "%ff");
+    }
 }
@@ -14,6 +14,7 @@ mod code;
 mod commonmark;
 mod compact_html;
 mod core;
+mod crossref;
 mod description_lists;
 mod empty;
 mod escape;
@@ -26,18 +27,21 @@ mod header_id_prefix;
 mod highlight;
 #[path = "tests/html.rs"]
 mod html_;
+mod include;
 mod inline_footnotes;
 mod insert;
 mod math;
 mod multiline_block_quotes;
 #[path = "tests/options.rs"]
 mod options_;
+mod pages;
 mod pathological;
 mod phoenix_heex;
 mod plugins;
 mod raw;
 mod regressions;
 mod rewriter;
+mod sections;
 mod shortcodes;
 #[path = "tests/sourcepos.rs"]
 mod sourcepos_;
@@ -48,8 +52,10 @@ mod subscript;
 mod subtext;
 mod supersubscript;
 mod table;
+mod table_of_contents;
 mod tagfilter;
 mod tasklist;
+mod typographer;
 mod underline;
 mod wikilinks;
 mod xml;
@@ -1,4 +1,8 @@
+use pretty_assertions::assert_eq;
+use std::sync::Arc;
+
 use super::*;
+use crate::options::LanguageGuesser;
 
 #[test]
 fn fenced_codeblock_closed_and_unclosed_root() {
@@ -314,3 +318,92 @@ fn nested_list_after_fenced_codeblock_sourcepos() {
         ])
     );
 }
+
+#[test]
+fn language_guesser() {
+    html_opts!(
+        [
+            parse.language_guesser = Some(Arc::new(options::FirstLineLanguageGuesser)),
+            parse.default_info_string = Some("text".into())
+        ],
+        concat!(
+            "```\n",
+            "#!/bin/sh -e\n",
+            "```\n",
+            "\n",
+            "```\n",
+            "$ cargo test\n",
+            "```\n",
+            "\n",
+            "```ruby\n",
+            "#!/usr/bin/env python3\n",
+            "```\n",
+            "\n",
+            "```\n",
+            "puts 1\n",
+            "```\n",
+        ),
+        concat!(
+            "<pre><code class=\"language-bash\">#!/bin/sh -e\n</code></pre>\n",
+            "<pre><code class=\"language-console\">$ cargo test\n</code></pre>\n",
+            "<pre><code class=\"language-ruby\">#!/usr/bin/env python3\n</code></pre>\n",
+            "<pre><code class=\"language-text\">puts 1\n</code></pre>\n",
+        ),
+    );
+
+    html_opts!(
+        [parse.language_guesser = Some(Arc::new(|code: &str| {
+            code.starts_with('{').then(|| "json".to_string())
+        }))],
+        "```\n{}\n```\n\n```\n[]\n```\n",
+        concat!(
+            "<pre><code class=\"language-json\">{}\n</code></pre>\n",
+            "<pre><code>[]\n</code></pre>\n",
+        ),
+    );
+}
+
+#[test]
+fn first_line_language_guesser() {
+    let guess = |code| options::FirstLineLanguageGuesser.guess(code);
+    assert_eq!(
+        guess("#!/usr/bin/env -S node --harmony\n"),
+        Some("javascript".into())
+    );
+    assert_eq!(
+        guess("#!/usr/local/bin/python3.12\nprint()\n"),
+        Some("python".into())
+    );
+    assert_eq!(guess("#!/usr/bin/perl -w\n"), Some("perl".into()));
+    assert_eq!(guess("<?xml version=\"1.0\"?>\n"), Some("xml".into()));
+    assert_eq!(guess("<!DOCTYPE html>\n"), Some("html".into()));
+    assert_eq!(guess("<?php echo 1; ?>\n"), Some("php".into()));
+    assert_eq!(guess("diff --git a/x b/x\n"), Some("diff".into()));
+    assert_eq!(guess("#!\n"), None);
+    assert_eq!(guess("fn main() {}\n"), None);
+    assert_eq!(guess(""), None);
+}
+
+#[test]
+fn language_aliases() {
+    let mut plugins = options::Plugins::default();
+    plugins.render.codefence_language_aliases = options::COMMON_LANGUAGE_ALIASES
+        .iter()
+        .map(|&(alias, name)| (alias.to_string(), name.to_string()))
+        .collect();
+    plugins
+        .render
+        .codefence_language_aliases
+        .insert("Sh".into(), "zsh".into());
+
+    html_plugins(
+        "```js\nf();\n```\n\n```Shell extra\nls\n```\n\n```Sh\nls\n```\n\n```lua\nf()\n```\n",
+        concat!(
+            "<pre><code class=\"language-javascript\">f();\n</code></pre>\n",
+            "<pre><code class=\"language-bash\">ls\n</code></pre>\n",
+            "<pre><code class=\"language-zsh\">ls\n</code></pre>\n",
+            "<pre><code class=\"language-lua\">f()\n</code></pre>\n",
+        ),
+        &plugins,
+    );
+}
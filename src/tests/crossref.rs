@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use pretty_assertions::assert_eq;
+
+use super::*;
+use crate::crossref::{CrossReferences, Target, TargetKind};
+
+#[test]
+fn cross_references() {
+    html_opts!(
+        [
+            extension.cross_references,
+            extension.header_custom_ids,
+            extension.table,
+            extension.table_captions
+        ],
+        concat!(
+            "# Intro {#sec:intro}\n",
+            "\n",
+            "See [@fig:cat], [@tbl:prices], [@lst:main] and [@sec:usage].\n",
+            "\n",
+            "![A cat](cat.png){#fig:cat}\n",
+            "\n",
+            "Table: Prices {#tbl:prices}\n",
+            "\n",
+            "| a |\n",
+            "|---|\n",
+            "\n",
+            "## Usage {#sec:usage}\n",
+            "\n",
+            "``` rust {#lst:main}\n",
+            "fn main() {}\n",
+            "```\n",
+        ),
+        concat!(
+            "<h1><a href=\"#sec:intro\" aria-hidden=\"true\" class=\"anchor\" id=\"sec:intro\"></a>Intro</h1>\n",
+            "<p>See <a href=\"#fig:cat\">Figure 1</a>, <a href=\"#tbl:prices\">Table 1</a>, ",
+            "<a href=\"#lst:main\">Listing 1</a> and <a href=\"#sec:usage\">Section 1.1</a>.</p>\n",
+            "<p><img id=\"fig:cat\" src=\"cat.png\" alt=\"A cat\" /></p>\n",
+            "<table id=\"tbl:prices\">\n",
+            "<caption>Table 1: Prices</caption>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>a</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "</table>\n",
+            "<h2><a href=\"#sec:usage\" aria-hidden=\"true\" class=\"anchor\" id=\"sec:usage\"></a>Usage</h2>\n",
+            "<pre id=\"lst:main\"><code class=\"language-rust\">fn main() {}\n",
+            "</code></pre>\n",
+        ),
+    );
+}
+
+#[test]
+fn cross_references_numbering() {
+    html_opts!(
+        [extension.cross_references, render.figure_with_caption],
+        concat!(
+            "![One](1.png \"First\"){#fig:one} ![Unlabelled](u.png) ![Two](2.png){#fig:two}\n",
+            "\n",
+            "[@fig:two] and [@fig:one], not [@doe99] or [@fig:missing].\n",
+        ),
+        concat!(
+            "<p><figure id=\"fig:one\"><img src=\"1.png\" alt=\"One\" title=\"First\" />",
+            "<figcaption>Figure 1: First</figcaption></figure> ",
+            "<figure><img src=\"u.png\" alt=\"Unlabelled\" /></figure> ",
+            "<figure id=\"fig:two\"><img src=\"2.png\" alt=\"Two\" />",
+            "<figcaption>Figure 2</figcaption></figure></p>\n",
+            "<p><a href=\"#fig:two\">Figure 2</a> and <a href=\"#fig:one\">Figure 1</a>, ",
+            "not [@doe99] or [@fig:missing].</p>\n",
+        ),
+    );
+}
+
+#[test]
+fn cross_references_equations() {
+    html_opts!(
+        [
+            extension.math_dollars,
+            extension.math_labels,
+            extension.cross_references
+        ],
+        "$$x$$ {#eq:x}\n\nBy [@eq:x] and @eq:x.\n",
+        concat!(
+            "<p><span class=\"math-equation\" id=\"eq:x\">",
+            "<span data-math-style=\"display\">x</span> ",
+            "<span class=\"math-equation-number\">(1)</span></span></p>\n",
+            "<p>By <a href=\"#eq:x\">Equation 1</a> and <a href=\"#eq:x\">(1)</a>.</p>\n",
+        ),
+    );
+}
+
+#[test]
+fn cross_references_disabled() {
+    html_opts!(
+        [extension.header_custom_ids],
+        "![Cat](cat.png){#fig:cat}\n\n``` rust {#lst:main}\n```\n\nSee [@fig:cat].\n",
+        concat!(
+            "<p><img src=\"cat.png\" alt=\"Cat\" />{#fig:cat}</p>\n",
+            "<pre><code class=\"language-rust\"></code></pre>\n",
+            "<p>See [@fig:cat].</p>\n",
+        ),
+    );
+}
+
+#[test]
+fn cross_references_prefixes() {
+    let mut options = Options::default();
+    options.extension.cross_references = true;
+    options.extension.cross_reference_prefixes.figure = "Fig.".to_string();
+    options.extension.cross_reference_prefixes.listing = String::new();
+    options.render.figure_with_caption = true;
+
+    compare_strs(
+        &markdown_to_html(
+            "![Cat](cat.png){#fig:cat}\n\n```{#lst:main}\n```\n\n[@fig:cat], [@lst:main]\n",
+            &options,
+        ),
+        concat!(
+            "<p><figure id=\"fig:cat\"><img src=\"cat.png\" alt=\"Cat\" />",
+            "<figcaption>Fig. 1</figcaption></figure></p>\n",
+            "<pre id=\"lst:main\"><code></code></pre>\n",
+            "<p><a href=\"#fig:cat\">Fig. 1</a>, <a href=\"#lst:main\">1</a></p>\n",
+        ),
+        "html",
+        "",
+    );
+}
+
+#[test]
+fn cross_references_external() {
+    let mut options = Options::default();
+    options.extension.cross_references = true;
+    options.extension.header_custom_ids = true;
+
+    let arena = Arena::new();
+    let chapter1 = parse_document(
+        &arena,
+        "# Basics {#sec:basics}\n\n![Cat](cat.png){#fig:cat}\n\n![Dog](dog.png){#fig:cat}\n",
+        &options,
+    );
+    let crossrefs = CrossReferences::collect(chapter1).with_base_url("chapter1.html");
+    assert_eq!(crossrefs.targets.len(), 2);
+    assert_eq!(
+        crossrefs.targets["sec:basics"],
+        Target {
+            kind: TargetKind::Section,
+            number: "1".to_string(),
+            url: "chapter1.html#sec:basics".to_string(),
+        }
+    );
+    assert_eq!(
+        crossrefs.targets["fig:cat"],
+        Target {
+            kind: TargetKind::Figure,
+            number: "1".to_string(),
+            url: "chapter1.html#fig:cat".to_string(),
+        }
+    );
+
+    let cb = |link_ref: options::BrokenLinkReference| {
+        Some(ResolvedReference {
+            url: format!("missing.html#{}", link_ref.normalized),
+            title: String::new(),
+        })
+    };
+    options.parse.external_cross_references = Some(Arc::new(crossrefs));
+    options.parse.broken_link_callback = Some(Arc::new(cb));
+
+    compare_strs(
+        &markdown_to_html(
+            "![Bird](bird.png){#fig:cat}\n\n[@fig:cat], [@sec:basics], [@sec:other] and [@doe99].\n",
+            &options,
+        ),
+        concat!(
+            "<p><img id=\"fig:cat\" src=\"bird.png\" alt=\"Bird\" /></p>\n",
+            "<p><a href=\"#fig:cat\">Figure 1</a>, ",
+            "<a href=\"chapter1.html#sec:basics\">Section 1</a>, ",
+            "<a href=\"missing.html#sec:other\">[@sec:other]</a> and [@doe99].</p>\n",
+        ),
+        "html",
+        "",
+    );
+}
+
+#[test]
+fn cross_references_sourcepos() {
+    assert_ast_match!(
+        [extension.cross_references],
+        "![Cat](cat.png){#fig:cat} and [@fig:cat].\n",
+        (document (1:1-1:41) [
+            (paragraph (1:1-1:41) [
+                (figure (1:1-1:25) [
+                    (image (1:1-1:15) "cat.png" [
+                        (text (1:3-1:5) "Cat")
+                    ])
+                ])
+                (text (1:26-1:30) " and ")
+                (link (1:31-1:40) "#fig:cat" [
+                    (text (1:31-1:40) "Figure 1")
+                ])
+                (text (1:41-1:41) ".")
+            ])
+        ])
+    );
+}
+
+#[test]
+fn cross_references_sourcepos_escapes() {
+    assert_ast_match!(
+        [extension.cross_references],
+        "![Cat](cat.png){#fig:cat}\n\n\\*&amp; see [@fig:cat] \\*\n",
+        (document (1:1-3:25) [
+            (paragraph (1:1-1:25) [
+                (figure (1:1-1:25) [
+                    (image (1:1-1:15) "cat.png" [
+                        (text (1:3-1:5) "Cat")
+                    ])
+                ])
+            ])
+            (paragraph (3:1-3:25) [
+                (text (3:2-3:12) "*& see ")
+                (link (3:13-3:22) "#fig:cat" [
+                    (text (3:13-3:22) "Figure 1")
+                ])
+                (text (3:23-3:25) " *")
+            ])
+        ])
+    );
+}
+
+#[test]
+#[cfg(feature = "syntect")]
+fn cross_references_listing_ids_with_plugins() {
+    struct MermaidAdapter;
+
+    impl crate::adapters::CodefenceRendererAdapter for MermaidAdapter {
+        fn write(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            _lang: &str,
+            _meta: &str,
+            code: &str,
+            _sourcepos: Option<crate::nodes::Sourcepos>,
+        ) -> std::fmt::Result {
+            writeln!(output, "<pre class=\"mermaid\">{}</pre>", code.trim())
+        }
+    }
+
+    let syntect = crate::plugins::syntect::SyntectAdapter::new(None);
+    let mermaid = MermaidAdapter;
+    let mut plugins = options::Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&syntect);
+    plugins
+        .render
+        .codefence_renderers
+        .insert("mermaid".to_string(), &mermaid);
+    let mut options = Options::default();
+    options.extension.cross_references = true;
+
+    assert_eq!(
+        markdown_to_html_with_plugins(
+            concat!(
+                "```text {#lst:main}\n",
+                "hi\n",
+                "```\n",
+                "\n",
+                "```mermaid {#lst:flow}\n",
+                "graph TD\n",
+                "```\n",
+                "\n",
+                "[@lst:main], [@lst:flow]\n",
+            ),
+            &options,
+            &plugins
+        ),
+        concat!(
+            "<pre class=\"syntax-highlighting\" id=\"lst:main\"><code class=\"language-text\">",
+            "<span class=\"text plain\">hi\n",
+            "</span></code></pre>\n",
+            "<div id=\"lst:flow\">\n",
+            "<pre class=\"mermaid\">graph TD</pre>\n",
+            "</div>\n",
+            "<p><a href=\"#lst:main\">Listing 1</a>, <a href=\"#lst:flow\">Listing 2</a></p>\n",
+        )
+    );
+}
@@ -314,3 +314,117 @@ fn leave_footnote_definitions() {
         ])
     );
 }
+
+#[test]
+fn footnote_id_prefix_and_labels() {
+    html_opts!(
+        [
+            extension.footnotes = true,
+            render.footnote_id_prefix = Some("doc-".to_string()),
+            render.footnote_section_class = Some("notes \"x\"".to_string()),
+            render.footnote_backref_symbol = Some("<back>".to_string()),
+            render.footnote_backref_label = Some("Retour".to_string())
+        ],
+        concat!("Hi[^x] and[^x].\n", "\n", "[^x]: A greeting.\n"),
+        concat!(
+            "<p>Hi<sup class=\"footnote-ref\"><a href=\"#doc-fn-x\" id=\"doc-fnref-x\" data-footnote-ref>1</a></sup> \
+             and<sup class=\"footnote-ref\"><a href=\"#doc-fn-x\" id=\"doc-fnref-x-2\" data-footnote-ref>1</a></sup>.</p>\n",
+            "<section class=\"notes &quot;x&quot;\" data-footnotes>\n",
+            "<ol>\n",
+            "<li id=\"doc-fn-x\">\n",
+            "<p>A greeting. <a href=\"#doc-fnref-x\" class=\"footnote-backref\" data-footnote-backref \
+             data-footnote-backref-idx=\"1\" aria-label=\"Retour 1\">&lt;back&gt;</a> \
+             <a href=\"#doc-fnref-x-2\" class=\"footnote-backref\" data-footnote-backref \
+             data-footnote-backref-idx=\"1-2\" aria-label=\"Retour 1-2\">&lt;back&gt;<sup class=\"footnote-ref\">2</sup></a></p>\n",
+            "</li>\n",
+            "</ol>\n",
+            "</section>\n",
+        )
+    );
+}
+
+#[test]
+fn footnote_placement_section() {
+    html_opts!(
+        [
+            extension.footnotes = true,
+            render.footnote_placement = options::FootnotePlacement::Section
+        ],
+        concat!(
+            "# One\n",
+            "\n",
+            "Text.[^a]\n",
+            "\n",
+            "## Sub\n",
+            "\n",
+            "More.[^b]\n",
+            "\n",
+            "# Two\n",
+            "\n",
+            "Last.[^c] Again.[^a]\n",
+            "\n",
+            "[^a]: A.\n",
+            "[^b]: B.\n",
+            "[^c]: C.\n",
+        ),
+        concat!(
+            "<h1>One</h1>\n",
+            "<p>Text.<sup class=\"footnote-ref\"><a href=\"#fn-a\" id=\"fnref-a\" data-footnote-ref>1</a></sup></p>\n",
+            "<h2>Sub</h2>\n",
+            "<p>More.<sup class=\"footnote-ref\"><a href=\"#fn-b\" id=\"fnref-b\" data-footnote-ref>2</a></sup></p>\n",
+            "<section class=\"footnotes\" data-footnotes>\n",
+            "<ol>\n",
+            "<li id=\"fn-a\">\n",
+            "<p>A. <a href=\"#fnref-a\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1\" aria-label=\"Back to reference 1\">↩</a> \
+             <a href=\"#fnref-a-2\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1-2\" aria-label=\"Back to reference 1-2\">↩<sup class=\"footnote-ref\">2</sup></a></p>\n",
+            "</li>\n",
+            "<li id=\"fn-b\">\n",
+            "<p>B. <a href=\"#fnref-b\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"2\" aria-label=\"Back to reference 2\">↩</a></p>\n",
+            "</li>\n",
+            "</ol>\n",
+            "</section>\n",
+            "<h1>Two</h1>\n",
+            "<p>Last.<sup class=\"footnote-ref\"><a href=\"#fn-c\" id=\"fnref-c\" data-footnote-ref>3</a></sup> \
+             Again.<sup class=\"footnote-ref\"><a href=\"#fn-a\" id=\"fnref-a-2\" data-footnote-ref>1</a></sup></p>\n",
+            "<section class=\"footnotes\" data-footnotes>\n",
+            "<ol start=\"3\">\n",
+            "<li id=\"fn-c\">\n",
+            "<p>C. <a href=\"#fnref-c\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"3\" aria-label=\"Back to reference 3\">↩</a></p>\n",
+            "</li>\n",
+            "</ol>\n",
+            "</section>\n",
+        )
+    );
+}
+
+#[test]
+fn footnote_placement_inline() {
+    html_opts!(
+        [
+            extension.footnotes = true,
+            render.footnote_placement = options::FootnotePlacement::Inline
+        ],
+        concat!(
+            "> Quoted.[^a]\n",
+            "\n",
+            "Text.[^b]\n",
+            "\n",
+            "[^a]: A, with its own note.[^b]\n",
+            "[^b]: B.\n",
+        ),
+        concat!(
+            "<blockquote>\n",
+            "<p>Quoted.<sup class=\"footnote-ref\"><a href=\"#fn-a\" id=\"fnref-a\" data-footnote-ref>1</a></sup></p>\n",
+            "</blockquote>\n",
+            "<aside class=\"footnote\" id=\"fn-a\">\n",
+            "<p>A, with its own note.<sup class=\"footnote-ref\"><a href=\"#fn-b\" id=\"fnref-b-2\" data-footnote-ref>2</a></sup> \
+             <a href=\"#fnref-a\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1\" aria-label=\"Back to reference 1\">↩</a></p>\n",
+            "</aside>\n",
+            "<aside class=\"footnote\" id=\"fn-b\">\n",
+            "<p>B. <a href=\"#fnref-b\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"2\" aria-label=\"Back to reference 2\">↩</a> \
+             <a href=\"#fnref-b-2\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"2-2\" aria-label=\"Back to reference 2-2\">↩<sup class=\"footnote-ref\">2</sup></a></p>\n",
+            "</aside>\n",
+            "<p>Text.<sup class=\"footnote-ref\"><a href=\"#fn-b\" id=\"fnref-b\" data-footnote-ref>2</a></sup></p>\n",
+        )
+    );
+}
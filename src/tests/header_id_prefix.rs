@@ -62,3 +62,103 @@ fn header_id_prefix_in_href_without_prefix() {
         opts.extension.header_id_prefix_in_href = true;
     });
 }
+
+#[test]
+fn header_custom_ids() {
+    html_opts!(
+        [extension.header_custom_ids],
+        concat!(
+            "# Getting started {#setup}\n",
+            "\n",
+            "Setext *heading* {#setext}\n",
+            "=========================\n",
+            "\n",
+            "## Closed {#closed} ##\n",
+        ),
+        concat!(
+            "<h1><a href=\"#setup\" aria-hidden=\"true\" class=\"anchor\" id=\"setup\"></a>Getting started</h1>\n",
+            "<h1><a href=\"#setext\" aria-hidden=\"true\" class=\"anchor\" id=\"setext\"></a>Setext <em>heading</em></h1>\n",
+            "<h2><a href=\"#closed\" aria-hidden=\"true\" class=\"anchor\" id=\"closed\"></a>Closed</h2>\n",
+        ),
+    );
+}
+
+#[test]
+fn header_custom_ids_not_ids() {
+    html_opts!(
+        [extension.header_custom_ids],
+        "# Braces{#nope}\n\n# Spaced {#a b}\n\n# Mid {#x} word\n",
+        concat!(
+            "<h1>Braces{#nope}</h1>\n",
+            "<h1>Spaced {#a b}</h1>\n",
+            "<h1>Mid {#x} word</h1>\n",
+        ),
+    );
+    html("# Off {#off}\n", "<h1>Off {#off}</h1>\n");
+}
+
+#[test]
+fn header_custom_ids_with_prefix() {
+    html_opts_i(
+        "# Intro {#start}\n\n# Start\n\n# Start\n",
+        concat!(
+            "<h1><a href=\"#user-content-start\" aria-hidden=\"true\" class=\"anchor\" id=\"user-content-start\"></a>Intro</h1>\n",
+            "<h1><a href=\"#user-content-start-1\" aria-hidden=\"true\" class=\"anchor\" id=\"user-content-start-1\"></a>Start</h1>\n",
+            "<h1><a href=\"#user-content-start-2\" aria-hidden=\"true\" class=\"anchor\" id=\"user-content-start-2\"></a>Start</h1>\n",
+        ),
+        true,
+        |opts| {
+            opts.extension.header_custom_ids = true;
+            opts.extension.header_id_prefix = Some("user-content-".to_owned());
+            opts.extension.header_id_prefix_in_href = true;
+        },
+    );
+}
+
+#[test]
+fn header_custom_ids_reserved_ahead() {
+    // Explicit IDs are reserved before rendering, so an earlier generated ID
+    // doesn't take an explicit one further down.
+    html_opts!(
+        [
+            extension.header_custom_ids = true,
+            extension.header_id_prefix = Some("".to_owned())
+        ],
+        "# Usage\n\n# How to use {#usage}\n",
+        concat!(
+            "<h1><a href=\"#usage-1\" aria-hidden=\"true\" class=\"anchor\" id=\"usage-1\"></a>Usage</h1>\n",
+            "<h1><a href=\"#usage\" aria-hidden=\"true\" class=\"anchor\" id=\"usage\"></a>How to use</h1>\n",
+        ),
+    );
+}
+
+#[test]
+fn header_custom_ids_escaped() {
+    html_opts!(
+        [extension.header_custom_ids],
+        "# Quote {#a\"b<c}\n",
+        "<h1><a href=\"#a&quot;b&lt;c\" aria-hidden=\"true\" class=\"anchor\" id=\"a&quot;b&lt;c\"></a>Quote</h1>\n",
+    );
+}
+
+#[test]
+fn header_reserved_ids() {
+    html_opts!(
+        [
+            extension.header_id_prefix = Some("".to_owned()),
+            extension.header_reserved_ids = vec!["content".to_owned(), "content-1".to_owned()]
+        ],
+        "# Content\n\n# Content\n",
+        concat!(
+            "<h1><a href=\"#content-2\" aria-hidden=\"true\" class=\"anchor\" id=\"content-2\"></a>Content</h1>\n",
+            "<h1><a href=\"#content-3\" aria-hidden=\"true\" class=\"anchor\" id=\"content-3\"></a>Content</h1>\n",
+        ),
+    );
+}
+
+#[test]
+fn header_custom_ids_commonmark() {
+    let mut options = Options::default();
+    options.extension.header_custom_ids = true;
+    commonmark("Title {#top}\n=====\n", "# Title {#top}\n", Some(&options));
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use pretty_assertions::assert_eq;
+
+use super::*;
+use crate::options::{IncludeReference, IncludeResolver, ResolvedInclude};
+
+/// Resolves includes from an in-memory store, relative to the directory of
+/// the including document.
+fn store(files: &[(&str, &str)]) -> Arc<dyn IncludeResolver> {
+    let files: HashMap<String, String> = files
+        .iter()
+        .map(|(path, content)| (path.to_string(), content.to_string()))
+        .collect();
+    Arc::new(move |include: IncludeReference| {
+        let dir = include
+            .from
+            .and_then(|from| from.rsplit_once('/'))
+            .map_or("", |(dir, _)| dir);
+        let path = if dir.is_empty() {
+            include.target.to_string()
+        } else {
+            format!("{}/{}", dir, include.target)
+        };
+        files.get(&path).map(|content| ResolvedInclude {
+            path,
+            content: content.clone(),
+        })
+    })
+}
+
+#[test]
+fn include() {
+    html_opts!(
+        [parse.include_resolver = Some(store(&[
+            ("intro.md", "# Intro\n\nHello.\n"),
+            ("notes", "- one\n- two\n"),
+        ]))],
+        concat!(
+            "{{#include intro.md}}\n",
+            "\n",
+            "![[notes]]\n",
+            "\n",
+            "{{#include missing.md}}\n",
+        ),
+        concat!(
+            "<h1>Intro</h1>\n",
+            "<p>Hello.</p>\n",
+            "<ul>\n",
+            "<li>one</li>\n",
+            "<li>two</li>\n",
+            "</ul>\n",
+            "<p>{{#include missing.md}}</p>\n",
+        ),
+    );
+}
+
+#[test]
+fn include_shared_namespaces() {
+    // The outer document's definitions don't survive a round trip through
+    // CommonMark, as they're only used by the included document.
+    html_opts!(
+        [
+            extension.footnotes = true,
+            parse.include_resolver = Some(store(&[(
+                "part.md",
+                "Read [more][home] and [the docs].[^1]\n\n[home]: /part\n"
+            )]))
+        ],
+        concat!(
+            "{{#include part.md}}\n",
+            "\n",
+            "[Home][home], again.[^1]\n",
+            "\n",
+            "[^1]: A note.\n",
+            "\n",
+            "[home]: /\n",
+            "[the docs]: /docs\n",
+        ),
+        concat!(
+            "<p>Read <a href=\"/part\">more</a> and <a href=\"/docs\">the docs</a>.<sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1\" data-footnote-ref>1</a></sup></p>\n",
+            "<p><a href=\"/part\">Home</a>, again.<sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1-2\" data-footnote-ref>1</a></sup></p>\n",
+            "<section class=\"footnotes\" data-footnotes>\n",
+            "<ol>\n",
+            "<li id=\"fn-1\">\n",
+            "<p>A note. <a href=\"#fnref-1\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1\" aria-label=\"Back to reference 1\">↩</a> <a href=\"#fnref-1-2\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1-2\" aria-label=\"Back to reference 1-2\">↩<sup class=\"footnote-ref\">2</sup></a></p>\n",
+            "</li>\n",
+            "</ol>\n",
+            "</section>\n",
+        ),
+        no_roundtrip,
+    );
+}
+
+#[test]
+fn include_line_ranges() {
+    html_opts!(
+        [parse.include_resolver = Some(store(&[("lines.md", "one\n\ntwo\n\nthree\n\nfour\n")]))],
+        concat!(
+            "{{#include lines.md:3}}\n",
+            "\n",
+            "{{#include lines.md:5:}}\n",
+            "\n",
+            "{{#include lines.md::1}}\n",
+            "\n",
+            "{{#include lines.md:3:5}}\n",
+            "\n",
+            "{{#include lines.md:x}}\n",
+        ),
+        concat!(
+            "<p>two</p>\n",
+            "<p>three</p>\n",
+            "<p>four</p>\n",
+            "<p>one</p>\n",
+            "<p>two</p>\n",
+            "<p>three</p>\n",
+            "<p>{{#include lines.md:x}}</p>\n",
+        ),
+    );
+}
+
+#[test]
+fn include_nested_and_cyclic() {
+    html_opts!(
+        [parse.include_resolver = Some(store(&[
+            ("book/a.md", "A\n\n{{#include b.md}}\n"),
+            ("book/b.md", "B\n\n![[a.md]]\n"),
+        ]))],
+        "{{#include book/a.md}}\n",
+        concat!("<p>A</p>\n", "<p>B</p>\n", "<p>![[a.md]]</p>\n"),
+    );
+}
+
+#[test]
+fn include_cyclic_through_document() {
+    html_opts!(
+        [
+            parse.include_resolver = Some(store(&[
+                ("book/main.md", "Main\n"),
+                ("book/a.md", "A\n\n{{#include main.md}}\n"),
+            ])),
+            parse.document_path = Some("book/main.md".to_string())
+        ],
+        "{{#include a.md}}\n",
+        concat!("<p>A</p>\n", "<p>{{#include main.md}}</p>\n"),
+    );
+}
+
+#[test]
+fn include_sections_anchorize() {
+    struct Underscores;
+
+    impl IncludeResolver for Underscores {
+        fn resolve(&self, include: IncludeReference) -> Option<ResolvedInclude> {
+            Some(ResolvedInclude {
+                path: include.target.to_string(),
+                content: "# One\n\nIn one.\n\n# Two_Two\n\nIn two.\n".to_string(),
+            })
+        }
+
+        fn anchorize(&self, heading: &str) -> String {
+            heading.to_lowercase().replace(' ', "_")
+        }
+    }
+
+    html_opts!(
+        [
+            extension.wikilinks_title_after_pipe = true,
+            parse.include_resolver = Some(Arc::new(Underscores))
+        ],
+        "![[Note#two two]]\n",
+        "<h1>Two_Two</h1>\n<p>In two.</p>\n",
+    );
+}
+
+#[test]
+fn include_sourcepos() {
+    let mut options = Options::default();
+    options.parse.sourcepos_chars = true;
+    options.parse.include_resolver = Some(store(&[("part.md", "# Part\n\né *x*\n")]));
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, "ü\n\n{{#include part.md:3}}\n", &options);
+
+    let include = root.last_child().unwrap();
+    match include.data().value {
+        NodeValue::Include(ref ni) => {
+            assert_eq!(ni.target, "part.md:3");
+            assert_eq!(ni.path, "part.md");
+            assert!(!ni.embed);
+        }
+        ref value => panic!("expected an include, got {:?}", value),
+    }
+    assert_eq!(include.data().sourcepos, sourcepos!((3:1-3:22)));
+
+    let emph = include
+        .descendants()
+        .find(|n| node_matches!(n, NodeValue::Emph));
+    assert_eq!(emph.unwrap().data().sourcepos, sourcepos!((3:3-3:5)));
+}
+
+#[test]
+fn include_sections() {
+    html_opts!(
+        [
+            extension.wikilinks_title_after_pipe = true,
+            extension.block_anchors = true,
+            parse.include_resolver = Some(store(&[(
+                "Note",
+                concat!(
+                    "# Note\n",
+                    "\n",
+                    "Intro. ^intro\n",
+                    "\n",
+                    "## Section One\n",
+                    "\n",
+                    "In one.\n",
+                    "\n",
+                    "### Detail\n",
+                    "\n",
+                    "Deeper.\n",
+                    "\n",
+                    "## Two\n",
+                    "\n",
+                    "- a\n",
+                    "- b ^item\n",
+                    "- c\n",
+                )
+            )]))
+        ],
+        concat!(
+            "![[Note#Section One]]\n",
+            "\n",
+            "![[Note#^intro]]\n",
+            "\n",
+            "![[Note#^item]]\n",
+            "\n",
+            "![[Note#Nowhere]]\n",
+        ),
+        concat!(
+            "<h2>Section One</h2>\n",
+            "<p>In one.</p>\n",
+            "<h3>Detail</h3>\n",
+            "<p>Deeper.</p>\n",
+            "<p id=\"intro\">Intro.</p>\n",
+            "<ul>\n",
+            "<li id=\"item\">b</li>\n",
+            "</ul>\n",
+            "<p><a href=\"Note#nowhere\" data-wikilink=\"true\" data-embed=\"true\">Note#Nowhere</a></p>\n",
+        ),
+    );
+}
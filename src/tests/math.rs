@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use super::*;
 use ntest::test_case;
 
@@ -161,3 +163,127 @@ fn sourcepos() {
         ])
     );
 }
+
+#[test]
+fn math_labels() {
+    html_opts!(
+        [extension.math_dollars, extension.math_labels],
+        concat!(
+            "As @eq:energy and \\eqref{area} show.\n",
+            "\n",
+            "$$E = mc^2$$ {#eq:energy}\n",
+            "\n",
+            "$$A = \\pi r^2$${#eq:area} and $$x$$ {#not a label}\n",
+            "\n",
+            "See @eq:missing, \\eqref{missing} and [@eq:energy](#top).\n",
+        ),
+        concat!(
+            "<p>As <a href=\"#eq:energy\">(1)</a> and <a href=\"#eq:area\">(2)</a> show.</p>\n",
+            "<p><span class=\"math-equation\" id=\"eq:energy\">",
+            "<span data-math-style=\"display\">E = mc^2</span> ",
+            "<span class=\"math-equation-number\">(1)</span></span></p>\n",
+            "<p><span class=\"math-equation\" id=\"eq:area\">",
+            "<span data-math-style=\"display\">A = \\pi r^2</span> ",
+            "<span class=\"math-equation-number\">(2)</span></span> and ",
+            "<span data-math-style=\"display\">x</span> {#not a label}</p>\n",
+            "<p>See @eq:missing, \\eqref{missing} and <a href=\"#top\">@eq:energy</a>.</p>\n",
+        ),
+    );
+}
+
+#[test]
+fn math_labels_code_block() {
+    html_opts!(
+        [extension.math_dollars, extension.math_labels],
+        concat!(
+            "$$x$$ {#eq:x}\n",
+            "\n",
+            "```math {#eq:energy}\n",
+            "E = mc^2\n",
+            "```\n",
+            "\n",
+            "As @eq:energy shows.\n",
+            "\n",
+            "```rust {#lst:main}\n",
+            "```\n",
+        ),
+        concat!(
+            "<p><span class=\"math-equation\" id=\"eq:x\">",
+            "<span data-math-style=\"display\">x</span> ",
+            "<span class=\"math-equation-number\">(1)</span></span></p>\n",
+            "<div class=\"math-equation\" id=\"eq:energy\">\n",
+            "<pre><code class=\"language-math\" data-math-style=\"display\">E = mc^2\n",
+            "</code></pre>\n",
+            "<span class=\"math-equation-number\">(2)</span>\n",
+            "</div>\n",
+            "<p>As <a href=\"#eq:energy\">(2)</a> shows.</p>\n",
+            "<pre><code class=\"language-rust\">",
+            "</code></pre>\n",
+        ),
+    );
+}
+
+#[test]
+fn math_labels_disabled() {
+    html_opts!(
+        [extension.math_dollars],
+        "$$E = mc^2$$ {#eq:energy}\n\nSee @eq:energy.\n",
+        concat!(
+            "<p><span data-math-style=\"display\">E = mc^2</span> {#eq:energy}</p>\n",
+            "<p>See @eq:energy.</p>\n",
+        ),
+    );
+}
+
+#[test]
+fn math_labels_broken_link_callback() {
+    let cb = |link_ref: options::BrokenLinkReference| match link_ref.normalized {
+        "eq:euler" | "euler" => Some(ResolvedReference {
+            url: "identities.html#eq:euler".to_string(),
+            title: link_ref.original.to_string(),
+        }),
+        _ => None,
+    };
+    let mut options = Options::default();
+    options.extension.math_dollars = true;
+    options.extension.math_labels = true;
+    options.parse.broken_link_callback = Some(Arc::new(cb));
+
+    compare_strs(
+        &markdown_to_html(
+            "By @eq:euler, \\eqref{euler} and @eq:pythagoras.\n",
+            &options,
+        ),
+        concat!(
+            "<p>By <a href=\"identities.html#eq:euler\" title=\"@eq:euler\">@eq:euler</a>, ",
+            "<a href=\"identities.html#eq:euler\" title=\"\\eqref{euler}\">\\eqref{euler}</a> ",
+            "and @eq:pythagoras.</p>\n",
+        ),
+        "html",
+        "",
+    );
+}
+
+#[test]
+fn math_labels_sourcepos() {
+    assert_ast_match!(
+        [extension.math_dollars, extension.math_labels],
+        "$$x$$ {#eq:x}\n"
+        "\n"
+        "By @eq:x.\n",
+        (document (1:1-3:9) [
+            (paragraph (1:1-1:13) [
+                (equation (1:1-1:13) [
+                    (math (1:1-1:5) "x")
+                ])
+            ])
+            (paragraph (3:1-3:9) [
+                (text (3:1-3:3) "By ")
+                (link (3:4-3:8) "#eq:x" [
+                    (text (3:4-3:8) "(1)")
+                ])
+                (text (3:9-3:9) ".")
+            ])
+        ])
+    );
+}
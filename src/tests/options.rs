@@ -1,3 +1,4 @@
+use crate::options::SmartPunctuation;
 use pretty_assertions::assert_eq;
 use std::sync::Arc;
 
@@ -87,6 +88,108 @@ fn smart_guillemets() {
     );
 }
 
+#[test]
+fn smart_punctuation_locales() {
+    let input = "\"Say 'hi',\" it's said.\n";
+    for (smart_punctuation, expected) in [
+        (SmartPunctuation::En, "<p>“Say ‘hi’,” it’s said.</p>\n"),
+        (SmartPunctuation::De, "<p>„Say ‚hi‘,“ it’s said.</p>\n"),
+        (
+            SmartPunctuation::Fr,
+            "<p>«\u{202f}Say ‹\u{202f}hi\u{202f}›,\u{202f}» it’s said.</p>\n",
+        ),
+        (SmartPunctuation::Ch, "<p>«Say ‹hi›,» it’s said.</p>\n"),
+        (SmartPunctuation::Ru, "<p>«Say „hi“,» it’s said.</p>\n"),
+        (SmartPunctuation::Ja, "<p>「Say 『hi』,」 it’s said.</p>\n"),
+    ] {
+        html_opts!(
+            [
+                parse.smart = true,
+                parse.smart_punctuation = smart_punctuation
+            ],
+            input,
+            expected
+        );
+    }
+}
+
+#[test]
+fn smart_punctuation_guillemets() {
+    let input = "<<Ja>>, << oui >>\n";
+    for (smart_punctuation, expected) in [
+        (SmartPunctuation::En, "<p>«Ja», « oui »</p>\n"),
+        (SmartPunctuation::De, "<p>»Ja«, » oui «</p>\n"),
+        (
+            SmartPunctuation::Fr,
+            "<p>«\u{202f}Ja\u{202f}», «\u{202f}oui\u{202f}»</p>\n",
+        ),
+        (SmartPunctuation::Ch, "<p>«Ja», « oui »</p>\n"),
+    ] {
+        html_opts!(
+            [
+                parse.smart = true,
+                parse.smart_punctuation = smart_punctuation
+            ],
+            input,
+            expected
+        );
+    }
+}
+
+#[test]
+fn smart_punctuation_french_spacing() {
+    html_opts!(
+        [
+            parse.smart = true,
+            parse.smart_punctuation = SmartPunctuation::Fr
+        ],
+        "Vraiment ? Oui : << bien sûr >> ; `a ; b` et [lien](http://x.fr \"t ?\") !\n",
+        "<p>Vraiment\u{202f}? Oui\u{a0}: «\u{202f}bien sûr\u{202f}»\u{202f}; <code>a ; b</code> \
+         et <a href=\"http://x.fr\" title=\"t ?\">lien</a>\u{202f}!</p>\n"
+    );
+
+    html_opts!(
+        [
+            parse.smart = true,
+            parse.smart_punctuation = SmartPunctuation::Fr,
+            extension.autolink = true,
+            render.sourcepos = true
+        ],
+        "Courriel : foo@bar.com ou ? x@y.fr !\n",
+        "<p data-sourcepos=\"1:1-1:36\">Courriel\u{a0}: <a data-sourcepos=\"1:12-1:22\" href=\"mailto:foo@bar.com\">foo@bar.com</a> \
+         ou\u{202f}? <a data-sourcepos=\"1:29-1:34\" href=\"mailto:x@y.fr\">x@y.fr</a>\u{202f}!</p>\n"
+    );
+
+    // Only with smart punctuation.
+    html_opts!(
+        [parse.smart_punctuation = SmartPunctuation::Fr],
+        "Vraiment ?\n",
+        "<p>Vraiment ?</p>\n"
+    );
+}
+
+#[test]
+fn smart_punctuation_front_matter_override() {
+    html_opts!(
+        [
+            parse.smart = true,
+            extension.front_matter_delimiter = Some("---".to_string())
+        ],
+        "---\ntitle: Notiz\nlang: de-DE\n---\n\n\"Hallo\"\n",
+        "<p>„Hallo“</p>\n"
+    );
+
+    html_opts!(
+        [
+            parse.smart = true,
+            parse.smart_punctuation = SmartPunctuation::Ru,
+            extension.front_matter_delimiter = Some("---".to_string())
+        ],
+        "---\nlang: xx\n---\n\n\"Привет\"\n",
+        "<p>«Привет»</p>\n"
+    );
+}
+
 #[test]
 fn broken_link_callback() {
     let cb = |link_ref: options::BrokenLinkReference| match link_ref.normalized {
@@ -0,0 +1,253 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+use crate::html::split_document;
+
+fn render_pages(input: &str, level: u8, options: &Options) -> Vec<(String, String)> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, input, options);
+    let pages = split_document(&arena, root, level, options, &options::Plugins::default());
+
+    (0..pages.pages.len())
+        .map(|ix| {
+            let mut html = String::new();
+            pages
+                .format_page(ix, options, &mut html, &options::Plugins::default())
+                .unwrap();
+            (pages.pages[ix].filename.clone(), html)
+        })
+        .collect()
+}
+
+#[test]
+fn split_pages_footnotes() {
+    let mut options = Options::default();
+    options.extension.footnotes = true;
+
+    let pages = render_pages(
+        concat!(
+            "Intro.[^a]\n",
+            "\n",
+            "# One\n",
+            "\n",
+            "Text.[^b] Again.[^a]\n",
+            "\n",
+            "# Two\n",
+            "\n",
+            "More.[^c][^b]\n",
+            "\n",
+            "[^a]: Note A.\n",
+            "[^b]: Note B.\n",
+            "[^c]: Note C.\n",
+        ),
+        1,
+        &options,
+    );
+
+    assert_eq!(
+        pages,
+        [
+            (
+                "index.html".to_string(),
+                concat!(
+                    "<p>Intro.<sup class=\"footnote-ref\"><a href=\"#fn-a\" id=\"fnref-a\" data-footnote-ref>1</a></sup></p>\n",
+                    "<section class=\"footnotes\" data-footnotes>\n",
+                    "<ol>\n",
+                    "<li id=\"fn-a\">\n",
+                    "<p>Note A. <a href=\"#fnref-a\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1\" aria-label=\"Back to reference 1\">↩</a> <a href=\"one.html#fnref-a-2\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1-2\" aria-label=\"Back to reference 1-2\">↩<sup class=\"footnote-ref\">2</sup></a></p>\n",
+                    "</li>\n",
+                    "</ol>\n",
+                    "</section>\n",
+                )
+                .to_string()
+            ),
+            (
+                "one.html".to_string(),
+                concat!(
+                    "<h1>One</h1>\n",
+                    "<p>Text.<sup class=\"footnote-ref\"><a href=\"#fn-b\" id=\"fnref-b\" data-footnote-ref>2</a></sup> Again.<sup class=\"footnote-ref\"><a href=\"index.html#fn-a\" id=\"fnref-a-2\" data-footnote-ref>1</a></sup></p>\n",
+                    "<section class=\"footnotes\" data-footnotes>\n",
+                    "<ol start=\"2\">\n",
+                    "<li id=\"fn-b\">\n",
+                    "<p>Note B. <a href=\"#fnref-b\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"2\" aria-label=\"Back to reference 2\">↩</a> <a href=\"two.html#fnref-b-2\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"2-2\" aria-label=\"Back to reference 2-2\">↩<sup class=\"footnote-ref\">2</sup></a></p>\n",
+                    "</li>\n",
+                    "</ol>\n",
+                    "</section>\n",
+                )
+                .to_string()
+            ),
+            (
+                "two.html".to_string(),
+                concat!(
+                    "<h1>Two</h1>\n",
+                    "<p>More.<sup class=\"footnote-ref\"><a href=\"#fn-c\" id=\"fnref-c\" data-footnote-ref>3</a></sup><sup class=\"footnote-ref\"><a href=\"one.html#fn-b\" id=\"fnref-b-2\" data-footnote-ref>2</a></sup></p>\n",
+                    "<section class=\"footnotes\" data-footnotes>\n",
+                    "<ol start=\"3\">\n",
+                    "<li id=\"fn-c\">\n",
+                    "<p>Note C. <a href=\"#fnref-c\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"3\" aria-label=\"Back to reference 3\">↩</a></p>\n",
+                    "</li>\n",
+                    "</ol>\n",
+                    "</section>\n",
+                )
+                .to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn split_pages_anchors_and_links() {
+    let mut options = Options::default();
+    options.extension.header_id_prefix = Some("user-content-".to_owned());
+    options.render.heading_numbers = true;
+
+    let pages = render_pages(
+        concat!(
+            "# Setup\n",
+            "\n",
+            "## Notes\n",
+            "\n",
+            "See [notes](#notes), [later notes](#user-content-notes-1) and [x](#nowhere).\n",
+            "\n",
+            "# Usage\n",
+            "\n",
+            "### Notes\n",
+            "\n",
+            "[Back](#setup).\n",
+        ),
+        1,
+        &options,
+    );
+
+    assert_eq!(
+        pages,
+        [
+            (
+                "setup.html".to_string(),
+                concat!(
+                    "<h1><a href=\"#setup\" aria-hidden=\"true\" class=\"anchor\" id=\"user-content-setup\"></a><span class=\"heading-number\">1</span> Setup</h1>\n",
+                    "<h2><a href=\"#notes\" aria-hidden=\"true\" class=\"anchor\" id=\"user-content-notes\"></a><span class=\"heading-number\">1.1</span> Notes</h2>\n",
+                    "<p>See <a href=\"#notes\">notes</a>, <a href=\"usage.html#user-content-notes-1\">later notes</a> and <a href=\"#nowhere\">x</a>.</p>\n",
+                )
+                .to_string()
+            ),
+            (
+                "usage.html".to_string(),
+                concat!(
+                    "<h1><a href=\"#usage\" aria-hidden=\"true\" class=\"anchor\" id=\"user-content-usage\"></a><span class=\"heading-number\">2</span> Usage</h1>\n",
+                    "<h3><a href=\"#notes-1\" aria-hidden=\"true\" class=\"anchor\" id=\"user-content-notes-1\"></a><span class=\"heading-number\">2.1</span> Notes</h3>\n",
+                    "<p><a href=\"setup.html#setup\">Back</a>.</p>\n",
+                )
+                .to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn split_pages_tree() {
+    let arena = Arena::new();
+    let options = Options::default();
+    let root = parse_document(
+        &arena,
+        "Preamble.\n\n# A\n\n## B\n\n### C\n\ntext\n\n# D\n",
+        &options,
+    );
+
+    let pages = split_document(&arena, root, 2, &options, &options::Plugins::default());
+    let summary: Vec<_> = pages
+        .pages
+        .iter()
+        .map(|page| {
+            (
+                page.filename.as_str(),
+                page.title.as_deref(),
+                page.level,
+                page.root.children().count(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("index.html", None, 0, 1),
+            ("a.html", Some("A"), 1, 1),
+            ("b.html", Some("B"), 2, 3),
+            ("d.html", Some("D"), 1, 1),
+        ]
+    );
+    assert!(root.first_child().is_none());
+    assert_eq!(pages.pages[2].root.data().sourcepos, (5, 1, 9, 4).into());
+
+    let mut html = String::new();
+    pages.format_index(&options, &mut html).unwrap();
+    assert_eq!(
+        html,
+        concat!(
+            "<ul class=\"page-index\">\n",
+            "<li><a href=\"a.html\">A</a>\n",
+            "<ul>\n",
+            "<li><a href=\"b.html\">B</a></li>\n",
+            "</ul>\n",
+            "</li>\n",
+            "<li><a href=\"d.html\">D</a></li>\n",
+            "</ul>\n",
+        )
+    );
+}
+
+#[test]
+fn split_pages_unique_filenames() {
+    let arena = Arena::new();
+    let options = Options::default();
+    let root = parse_document(
+        &arena,
+        "intro\n\n# Index\n\nx\n\n# !!!\n\ny\n\n# Page 3\n",
+        &options,
+    );
+
+    let pages = split_document(&arena, root, 1, &options, &options::Plugins::default());
+    let filenames: Vec<_> = pages
+        .pages
+        .iter()
+        .map(|page| page.filename.as_str())
+        .collect();
+    assert_eq!(
+        filenames,
+        ["index.html", "index-1.html", "page-3.html", "page-3-1.html"]
+    );
+}
+
+#[test]
+fn split_pages_filenames_from_custom_ids() {
+    let arena = Arena::new();
+    let mut options = Options::default();
+    options.extension.header_custom_ids = true;
+    let root = parse_document(
+        &arena,
+        "# A {#../../etc/passwd}\n\n# B {#/abs/path}\n\n# C {#./..}\n",
+        &options,
+    );
+
+    let pages = split_document(&arena, root, 1, &options, &options::Plugins::default());
+    let filenames: Vec<_> = pages
+        .pages
+        .iter()
+        .map(|page| page.filename.as_str())
+        .collect();
+    assert_eq!(filenames, ["etcpasswd.html", "abspath.html", "page-3.html"]);
+}
+
+#[test]
+fn split_pages_empty() {
+    let arena = Arena::new();
+    let options = Options::default();
+    let root = parse_document(&arena, "", &options);
+
+    let pages = split_document(&arena, root, 1, &options, &options::Plugins::default());
+    assert!(pages.pages.is_empty());
+
+    let mut html = String::new();
+    pages.format_index(&options, &mut html).unwrap();
+    assert_eq!(html, "");
+}
@@ -1,7 +1,12 @@
+use pretty_assertions::assert_eq;
 use std::borrow::Cow;
 
 use crate::{
-    adapters::{CodefenceRendererAdapter, HeadingAdapter, HeadingMeta, SyntaxHighlighterAdapter},
+    adapters::{
+        AnchorizerAdapter, CodefenceRendererAdapter, FenceMeta, FootnoteAdapter, FootnoteMeta,
+        FootnoteReferenceMeta, HeadingAdapter, HeadingMeta, MathRendererAdapter, MathRendering,
+        SyntaxHighlighterAdapter,
+    },
     nodes::Sourcepos,
 };
 
@@ -434,3 +439,1218 @@ fn syntect_plugin_with_prefixed_css_classes() {
 
     html_plugins(input, expected, &plugins);
 }
+
+#[test]
+fn heading_adapter_plugin_numbers() {
+    struct MockAdapter;
+
+    impl HeadingAdapter for MockAdapter {
+        fn enter(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            heading: &HeadingMeta,
+            _sourcepos: Option<Sourcepos>,
+        ) -> std::fmt::Result {
+            write!(
+                output,
+                "<h{} data-number=\"{}\">",
+                heading.level,
+                heading.number.as_deref().unwrap_or("-")
+            )
+        }
+
+        fn exit(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            heading: &HeadingMeta,
+        ) -> std::fmt::Result {
+            write!(
+                output,
+                "<!-- {} --></h{}>",
+                heading.number.as_deref().unwrap_or("-"),
+                heading.level
+            )
+        }
+    }
+
+    let mut options = Options::default();
+    options.render.heading_numbers = true;
+    let mut plugins = options::Plugins::default();
+    plugins.render.heading_adapter = Some(&MockAdapter);
+
+    assert_eq!(
+        markdown_to_html_with_plugins("# A\n\n## B\n\n> # C\n", &options, &plugins),
+        concat!(
+            "<h1 data-number=\"1\">A<!-- 1 --></h1>\n",
+            "<h2 data-number=\"1.1\">B<!-- 1.1 --></h2>\n",
+            "<blockquote>\n",
+            "<h1 data-number=\"-\">C<!-- - --></h1>\n",
+            "</blockquote>\n",
+        )
+    );
+}
+
+#[test]
+fn heading_adapter_plugin_ids() {
+    struct MockAdapter;
+
+    impl HeadingAdapter for MockAdapter {
+        fn enter(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            heading: &HeadingMeta,
+            _sourcepos: Option<Sourcepos>,
+        ) -> std::fmt::Result {
+            write!(output, "<h{}", heading.level)?;
+            if let Some(ref id) = heading.id {
+                write!(output, " id=\"{}\"", id)?;
+            }
+            output.write_str(">")
+        }
+
+        fn exit(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            heading: &HeadingMeta,
+        ) -> std::fmt::Result {
+            write!(
+                output,
+                "<!-- {} --></h{}>",
+                heading.id.as_deref().unwrap_or("-"),
+                heading.level
+            )
+        }
+    }
+
+    struct Underscores;
+
+    impl AnchorizerAdapter for Underscores {
+        fn slugify(&self, header: &str) -> String {
+            header.to_lowercase().replace(' ', "_")
+        }
+
+        fn unique(&self, slug: &str, n: usize) -> String {
+            format!("{}_{}", slug, n)
+        }
+    }
+
+    let mut options = Options::default();
+    options.extension.header_custom_ids = true;
+    let mut plugins = options::Plugins::default();
+    plugins.render.heading_adapter = Some(&MockAdapter);
+
+    assert_eq!(
+        markdown_to_html_with_plugins("# A {#custom}\n\n## Plain B\n", &options, &plugins),
+        concat!(
+            "<h1 id=\"custom\">A<!-- custom --></h1>\n",
+            "<h2>Plain B<!-- - --></h2>",
+        )
+    );
+
+    options.extension.header_id_prefix = Some("h-".to_string());
+    plugins.render.anchorizer_adapter = Some(&Underscores);
+    assert_eq!(
+        markdown_to_html_with_plugins("# A {#custom}\n\n## Plain B\n", &options, &plugins),
+        concat!(
+            "<h1 id=\"h-custom\">A<!-- h-custom --></h1>\n",
+            "<h2 id=\"h-plain_b\">Plain B<!-- h-plain_b --></h2>",
+        )
+    );
+}
+
+#[test]
+fn anchorizer_adapter_plugin() {
+    struct GitLabLike;
+
+    impl AnchorizerAdapter for GitLabLike {
+        fn slugify(&self, header: &str) -> String {
+            header
+                .to_lowercase()
+                .chars()
+                .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '_')
+                .map(|c| if c == ' ' { '_' } else { c })
+                .collect()
+        }
+
+        fn unique(&self, slug: &str, n: usize) -> String {
+            format!("{}_{}", slug, n)
+        }
+    }
+
+    let arena = Arena::new();
+    let mut options = Options::default();
+    options.extension.header_id_prefix = Some("".to_string());
+    options.extension.table_of_contents = true;
+    options.extension.header_reserved_ids = vec!["install".to_string()];
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.anchorizer_adapter = Some(&GitLabLike);
+
+    let root = parse_document(
+        &arena,
+        "[TOC]\n\n# Read me!\n\n## Install\n\n# Read me!\n",
+        &options,
+    );
+    let mut output = String::new();
+    html::format_document_with_plugins(root, &options, &mut output, &plugins).unwrap();
+    compare_strs(
+        &output,
+        concat!(
+            "<ul class=\"table-of-contents\">\n",
+            "<li><a href=\"#read_me\">Read me!</a>\n",
+            "<ul>\n",
+            "<li><a href=\"#install_1\">Install</a></li>\n",
+            "</ul>\n",
+            "</li>\n",
+            "<li><a href=\"#read_me_1\">Read me!</a></li>\n",
+            "</ul>\n",
+            "<h1><a href=\"#read_me\" aria-hidden=\"true\" class=\"anchor\" id=\"read_me\"></a>Read me!</h1>\n",
+            "<h2><a href=\"#install_1\" aria-hidden=\"true\" class=\"anchor\" id=\"install_1\"></a>Install</h2>\n",
+            "<h1><a href=\"#read_me_1\" aria-hidden=\"true\" class=\"anchor\" id=\"read_me_1\"></a>Read me!</h1>\n",
+        ),
+        "regular",
+        "",
+    );
+
+    let anchors: Vec<_> = html::toc_with_plugins(root, &options, &plugins)
+        .into_iter()
+        .map(|e| e.anchor)
+        .collect();
+    assert_eq!(anchors, ["read_me", "install_1", "read_me_1"]);
+}
+
+#[test]
+fn footnote_adapter_plugin() {
+    struct Sidenotes;
+
+    impl FootnoteAdapter for Sidenotes {
+        fn write_reference(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            reference: &FootnoteReferenceMeta,
+            _sourcepos: Option<Sourcepos>,
+        ) -> std::fmt::Result {
+            write!(
+                output,
+                "<label for=\"{}\" id=\"{}\">{}.{}</label>",
+                reference.href, reference.id, reference.ix, reference.ref_num
+            )
+        }
+
+        fn enter_section(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            start: u32,
+            _sourcepos: Option<Sourcepos>,
+        ) -> std::fmt::Result {
+            writeln!(output, "<div class=\"notes\" data-start=\"{}\">", start)
+        }
+
+        fn exit_section(&self, output: &mut dyn std::fmt::Write) -> std::fmt::Result {
+            writeln!(output, "</div>")
+        }
+
+        fn enter_definition(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            footnote: &FootnoteMeta,
+            sourcepos: Option<Sourcepos>,
+        ) -> std::fmt::Result {
+            write!(
+                output,
+                "<small id=\"{}\" data-backrefs=\"{}\" data-placement=\"{:?}\"",
+                footnote.id,
+                footnote.reference_hrefs.join(" "),
+                footnote.placement
+            )?;
+            if let Some(sourcepos) = sourcepos {
+                write!(output, " data-sourcepos=\"{}\"", sourcepos)?;
+            }
+            output.write_str(">")
+        }
+
+        fn exit_definition(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            _footnote: &FootnoteMeta,
+        ) -> std::fmt::Result {
+            writeln!(output, "</small>")
+        }
+    }
+
+    let mut options = Options::default();
+    options.extension.footnotes = true;
+    options.render.footnote_id_prefix = Some("p-".to_string());
+    options.render.footnote_placement = options::FootnotePlacement::Section;
+    options.render.sourcepos = true;
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.footnote_adapter = Some(&Sidenotes);
+
+    let arena = Arena::new();
+    let root = parse_document(
+        &arena,
+        concat!(
+            "# One\n",
+            "\n",
+            "A[^x] B[^x].\n",
+            "\n",
+            "# Two\n",
+            "\n",
+            "C[^y].\n",
+            "\n",
+            "[^x]: X.\n",
+            "[^y]: Y.\n",
+        ),
+        &options,
+    );
+    let mut output = String::new();
+    html::format_document_with_plugins(root, &options, &mut output, &plugins).unwrap();
+    compare_strs(
+        &output,
+        concat!(
+            "<h1 data-sourcepos=\"1:1-1:5\">One</h1>\n",
+            "<p data-sourcepos=\"3:1-3:12\">A<label for=\"#p-fn-x\" id=\"p-fnref-x\">1.1</label> \
+             B<label for=\"#p-fn-x\" id=\"p-fnref-x-2\">1.2</label>.</p>\n",
+            "<div class=\"notes\" data-start=\"1\">\n",
+            "<small id=\"p-fn-x\" data-backrefs=\"#p-fnref-x #p-fnref-x-2\" data-placement=\"Section\" data-sourcepos=\"9:1-9:8\">\n",
+            "<p data-sourcepos=\"9:7-9:8\">X.</p>\n",
+            "</small>\n",
+            "</div>\n",
+            "<h1 data-sourcepos=\"5:1-5:5\">Two</h1>\n",
+            "<p data-sourcepos=\"7:1-7:6\">C<label for=\"#p-fn-y\" id=\"p-fnref-y\">2.1</label>.</p>\n",
+            "<div class=\"notes\" data-start=\"2\">\n",
+            "<small id=\"p-fn-y\" data-backrefs=\"#p-fnref-y\" data-placement=\"Section\" data-sourcepos=\"10:1-10:8\">\n",
+            "<p data-sourcepos=\"10:7-10:8\">Y.</p>\n",
+            "</small>\n",
+            "</div>\n",
+        ),
+        "regular",
+        "",
+    );
+}
+
+#[test]
+fn math_renderer_plugin() {
+    struct MathML;
+
+    impl MathRendererAdapter for MathML {
+        fn write(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            literal: &str,
+            display_math: bool,
+            sourcepos: Option<Sourcepos>,
+        ) -> Result<MathRendering, std::fmt::Error> {
+            let display = if display_math { "block" } else { "inline" };
+            write!(output, "<math display=\"{}\"", display)?;
+            if let Some(sourcepos) = sourcepos {
+                write!(output, " data-sourcepos=\"{}\"", sourcepos)?;
+            }
+            output.write_str("><mtext>")?;
+            html::escape(output, literal)?;
+            output.write_str("</mtext></math>")?;
+            Ok(MathRendering::Written)
+        }
+    }
+
+    let mut options = Options::default();
+    options.extension.math_dollars = true;
+    options.extension.math_code = true;
+    options.render.sourcepos = true;
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.math_renderer_adapter = Some(&MathML);
+
+    let arena = Arena::new();
+    let root = parse_document(
+        &arena,
+        concat!(
+            "$a<b$ and $`c`$\n",
+            "\n",
+            "$$d$$\n",
+            "\n",
+            "```math\n",
+            "e\n",
+            "```\n",
+        ),
+        &options,
+    );
+    let mut output = String::new();
+    html::format_document_with_plugins(root, &options, &mut output, &plugins).unwrap();
+    compare_strs(
+        &output,
+        concat!(
+            "<p data-sourcepos=\"1:1-1:15\"><math display=\"inline\" data-sourcepos=\"1:1-1:5\"><mtext>a&lt;b</mtext></math> and ",
+            "<math display=\"inline\" data-sourcepos=\"1:11-1:15\"><mtext>c</mtext></math></p>\n",
+            "<p data-sourcepos=\"3:1-3:5\"><math display=\"block\" data-sourcepos=\"3:1-3:5\"><mtext>d</mtext></math></p>\n",
+            "<math display=\"block\" data-sourcepos=\"5:1-7:3\"><mtext>e\n</mtext></math>\n",
+        ),
+        "regular",
+        "",
+    );
+}
+
+#[test]
+#[cfg(feature = "mathml")]
+fn mathml_plugin() {
+    let adapter = crate::plugins::mathml::MathMLAdapter::new();
+
+    let mut options = Options::default();
+    options.extension.math_dollars = true;
+    options.extension.math_code = true;
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.math_renderer_adapter = Some(&adapter);
+
+    let input = concat!(
+        "$\\alpha^2 \\leq \\frac{a}{b}$\n",
+        "\n",
+        "$$\\sum_{i=1}^n x_i$$\n",
+        "\n",
+        "```math\n",
+        "\\begin{pmatrix} a & b \\\\ c & d \\end{pmatrix}\n",
+        "```\n",
+    );
+    let expected = concat!(
+        "<p><math><msup><mi>α</mi><mn>2</mn></msup><mo>≤</mo>",
+        "<mfrac><mi>a</mi><mi>b</mi></mfrac></math></p>\n",
+        "<p><math display=\"block\"><munderover><mo>∑</mo>",
+        "<mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mi>n</mi></munderover>",
+        "<msub><mi>x</mi><mi>i</mi></msub></math></p>\n",
+        "<math display=\"block\"><mrow><mo fence=\"true\" form=\"prefix\">(</mo><mtable>",
+        "<mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd></mtr>",
+        "<mtr><mtd><mi>c</mi></mtd><mtd><mi>d</mi></mtd></mtr>",
+        "</mtable><mo fence=\"true\" form=\"postfix\">)</mo></mrow></math>\n",
+    );
+
+    let html = markdown_to_html_with_plugins(input, &options, &plugins);
+    compare_strs(&html, expected, "rendered", input);
+    assert!(adapter.take_diagnostics().is_empty());
+}
+
+#[test]
+#[cfg(feature = "mathml")]
+fn mathml_plugin_align() {
+    use crate::plugins::mathml::tex_to_mathml;
+
+    assert_eq!(
+        tex_to_mathml(
+            "\\begin{align} f(x) &= x^2 \\\\ &\\geq 0 \\\\ \\end{align}",
+            true
+        )
+        .unwrap(),
+        concat!(
+            "<math display=\"block\"><mtable displaystyle=\"true\">",
+            "<mtr><mtd style=\"text-align: right\"><mrow><mi>f</mi><mo>(</mo><mi>x</mi><mo>)</mo></mrow></mtd>",
+            "<mtd style=\"text-align: left\"><mrow><mo>=</mo><msup><mi>x</mi><mn>2</mn></msup></mrow></mtd></mtr>",
+            "<mtr><mtd style=\"text-align: right\"><mrow></mrow></mtd>",
+            "<mtd style=\"text-align: left\"><mrow><mo>≥</mo><mn>0</mn></mrow></mtd></mtr>",
+            "</mtable></math>"
+        )
+    );
+    assert_eq!(
+        tex_to_mathml("\\mathbb{R} \\to \\mathbf{x}", false).unwrap(),
+        "<math><mi>ℝ</mi><mo>→</mo><mi>𝐱</mi></math>"
+    );
+}
+
+#[test]
+#[cfg(feature = "mathml")]
+fn mathml_plugin_deep_nesting() {
+    use crate::plugins::mathml::{Error, tex_to_mathml};
+
+    let tex = format!("{}x{}", "{".repeat(5000), "}".repeat(5000));
+    assert_eq!(
+        tex_to_mathml(&tex, false),
+        Err(Error::Syntax("nested too deeply".to_string()))
+    );
+    assert_eq!(
+        tex_to_mathml(&"\\not".repeat(5000), false),
+        Err(Error::Syntax("nested too deeply".to_string()))
+    );
+
+    let tex = format!("{}x{}", "{".repeat(100), "}".repeat(100));
+    assert_eq!(
+        tex_to_mathml(&tex, false).unwrap(),
+        "<math><mi>x</mi></math>"
+    );
+}
+
+#[test]
+#[cfg(feature = "mathml")]
+fn mathml_plugin_fallback() {
+    use crate::plugins::mathml::{Error, MathDiagnostic};
+
+    let adapter = crate::plugins::mathml::MathMLAdapter::new();
+
+    let mut options = Options::default();
+    options.extension.math_dollars = true;
+    options.extension.math_code = true;
+    options.render.sourcepos = true;
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.math_renderer_adapter = Some(&adapter);
+
+    let input = concat!(
+        "$\\boxed{a<b}$ and ${x$ and $y$\n",
+        "\n",
+        "$`\\boxed{c}`$\n",
+        "\n",
+        "```math\n",
+        "\\boxed{d}\n",
+        "```\n",
+    );
+    let expected = concat!(
+        "<p data-sourcepos=\"1:1-1:30\">",
+        "<span data-math-style=\"inline\" data-sourcepos=\"1:1-1:13\" ",
+        "data-math-error=\"unsupported command \\boxed\">\\boxed{a&lt;b}</span> and ",
+        "<span data-math-style=\"inline\" data-sourcepos=\"1:19-1:22\" ",
+        "data-math-error=\"expected }\">{x</span> and ",
+        "<math data-sourcepos=\"1:28-1:30\"><mi>y</mi></math></p>\n",
+        "<p data-sourcepos=\"3:1-3:13\">",
+        "<code data-math-style=\"inline\" data-sourcepos=\"3:1-3:13\" ",
+        "data-math-error=\"unsupported command \\boxed\">\\boxed{c}</code></p>\n",
+        "<pre data-sourcepos=\"5:1-7:3\" data-math-error=\"unsupported command \\boxed\">",
+        "<code class=\"language-math\" data-math-style=\"display\">\\boxed{d}\n",
+        "</code></pre>\n",
+    );
+
+    let html = markdown_to_html_with_plugins(input, &options, &plugins);
+    compare_strs(&html, expected, "rendered", input);
+    assert_eq!(
+        adapter.take_diagnostics(),
+        vec![
+            MathDiagnostic {
+                literal: "\\boxed{a<b}".to_string(),
+                error: Error::UnsupportedCommand("boxed".to_string()),
+                sourcepos: Some((1, 1, 1, 13).into()),
+            },
+            MathDiagnostic {
+                literal: "{x".to_string(),
+                error: Error::Syntax("expected }".to_string()),
+                sourcepos: Some((1, 19, 1, 22).into()),
+            },
+            MathDiagnostic {
+                literal: "\\boxed{c}".to_string(),
+                error: Error::UnsupportedCommand("boxed".to_string()),
+                sourcepos: Some((3, 1, 3, 13).into()),
+            },
+            MathDiagnostic {
+                literal: "\\boxed{d}\n".to_string(),
+                error: Error::UnsupportedCommand("boxed".to_string()),
+                sourcepos: Some((5, 1, 7, 3).into()),
+            },
+        ]
+    );
+    assert!(adapter.take_diagnostics().is_empty());
+}
+
+#[test]
+#[cfg(feature = "syntect")]
+fn syntect_plugin_with_fence_meta() {
+    let adapter = crate::plugins::syntect::SyntectAdapter::new(Some("base16-ocean.dark"));
+
+    let input = concat!(
+        "```text title=\"notes <1>.txt\" {2} showLineNumbers=9\n",
+        "one\n",
+        "two\n",
+        "```\n",
+    );
+    let expected = concat!(
+        "<div class=\"code-title\">notes &lt;1&gt;.txt</div>\n",
+        "<pre style=\"background-color:#2b303b;\"><code class=\"language-text\">",
+        "<span class=\"line\" data-line-number=\"9\"><span style=\"color:#c0c5ce;\">one</span></span>\n",
+        "<span class=\"line highlighted\" data-line-number=\"10\"><span style=\"color:#c0c5ce;\">two</span></span>\n",
+        "</code></pre>\n",
+    );
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&adapter);
+
+    html_plugins(input, expected, &plugins);
+}
+
+#[test]
+#[cfg(feature = "syntect")]
+fn syntect_plugin_with_fence_meta_css_classes() {
+    let adapter = crate::plugins::syntect::SyntectAdapter::new(None);
+
+    let input = concat!("```rust {1}\n", "/* a\n", "b */\n", "```\n");
+    let expected = concat!(
+        "<pre class=\"syntax-highlighting\"><code class=\"language-rust\">",
+        "<span class=\"line highlighted\"><span class=\"source rust\"><span class=\"comment block rust\">",
+        "<span class=\"punctuation definition comment rust\">/*</span> a</span></span></span>\n",
+        "<span class=\"line\"><span class=\"source rust\"><span class=\"comment block rust\">",
+        "b <span class=\"punctuation definition comment rust\">*/</span></span></span></span>\n",
+        "</code></pre>\n",
+    );
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&adapter);
+
+    html_plugins(input, expected, &plugins);
+}
+
+#[test]
+fn fence_meta_parse() {
+    let meta =
+        FenceMeta::parse(r#"title="a b.rs" label='x y' {1, 4-5} {9} linenos {5-3} flag=  ="""#);
+    assert_eq!(
+        meta.attributes,
+        vec![
+            ("title".to_string(), "a b.rs".to_string()),
+            ("label".to_string(), "x y".to_string()),
+            ("flag".to_string(), String::new()),
+        ]
+    );
+    assert_eq!(meta.highlighted_lines, vec![(1, 1), (4, 5), (9, 9)]);
+    assert_eq!(meta.flags, vec!["linenos", "{5-3}", "=\"\""]);
+    assert_eq!(meta.line_numbers(), None);
+    assert!(meta.is_highlighted(5));
+    assert!(!meta.is_highlighted(6));
+
+    assert_eq!(
+        FenceMeta::parse("showLineNumbers=0").line_numbers(),
+        Some(0)
+    );
+    assert_eq!(FenceMeta::parse(""), FenceMeta::default());
+}
+
+#[test]
+#[cfg(feature = "tree-sitter")]
+fn tree_sitter_plugin() {
+    let mut adapter = crate::plugins::tree_sitter::TreeSitterAdapter::new();
+    adapter
+        .register(
+            "json",
+            tree_sitter_json::LANGUAGE,
+            concat!(
+                "(string) @string\n",
+                "(pair key: (string) @property)\n",
+                "(number) @number\n",
+                "[(true) (false) (null)] @constant.builtin\n",
+                "[\"{\" \"}\"] @punctuation.bracket\n",
+            ),
+            "",
+            "",
+        )
+        .unwrap();
+    assert!(adapter.alias("jsonc", "json"));
+    assert!(!adapter.alias("yml", "yaml"));
+
+    let input = concat!(
+        "```jsonc\n",
+        "{\"a\": 1, \"b\": [true, \"<c>\"]}\n",
+        "```\n",
+        "\n",
+        "```yaml\n",
+        "a: <b>\n",
+        "```\n",
+    );
+    let expected = concat!(
+        "<pre class=\"syntax-highlighting\"><code class=\"language-jsonc\">",
+        "<span class=\"punctuation section\">{</span>",
+        "<span class=\"variable other member\">&quot;a&quot;</span>: ",
+        "<span class=\"constant numeric\">1</span>, ",
+        "<span class=\"variable other member\">&quot;b&quot;</span>: ",
+        "[<span class=\"constant language\">true</span>, ",
+        "<span class=\"string\">&quot;&lt;c&gt;&quot;</span>]",
+        "<span class=\"punctuation section\">}</span>\n",
+        "</code></pre>\n",
+        "<pre class=\"syntax-highlighting\"><code class=\"language-yaml\">a: &lt;b&gt;\n",
+        "</code></pre>\n",
+    );
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&adapter);
+
+    html_plugins(input, expected, &plugins);
+}
+
+#[test]
+#[cfg(feature = "tree-sitter")]
+fn tree_sitter_plugin_grammar_queries() {
+    let mut adapter = crate::plugins::tree_sitter::TreeSitterAdapter::new();
+    adapter
+        .register(
+            "json",
+            tree_sitter_json::LANGUAGE,
+            tree_sitter_json::HIGHLIGHTS_QUERY,
+            "",
+            "",
+        )
+        .unwrap();
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&adapter);
+
+    html_plugins(
+        "```json\n{\"a\": [1, \"\\n\", null]}\n```\n",
+        concat!(
+            "<pre class=\"syntax-highlighting\"><code class=\"language-json\">",
+            "{<span class=\"string\">&quot;a&quot;</span>: ",
+            "[<span class=\"constant numeric\">1</span>, ",
+            "<span class=\"string\">&quot;<span class=\"constant character escape\">\\n</span>&quot;</span>, ",
+            "<span class=\"constant language\">null</span>]}\n",
+            "</code></pre>\n",
+        ),
+        &plugins,
+    );
+}
+
+#[test]
+#[cfg(feature = "tree-sitter")]
+fn tree_sitter_plugin_with_prefixed_css_classes() {
+    let mut adapter = crate::plugins::tree_sitter::TreeSitterAdapter::with_class_prefix("hl-");
+    adapter
+        .register(
+            "json",
+            tree_sitter_json::LANGUAGE,
+            "(number) @number",
+            "",
+            "",
+        )
+        .unwrap();
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&adapter);
+
+    html_plugins(
+        "```json\n[1]\n```\n",
+        concat!(
+            "<pre class=\"syntax-highlighting\"><code class=\"language-json\">",
+            "[<span class=\"hl-constant hl-numeric\">1</span>]\n",
+            "</code></pre>\n",
+        ),
+        &plugins,
+    );
+    assert!(
+        adapter
+            .register("json", tree_sitter_json::LANGUAGE, "(nope) @x", "", "")
+            .is_err()
+    );
+}
+
+#[test]
+#[cfg(feature = "syntect")]
+fn syntect_plugin_stylesheet() {
+    let adapter = crate::plugins::syntect::SyntectAdapterBuilder::new()
+        .css_with_class_prefix("hl-")
+        .build();
+
+    let light = adapter.stylesheet("InspiredGitHub").unwrap();
+    assert!(light.starts_with(concat!(
+        "pre.syntax-highlighting {\n",
+        " color: #323232;\n",
+        " background-color: #ffffff;\n",
+        "}\n",
+        "pre.syntax-highlighting .line.highlighted {\n",
+        " background-color: #f5f5f5;\n",
+        "}\n",
+        "\n",
+    )));
+    assert!(light.contains(".hl-comment {\n"));
+    assert!(!light.contains(".comment {\n"));
+
+    let css = adapter
+        .stylesheet_with_dark_theme("InspiredGitHub", "base16-ocean.dark")
+        .unwrap();
+    let (before, dark) = css
+        .split_once("\n@media (prefers-color-scheme: dark) {\n")
+        .unwrap();
+    assert_eq!(before, light);
+    assert_eq!(
+        dark,
+        format!("{}}}\n", adapter.stylesheet("base16-ocean.dark").unwrap())
+    );
+    assert!(matches!(
+        adapter.stylesheet_with_dark_theme("InspiredGitHub", "nope"),
+        Err(crate::plugins::syntect::StylesheetError::UnknownTheme(name)) if name == "nope"
+    ));
+}
+
+#[test]
+#[cfg(feature = "syntect")]
+fn syntect_plugin_load_from_paths() {
+    let dir = std::env::temp_dir().join(format!("comrak-syntect-{}", std::process::id()));
+    let syntaxes = dir.join("syntaxes");
+    let themes = dir.join("themes");
+    std::fs::create_dir_all(&syntaxes).unwrap();
+    std::fs::create_dir_all(themes.join("dark")).unwrap();
+
+    std::fs::write(
+        syntaxes.join("Greeting.sublime-syntax"),
+        concat!(
+            "%YAML 1.2\n",
+            "---\n",
+            "name: Greeting\n",
+            "file_extensions: [greeting]\n",
+            "scope: source.greeting\n",
+            "contexts:\n",
+            "  main:\n",
+            "    - match: '\\bhello\\b'\n",
+            "      scope: keyword.greeting\n",
+        ),
+    )
+    .unwrap();
+    let theme = concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<plist version=\"1.0\">\n",
+        "<dict>\n",
+        "  <key>settings</key>\n",
+        "  <array>\n",
+        "    <dict><key>settings</key><dict>",
+        "<key>background</key><string>#000000</string>",
+        "<key>foreground</key><string>#ffffff</string>",
+        "</dict></dict>\n",
+        "    <dict><key>scope</key><string>keyword</string><key>settings</key><dict>",
+        "<key>foreground</key><string>#ff0000</string>",
+        "</dict></dict>\n",
+        "  </array>\n",
+        "</dict>\n",
+        "</plist>\n",
+    );
+    std::fs::write(themes.join("dark").join("Night.tmTheme"), theme).unwrap();
+    std::fs::write(dir.join("Plain.tmTheme"), theme).unwrap();
+
+    let builder = crate::plugins::syntect::SyntectAdapterBuilder::new()
+        .add_syntaxes_from_folder(&syntaxes)
+        .unwrap()
+        .add_themes_from_folder(&themes)
+        .unwrap()
+        .add_theme_file(dir.join("Plain.tmTheme"))
+        .unwrap();
+    assert!(
+        crate::plugins::syntect::SyntectAdapterBuilder::new()
+            .add_theme_file(dir.join("Missing.tmTheme"))
+            .is_err()
+    );
+
+    let adapter = builder.build();
+    let names: Vec<&str> = adapter.theme_names().collect();
+    assert!(names.contains(&"Night"));
+    assert!(names.contains(&"Plain"));
+    assert!(names.contains(&"InspiredGitHub"));
+    assert!(
+        adapter
+            .syntaxes()
+            .iter()
+            .any(|syntax| syntax.name == "Greeting" && syntax.file_extensions == ["greeting"])
+    );
+
+    let mut plugins = options::Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&adapter);
+    html_plugins(
+        "```greeting\nhello world\n```\n",
+        concat!(
+            "<pre class=\"syntax-highlighting\"><code class=\"language-greeting\">",
+            "<span class=\"source greeting\"><span class=\"keyword greeting\">hello</span> world\n",
+            "</span></code></pre>\n",
+        ),
+        &plugins,
+    );
+
+    let adapter = crate::plugins::syntect::SyntectAdapterBuilder::new()
+        .theme("Plain")
+        .add_theme_file(dir.join("Plain.tmTheme"))
+        .unwrap()
+        .add_syntaxes_from_folder(&syntaxes)
+        .unwrap()
+        .build();
+    plugins.render.codefence_syntax_highlighter = Some(&adapter);
+    html_plugins(
+        "```greeting\nhello world\n```\n",
+        concat!(
+            "<pre style=\"background-color:#000000;\"><code class=\"language-greeting\">",
+            "<span style=\"color:#ff0000;\">hello</span>",
+            "<span style=\"color:#ffffff;\"> world\n",
+            "</span></code></pre>\n",
+        ),
+        &plugins,
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn shell_session_plugin() {
+    let adapter = crate::plugins::shell_session::ShellSessionAdapter::new().with_prompts(&[
+        "$ ",
+        "PS> ",
+        "PS C:\\> ",
+    ]);
+    let mut plugins = options::Plugins::default();
+    plugins
+        .render
+        .codefence_renderers
+        .insert("console".to_string(), &adapter);
+
+    html_plugins(
+        concat!(
+            "```console\n",
+            "$ cargo build \\\n",
+            "    --release\n",
+            "   Compiling <crate>\n",
+            "\n",
+            "PS C:\\> dir\n",
+            "```\n",
+        ),
+        concat!(
+            "<pre class=\"shell-session\"><code class=\"language-console\">",
+            "<span class=\"prompt\" style=\"user-select: none\">$ </span>",
+            "<span class=\"command\">cargo build \\</span>\n",
+            "<span class=\"command\">    --release</span>\n",
+            "<span class=\"output\">   Compiling &lt;crate&gt;</span>\n",
+            "\n",
+            "<span class=\"prompt\" style=\"user-select: none\">PS C:\\&gt; </span>",
+            "<span class=\"command\">dir</span>\n",
+            "</code></pre>\n",
+        ),
+        &plugins,
+    );
+}
+
+#[test]
+#[cfg(feature = "syntect")]
+fn shell_session_plugin_with_syntect() {
+    let syntect = crate::plugins::syntect::SyntectAdapter::new(None);
+    let adapter = crate::plugins::shell_session::ShellSessionAdapter::new()
+        .with_highlighter(&syntect, "bash");
+    let mut plugins = options::Plugins::default();
+    plugins
+        .render
+        .codefence_renderers
+        .insert("console".to_string(), &adapter);
+
+    let arena = Arena::new();
+    let mut options = Options::default();
+    options.render.sourcepos = true;
+    let root = parse_document(&arena, "```console\n$ echo hi\nhi\n```\n", &options);
+    let mut html = String::new();
+    format_html_with_plugins(root, &options, &mut html, &plugins).unwrap();
+
+    assert!(html.starts_with(concat!(
+        "<pre class=\"shell-session\" data-sourcepos=\"1:1-4:3\"><code class=\"language-console\">",
+        "<span class=\"prompt\" style=\"user-select: none\">$ </span>",
+        "<span class=\"command\"><span class=\"source shell bash\">",
+    )));
+    assert!(html.contains("<span class=\"support function echo shell\">echo</span>"));
+    assert!(html.ends_with("</span>\n<span class=\"output\">hi</span>\n</code></pre>\n"));
+}
+
+#[test]
+fn diff_plugin() {
+    let adapter = crate::plugins::diff::DiffAdapter::new();
+    let mut plugins = options::Plugins::default();
+    plugins
+        .render
+        .codefence_renderers
+        .insert("diff".to_string(), &adapter);
+
+    html_plugins(
+        concat!(
+            "```diff sql\n",
+            "--- a/q.sql\n",
+            "+++ b/q.sql\n",
+            "@@ -1,2 +1,2 @@\n",
+            " SELECT 1;\n",
+            "--- old\n",
+            "+SELECT 2 < 3;\n",
+            "\\ No newline at end of file\n",
+            "```\n",
+        ),
+        concat!(
+            "<pre class=\"diff\"><code class=\"language-diff\" data-lang=\"sql\">",
+            "<span class=\"header\">--- a/q.sql</span>\n",
+            "<span class=\"header\">+++ b/q.sql</span>\n",
+            "<span class=\"hunk\">@@ -1,2 +1,2 @@</span>\n",
+            "<span class=\"unchanged\"><span class=\"marker\" style=\"user-select: none\"> </span>SELECT 1;</span>\n",
+            "<span class=\"deleted\"><span class=\"marker\" style=\"user-select: none\">-</span>-- old</span>\n",
+            "<span class=\"inserted\"><span class=\"marker\" style=\"user-select: none\">+</span>SELECT 2 &lt; 3;</span>\n",
+            "\\ No newline at end of file\n",
+            "</code></pre>\n",
+        ),
+        &plugins,
+    );
+}
+
+#[test]
+#[cfg(feature = "syntect")]
+fn diff_plugin_with_syntect() {
+    let syntect = crate::plugins::syntect::SyntectAdapter::new(None);
+    let adapter = crate::plugins::diff::DiffAdapter::new().with_highlighter(&syntect);
+    let mut plugins = options::Plugins::default();
+    plugins
+        .render
+        .codefence_renderers
+        .insert("diff".to_string(), &adapter);
+
+    html_plugins(
+        "```diff rust\n-let x = 1;\n```\n",
+        concat!(
+            "<pre class=\"diff\"><code class=\"language-diff\" data-lang=\"rust\">",
+            "<span class=\"deleted\"><span class=\"marker\" style=\"user-select: none\">-</span>",
+            "<span class=\"source rust\"><span class=\"storage type rust\">let</span> x ",
+            "<span class=\"keyword operator rust\">=</span> ",
+            "<span class=\"constant numeric integer decimal rust\">1</span>",
+            "<span class=\"punctuation terminator rust\">;</span></span></span>\n",
+            "</code></pre>\n",
+        ),
+        &plugins,
+    );
+
+    html_plugins(
+        "```diff\n+let x = 1;\n```\n",
+        concat!(
+            "<pre class=\"diff\"><code class=\"language-diff\">",
+            "<span class=\"inserted\"><span class=\"marker\" style=\"user-select: none\">+</span>",
+            "let x = 1;</span>\n",
+            "</code></pre>\n",
+        ),
+        &plugins,
+    );
+}
+
+#[test]
+#[cfg(feature = "syntect")]
+fn diff_plugin_with_syntect_per_line() {
+    let syntect = crate::plugins::syntect::SyntectAdapter::new(None);
+    let adapter = crate::plugins::diff::DiffAdapter::new().with_highlighter(&syntect);
+    let mut plugins = options::Plugins::default();
+    plugins
+        .render
+        .codefence_renderers
+        .insert("diff".to_string(), &adapter);
+
+    // Each line is highlighted on its own, so the end of a comment spanning
+    // lines isn't highlighted as one.
+    html_plugins(
+        "```diff rust\n+/* a\n+b */\n```\n",
+        concat!(
+            "<pre class=\"diff\"><code class=\"language-diff\" data-lang=\"rust\">",
+            "<span class=\"inserted\"><span class=\"marker\" style=\"user-select: none\">+</span>",
+            "<span class=\"source rust\"><span class=\"comment block rust\">",
+            "<span class=\"punctuation definition comment rust\">/*</span> a</span></span></span>\n",
+            "<span class=\"inserted\"><span class=\"marker\" style=\"user-select: none\">+</span>",
+            "<span class=\"source rust\">b <span class=\"keyword operator rust\">*</span>",
+            "<span class=\"keyword operator rust\">/</span></span></span>\n",
+            "</code></pre>\n",
+        ),
+        &plugins,
+    );
+}
+
+#[test]
+fn diagram_plugin() {
+    let input = concat!(
+        "```mermaid\n",
+        "A --> B & C\n",
+        "```\n",
+        "\n",
+        "```dot title=\"A <graph>\"\n",
+        "digraph { a -> b }\n",
+        "```\n",
+    );
+
+    let adapter = crate::plugins::diagrams::DiagramAdapter::new();
+    let mut plugins = options::Plugins::default();
+    adapter.register(&mut plugins.render);
+    html_plugins(
+        input,
+        concat!(
+            "<pre class=\"mermaid\">A --&gt; B &amp; C\n</pre>\n",
+            "<pre class=\"dot\">digraph { a -&gt; b }\n</pre>\n",
+        ),
+        &plugins,
+    );
+    html_plugins(
+        "```mermaid\n</pre><script>alert(1)</script>\n```\n",
+        "<pre class=\"mermaid\">&lt;/pre&gt;&lt;script&gt;alert(1)&lt;/script&gt;\n</pre>\n",
+        &plugins,
+    );
+
+    let adapter = crate::plugins::diagrams::DiagramAdapter::new()
+        .with_wrapper(crate::plugins::diagrams::DiagramWrapper::Figure)
+        .unescaped();
+    let mut plugins = options::Plugins::default();
+    adapter.register(&mut plugins.render);
+    html_plugins(
+        input,
+        concat!(
+            "<figure class=\"diagram\"><pre class=\"mermaid\">A --> B & C\n</pre></figure>\n",
+            "<figure class=\"diagram\"><pre class=\"dot\">digraph { a -> b }\n</pre>",
+            "<figcaption>A &lt;graph&gt;</figcaption></figure>\n",
+        ),
+        &plugins,
+    );
+
+    let arena = Arena::new();
+    let options = Options::default();
+    let root = parse_document(&arena, input, &options);
+    let mut md = String::new();
+    format_commonmark(root, &options, &mut md).unwrap();
+    assert_eq!(md, input);
+
+    let mut xml = String::new();
+    format_xml(root, &options, &mut xml).unwrap();
+    assert!(xml.contains(
+        "<code_block info=\"mermaid\" xml:space=\"preserve\">A --&gt; B &amp; C\n</code_block>"
+    ));
+}
+
+#[test]
+#[cfg(unix)]
+fn command_diagram_plugin() {
+    let input = "```svg\n<?xml version=\"1.0\"?>\n<svg><g/></svg>\n```\n";
+
+    let adapter = crate::plugins::diagrams::CommandDiagramAdapter::new("cat", &["-"]);
+    let mut plugins = options::Plugins::default();
+    plugins
+        .render
+        .codefence_renderers
+        .insert("svg".to_string(), &adapter);
+    html_plugins(
+        input,
+        "<figure class=\"diagram\"><svg><g/></svg></figure>\n",
+        &plugins,
+    );
+
+    let adapter =
+        crate::plugins::diagrams::CommandDiagramAdapter::new("comrak-no-such-command", &["-x"]);
+    plugins
+        .render
+        .codefence_renderers
+        .insert("svg".to_string(), &adapter);
+    html_plugins(
+        input,
+        concat!(
+            "<pre><code class=\"language-svg\">&lt;?xml version=&quot;1.0&quot;?&gt;\n",
+            "&lt;svg&gt;&lt;g/&gt;&lt;/svg&gt;\n",
+            "</code></pre>\n",
+        ),
+        &plugins,
+    );
+}
+
+#[test]
+#[cfg(feature = "syntect")]
+fn deferred_plugin() {
+    struct Headings;
+    impl HeadingAdapter for Headings {
+        fn enter(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            heading: &HeadingMeta,
+            sourcepos: Option<Sourcepos>,
+        ) -> std::fmt::Result {
+            write!(
+                output,
+                "<h{} data-content=\"{}\" data-at=\"{}\">",
+                heading.level,
+                heading.content,
+                sourcepos.unwrap()
+            )
+        }
+
+        fn exit(
+            &self,
+            output: &mut dyn std::fmt::Write,
+            heading: &HeadingMeta,
+        ) -> std::fmt::Result {
+            writeln!(output, "</h{}>", heading.level)
+        }
+    }
+
+    let input = concat!(
+        "# Title\n",
+        "\n",
+        "```mermaid\n",
+        "A --> B\n",
+        "```\n",
+        "\n",
+        "```rust {1}\n",
+        "let x = 1;\n",
+        "```\n",
+        "\n",
+        "```\n",
+        "plain\n",
+        "```\n",
+        "\n",
+        "## End\n",
+    );
+    let mut options = Options::default();
+    options.render.sourcepos = true;
+
+    let syntect = crate::plugins::syntect::SyntectAdapter::new(None);
+    let diagrams = crate::plugins::diagrams::DiagramAdapter::new();
+    let mut plugins = options::Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&syntect);
+    plugins.render.heading_adapter = Some(&Headings);
+    diagrams.register(&mut plugins.render);
+    let expected = markdown_to_html_with_plugins(input, &options, &plugins);
+
+    let deferred = crate::html::Deferred::new().with_highlighter(&syntect);
+    let mut deferred_plugins = options::Plugins::default();
+    deferred_plugins.render.codefence_syntax_highlighter = Some(&deferred);
+    deferred_plugins.render.heading_adapter = Some(&deferred);
+    deferred_plugins
+        .render
+        .codefence_renderers
+        .insert("mermaid".to_string(), &deferred);
+    let html = markdown_to_html_with_plugins(input, &options, &deferred_plugins);
+
+    let jobs = deferred.take_jobs();
+    assert_eq!(jobs.len(), 7);
+    assert!(html.contains(&jobs[2].placeholder()));
+    match &jobs[3].kind {
+        crate::html::DeferredKind::Highlight { lang, meta, code } => {
+            assert_eq!(lang.as_deref(), Some("rust"));
+            assert_eq!(meta.highlighted_lines, [(1, 1)]);
+            assert_eq!(code, "let x = 1;\n");
+        }
+        kind => panic!("unexpected job {:?}", kind),
+    }
+    assert_eq!(jobs[2].sourcepos.unwrap().to_string(), "3:1-5:3");
+    assert_eq!(jobs[3].sourcepos.unwrap().to_string(), "7:1-9:3");
+
+    // Render the jobs in parallel, as each stands alone.
+    let outputs: Vec<String> = std::thread::scope(|scope| {
+        let handles: Vec<_> = jobs
+            .iter()
+            .map(|job| {
+                let plugins = &plugins;
+                scope.spawn(move || job.render(&plugins.render).unwrap())
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    assert_eq!(deferred.substitute(&html, &outputs), expected);
+
+    assert!(deferred.take_jobs().is_empty());
+    assert_eq!(
+        deferred
+            .substitute(&html, &outputs[..1])
+            .matches("<!--comrak-deferred-")
+            .count(),
+        6
+    );
+}
+
+#[test]
+fn deferred_plugin_forged_placeholder() {
+    let deferred = crate::html::Deferred::new();
+    let mut plugins = options::Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&deferred);
+
+    let mut options = Options::default();
+    options.render.r#unsafe = true;
+    let input = "<!--comrak-deferred:0-->\n\n```\nhi\n```\n";
+    let html = markdown_to_html_with_plugins(input, &options, &plugins);
+
+    assert_eq!(deferred.take_jobs().len(), 1);
+    assert_eq!(
+        deferred.substitute(&html, &["HI"]),
+        "<!--comrak-deferred:0-->\n<pre><code>HI</code></pre>\n"
+    );
+}
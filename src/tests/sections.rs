@@ -0,0 +1,129 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+use crate::sections::{Section, sections};
+
+fn outline(section: &Section<'_>) -> Vec<(String, String, usize)> {
+    let mut out = vec![];
+    for subsection in &section.subsections {
+        out.push((
+            subsection.number_string().unwrap(),
+            html::collect_text(subsection.heading.unwrap()),
+            subsection.content.len(),
+        ));
+        out.extend(outline(subsection));
+    }
+    out
+}
+
+#[test]
+fn section_tree() {
+    let arena = Arena::new();
+    let root = parse_document(
+        &arena,
+        concat!(
+            "Preamble.\n",
+            "\n",
+            "# Intro\n",
+            "\n",
+            "Text.\n",
+            "\n",
+            "### Skipped\n",
+            "\n",
+            "## Sibling\n",
+            "\n",
+            "> # Quoted\n",
+            "\n",
+            "- list\n",
+            "\n",
+            "# Usage\n",
+            "\n",
+            "#### Deep\n",
+        ),
+        &Options::default(),
+    );
+
+    let tree = sections(root);
+    assert!(tree.heading.is_none());
+    assert_eq!(tree.level, 0);
+    assert_eq!(tree.number_string(), None);
+    assert_eq!(tree.content.len(), 1);
+    assert_eq!(
+        outline(&tree),
+        [
+            ("1".into(), "Intro".into(), 1),
+            ("1.1".into(), "Skipped".into(), 0),
+            ("1.2".into(), "Sibling".into(), 2),
+            ("2".into(), "Usage".into(), 0),
+            ("2.1".into(), "Deep".into(), 0),
+        ]
+    );
+
+    let intro = &tree.subsections[0];
+    assert_eq!(intro.nodes().len(), 6);
+    assert_eq!(
+        intro.nodes().last().unwrap().data().sourcepos,
+        (13, 1, 13, 6).into()
+    );
+    assert_eq!(tree.nodes().len(), root.children().count());
+}
+
+#[test]
+fn section_tree_empty() {
+    let arena = Arena::new();
+    let root = parse_document(&arena, "", &Options::default());
+
+    let tree = sections(root);
+    assert!(tree.content.is_empty());
+    assert!(tree.subsections.is_empty());
+}
+
+#[test]
+fn heading_numbers() {
+    html_opts!(
+        [render.heading_numbers],
+        concat!(
+            "# Scope\n",
+            "\n",
+            "### Skipped\n",
+            "\n",
+            "## Terms\n",
+            "\n",
+            "> ## Not numbered\n",
+            "\n",
+            "# Usage\n",
+        ),
+        concat!(
+            "<h1><span class=\"heading-number\">1</span> Scope</h1>\n",
+            "<h3><span class=\"heading-number\">1.1</span> Skipped</h3>\n",
+            "<h2><span class=\"heading-number\">1.2</span> Terms</h2>\n",
+            "<blockquote>\n",
+            "<h2>Not numbered</h2>\n",
+            "</blockquote>\n",
+            "<h1><span class=\"heading-number\">2</span> Usage</h1>\n",
+        ),
+    );
+}
+
+#[test]
+fn heading_numbers_with_ids_and_toc() {
+    html_opts!(
+        [
+            render.heading_numbers = true,
+            extension.table_of_contents = true,
+            extension.header_id_prefix = Some("".to_owned())
+        ],
+        "[TOC]\n\n# Scope\n\n## Terms\n",
+        concat!(
+            "<ul class=\"table-of-contents\">\n",
+            "<li><a href=\"#scope\"><span class=\"heading-number\">1</span> Scope</a>\n",
+            "<ul>\n",
+            "<li><a href=\"#terms\"><span class=\"heading-number\">1.1</span> Terms</a></li>\n",
+            "</ul>\n",
+            "</li>\n",
+            "</ul>\n",
+            "<h1><a href=\"#scope\" aria-hidden=\"true\" class=\"anchor\" id=\"scope\"></a><span class=\"heading-number\">1</span> Scope</h1>\n",
+            "<h2><a href=\"#terms\" aria-hidden=\"true\" class=\"anchor\" id=\"terms\"></a><span class=\"heading-number\">1.1</span> Terms</h2>\n",
+        ),
+    );
+}
@@ -1,7 +1,10 @@
 use nodes::NodeValueDiscriminants;
 use pretty_assertions::assert_eq;
+use std::sync::Arc;
 use strum::VariantArray;
 
+use crate::options::{IncludeReference, ResolvedInclude};
+
 use super::*;
 
 type TestCase = (&'static [Sourcepos], &'static str);
@@ -238,6 +241,15 @@ hello world
 "#,
 );
 
+const TABLE_CAPTION: TestCase = (
+    &[sourcepos!((4:1-4:23))],
+    r#"| a |
+|---|
+
+Table: *Results* {#tbl}
+"#,
+);
+
 const TABLE_CELL: TestCase = (
     &[
         sourcepos!((3:2-3:8)),
@@ -468,6 +480,42 @@ hello world
 after"#,
 );
 
+const TABLE_OF_CONTENTS: TestCase = (
+    &[sourcepos!((3:1-3:5))],
+    r#"before
+
+[TOC]
+
+after"#,
+);
+
+const TYPOGRAPHIC: TestCase = (
+    &[sourcepos!((1:5-1:7)), sourcepos!((2:2-2:3))],
+    r#"Acme(c) 2024
+1st"#,
+);
+
+const EQUATION: TestCase = (
+    &[sourcepos!((1:7-1:23))],
+    r#"Hence $$x = 1$$ {#eq:x}, by @eq:x."#,
+);
+
+const FIGURE: TestCase = (
+    &[sourcepos!((1:5-1:29))],
+    r#"See ![Cat](cat.png){#fig:cat} in [@fig:cat]."#,
+);
+
+const INCLUDE: TestCase = (
+    &[sourcepos!((3:1-3:12))],
+    r#"before
+
+![[part.md]]
+
+after"#,
+);
+
+const BLOCK_ANCHOR: TestCase = (&[sourcepos!((1:11-1:14))], r#"Some *it* ^abc"#);
+
 fn node_values() -> HashMap<NodeValueDiscriminants, TestCase> {
     use NodeValueDiscriminants::*;
 
@@ -500,6 +548,7 @@ fn node_values() -> HashMap<NodeValueDiscriminants, TestCase> {
                 Table => TABLE,
                 TableRow => TABLE_ROW,
                 TableCell => TABLE_CELL,
+                TableCaption => TABLE_CAPTION,
                 Text => TEXT,
                 SoftBreak => SOFT_BREAK,
                 LineBreak => LINE_BREAK,
@@ -522,6 +571,12 @@ fn node_values() -> HashMap<NodeValueDiscriminants, TestCase> {
                 Alert => ALERT,
                 Subtext => SUBTEXT,
                 BlockDirective => BLOCK_DIRECTIVE,
+                TableOfContents => TABLE_OF_CONTENTS,
+                Typographic => TYPOGRAPHIC,
+                Equation => EQUATION,
+                Figure => FIGURE,
+                Include => INCLUDE,
+                BlockAnchor => BLOCK_ANCHOR,
                 Raw => unreachable!(),
                 #[cfg(feature = "phoenix_heex")]
                 HeexBlock => HEEX_BLOCK,
@@ -543,6 +598,7 @@ fn sourcepos() {
     options.extension.description_lists = true;
     options.extension.footnotes = true;
     options.extension.table = true;
+    options.extension.table_captions = true;
     options.extension.tasklist = true;
     options.extension.strikethrough = true;
     options.extension.highlight = true;
@@ -556,13 +612,24 @@ fn sourcepos() {
     }
     options.extension.math_code = true;
     options.extension.math_dollars = true;
+    options.extension.math_labels = true;
+    options.extension.cross_references = true;
     options.extension.multiline_block_quotes = true;
     options.extension.wikilinks_title_after_pipe = true;
+    options.extension.block_anchors = true;
     options.extension.underline = true;
     options.extension.spoiler = true;
     options.extension.alerts = true;
     options.extension.subtext = true;
     options.extension.block_directive = true;
+    options.extension.table_of_contents = true;
+    options.parse.typographer = true;
+    options.parse.include_resolver = Some(Arc::new(|include: IncludeReference| {
+        (include.target == "part.md").then(|| ResolvedInclude {
+            path: "part.md".to_string(),
+            content: "# Part\n".to_string(),
+        })
+    }));
     #[cfg(feature = "phoenix_heex")]
     {
         options.extension.phoenix_heex = true;
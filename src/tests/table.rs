@@ -519,3 +519,796 @@ fn table_missing_cells_sourcepos() {
         ])
     );
 }
+
+#[test]
+fn extended_table_spans() {
+    html_opts!(
+        [extension.table, extension.extended_tables],
+        concat!(
+            "| a | b | c |\n",
+            "|---|:-:|---|\n",
+            "| d || e |\n",
+            "| f | g | h |\n",
+            "| ^^ | i || \n",
+            "| ^^ | ^^ | j |\n",
+        ),
+        concat!(
+            "<table>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>a</th>\n",
+            "<th align=\"center\">b</th>\n",
+            "<th>c</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td colspan=\"2\">d</td>\n",
+            "<td>e</td>\n",
+            "</tr>\n",
+            "<tr>\n",
+            "<td rowspan=\"3\">f</td>\n",
+            "<td align=\"center\">g</td>\n",
+            "<td>h</td>\n",
+            "</tr>\n",
+            "<tr>\n",
+            "<td align=\"center\" colspan=\"2\" rowspan=\"2\">i</td>\n",
+            "</tr>\n",
+            "<tr>\n",
+            "<td>j</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn extended_table_spans_disabled() {
+    html_opts!(
+        [extension.table],
+        concat!("| a | b |\n", "|---|---|\n", "| c ||\n", "| ^^ | d |\n"),
+        concat!(
+            "<table>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>a</th>\n",
+            "<th>b</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td>c</td>\n",
+            "<td></td>\n",
+            "</tr>\n",
+            "<tr>\n",
+            "<td>^^</td>\n",
+            "<td>d</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn extended_table_rowspan_without_cell_above() {
+    html_opts!(
+        [extension.table, extension.extended_tables],
+        concat!("| a |\n", "|---|\n", "| ^^ |\n"),
+        concat!(
+            "<table>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>a</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td>^^</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn extended_table_header_rows() {
+    html_opts!(
+        [extension.table, extension.extended_tables],
+        concat!(
+            "Fruit\n",
+            "| Name | Details ||\n",
+            "| ^^ | Size | Color |\n",
+            "|------|------|-------|\n",
+            "| Apple | 10 | red |\n",
+        ),
+        concat!(
+            "<p>Fruit</p>\n",
+            "<table>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th rowspan=\"2\">Name</th>\n",
+            "<th colspan=\"2\">Details</th>\n",
+            "</tr>\n",
+            "<tr>\n",
+            "<th>Size</th>\n",
+            "<th>Color</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td>Apple</td>\n",
+            "<td>10</td>\n",
+            "<td>red</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn extended_table_headerless() {
+    html_opts!(
+        [extension.table, extension.extended_tables],
+        concat!("|---|--:|\n", "| a | b |\n", "| ^^ | c |\n"),
+        concat!(
+            "<table>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td rowspan=\"2\">a</td>\n",
+            "<td align=\"right\">b</td>\n",
+            "</tr>\n",
+            "<tr>\n",
+            "<td align=\"right\">c</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn extended_table_headerless_delimiter_row_only() {
+    html_opts!(
+        [extension.table, extension.extended_tables],
+        concat!(
+            "Text\n",
+            "\n",
+            "|---|---|\n",
+            "\n",
+            "More\n",
+            "\n",
+            "- |-|\n"
+        ),
+        concat!(
+            "<p>Text</p>\n",
+            "<p>|---|---|</p>\n",
+            "<p>More</p>\n",
+            "<ul>\n",
+            "<li>|-|</li>\n",
+            "</ul>\n",
+        ),
+    );
+
+    assert_ast_match!(
+        [extension.table, extension.extended_tables],
+        "  |---|\n",
+        (document (1:1-1:7) [
+            (paragraph (1:3-1:7) [
+                (text (1:3-1:7) "|---|")
+            ])
+        ])
+    );
+}
+
+#[test]
+fn extended_table_multiline_cells() {
+    html_opts!(
+        [extension.table, extension.extended_tables],
+        concat!(
+            "| a | b |\n",
+            "|---|---|\n",
+            "| c | d | \\\n",
+            "|   | e |\\\n",
+            "| f |   |\n",
+            "| g | h |\n",
+        ),
+        concat!(
+            "<table>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>a</th>\n",
+            "<th>b</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td>c\n",
+            "f</td>\n",
+            "<td>d\n",
+            "e</td>\n",
+            "</tr>\n",
+            "<tr>\n",
+            "<td>g</td>\n",
+            "<td>h</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+        ),
+        no_roundtrip,
+    );
+}
+
+#[test]
+fn extended_table_multiline_cells_sourcepos() {
+    assert_ast_match!(
+        [extension.table, extension.extended_tables],
+        "| a |\n"
+        "|---|\n"
+        "| b |\\\n"
+        "| c |\n"
+        ,
+        (document (1:1-4:5) [
+            (table (1:1-4:5) [
+                (table_row (1:1-1:5) [
+                    (table_cell (1:2-1:4) [
+                        (text (1:3-1:3) "a")
+                    ])
+                ])
+                (table_row (3:1-4:5) [
+                    (table_cell (3:2-4:4) [
+                        (text (3:3-3:3) "b")
+                        (softbreak (3:4-3:4))
+                        (text (4:3-4:3) "c")
+                    ])
+                ])
+            ])
+        ])
+    );
+}
+
+#[test]
+fn extended_table_commonmark() {
+    let mut options = Options::default();
+    options.extension.table = true;
+    options.extension.extended_tables = true;
+
+    commonmark(
+        concat!(
+            "|:-|-|\n",
+            "| a || \n",
+            "| b | c |\\\n",
+            "|   | d |\n",
+            "| ^^ | e |\n",
+        ),
+        concat!(
+            "| :-- | --- |\n",
+            "| a ||\n",
+            "| b | c d |\n",
+            "| ^^ | e |\n",
+        ),
+        Some(&options),
+    );
+}
+
+#[test]
+fn grid_table() {
+    html_opts!(
+        [extension.grid_tables],
+        concat!(
+            "+---+-----+\n",
+            "| a | b   |\n",
+            "+===+=====+\n",
+            "| c | - d |\n",
+            "|   | - e |\n",
+            "+---+-----+\n",
+        ),
+        concat!(
+            "<table>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>a</th>\n",
+            "<th>b</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td>c</td>\n",
+            "<td>\n",
+            "<ul>\n",
+            "<li>d</li>\n",
+            "<li>e</li>\n",
+            "</ul>\n",
+            "</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn grid_table_definitions() {
+    html_opts!(
+        [extension.grid_tables, extension.footnotes],
+        concat!(
+            "See [home] and[^a].\n",
+            "\n",
+            "+---------------+---------+\n",
+            "| Cell[^a]      | [home]  |\n",
+            "|               |         |\n",
+            "| [^a]: A note. |         |\n",
+            "|               |         |\n",
+            "| [home]: /     |         |\n",
+            "+---------------+---------+\n",
+        ),
+        concat!(
+            "<p>See <a href=\"/\">home</a> and<sup class=\"footnote-ref\"><a href=\"#fn-a\" id=\"fnref-a\" data-footnote-ref>1</a></sup>.</p>\n",
+            "<table>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td>Cell<sup class=\"footnote-ref\"><a href=\"#fn-a\" id=\"fnref-a-2\" data-footnote-ref>1</a></sup></td>\n",
+            "<td><a href=\"/\">home</a></td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+            "<section class=\"footnotes\" data-footnotes>\n",
+            "<ol>\n",
+            "<li id=\"fn-a\">\n",
+            "<p>A note. <a href=\"#fnref-a\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1\" aria-label=\"Back to reference 1\">↩</a> <a href=\"#fnref-a-2\" class=\"footnote-backref\" data-footnote-backref data-footnote-backref-idx=\"1-2\" aria-label=\"Back to reference 1-2\">↩<sup class=\"footnote-ref\">2</sup></a></p>\n",
+            "</li>\n",
+            "</ol>\n",
+            "</section>\n",
+        ),
+    );
+}
+
+#[test]
+fn grid_table_blocks() {
+    html_opts!(
+        [extension.grid_tables],
+        concat!(
+            "+:-----+-----------:+\n",
+            "| one  | para one   |\n",
+            "|      |            |\n",
+            "| *two*| para [two] |\n",
+            "+------+------------+\n",
+            "| ```  |     code   |\n",
+            "| x    |            |\n",
+            "| ```  | [two]: /u  |\n",
+            "+------+------------+\n",
+            "\n",
+            "[two]\n",
+        ),
+        concat!(
+            "<table>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td align=\"left\">\n",
+            "<p>one</p>\n",
+            "<p><em>two</em></p>\n",
+            "</td>\n",
+            "<td align=\"right\">\n",
+            "<p>para one</p>\n",
+            "<p>para <a href=\"/u\">two</a></p>\n",
+            "</td>\n",
+            "</tr>\n",
+            "<tr>\n",
+            "<td align=\"left\">\n",
+            "<pre><code>x\n",
+            "</code></pre>\n",
+            "</td>\n",
+            "<td align=\"right\">\n",
+            "<pre><code>code\n",
+            "</code></pre>\n",
+            "</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+            "<p><a href=\"/u\">two</a></p>\n",
+        ),
+    );
+}
+
+#[test]
+fn grid_table_nested() {
+    html_opts!(
+        [extension.grid_tables],
+        concat!(
+            "+-----------+\n",
+            "| +---+---+ |\n",
+            "| | a | b | |\n",
+            "| +---+---+ |\n",
+            "+-----------+\n",
+        ),
+        concat!(
+            "<table>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td>\n",
+            "<table>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td>a</td>\n",
+            "<td>b</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+            "</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn grid_table_malformed() {
+    html_opts!(
+        [extension.grid_tables],
+        concat!(
+            "+---+---+\n",
+            "| a | b\n",
+            "+---+---+\n",
+            "\n",
+            "text\n",
+            "+---+\n",
+            "| c |\n",
+            "+---+\n",
+        ),
+        concat!(
+            "<p>+---+---+\n",
+            "| a | b\n",
+            "+---+---+</p>\n",
+            "<p>text\n",
+            "+---+\n",
+            "| c |\n",
+            "+---+</p>\n",
+        ),
+    );
+}
+
+#[test]
+fn grid_table_disabled() {
+    html_opts!(
+        [extension.table],
+        concat!("+---+\n", "| a |\n", "+---+\n"),
+        concat!("<p>+---+\n", "| a |\n", "+---+</p>\n"),
+    );
+}
+
+#[test]
+fn grid_table_sourcepos() {
+    assert_ast_match!(
+        [extension.grid_tables],
+        "> +----+------+\n"
+        "> | a  | - b  |\n"
+        "> |    |      |\n"
+        "> |    | c    |\n"
+        "> +----+------+\n"
+        ,
+        (document (1:1-5:15) [
+            (block_quote (1:1-5:15) [
+                (table (1:3-5:15) [
+                    (table_row (2:3-4:15) [
+                        (table_cell (2:4-4:7) [
+                            (paragraph (2:5-2:5) [
+                                (text (2:5-2:5) "a")
+                            ])
+                        ])
+                        (table_cell (2:9-4:14) [
+                            (list (2:10-2:12) [
+                                (item (2:10-2:12) [
+                                    (paragraph (2:12-2:12) [
+                                        (text (2:12-2:12) "b")
+                                    ])
+                                ])
+                            ])
+                            (paragraph (4:10-4:10) [
+                                (text (4:10-4:10) "c")
+                            ])
+                        ])
+                    ])
+                ])
+            ])
+        ])
+    );
+}
+
+#[test]
+fn grid_table_commonmark() {
+    let mut options = Options::default();
+    options.extension.grid_tables = true;
+
+    commonmark(
+        concat!(
+            "+------+---+\n",
+            "| a    | b |\n",
+            "+======+==:+\n",
+            "| 1. x | y |\n",
+            "|      |   |\n",
+            "|  z   |   |\n",
+            "+------+---+\n",
+        ),
+        concat!(
+            "+------+---+\n",
+            "| a    | b |\n",
+            "+======+==:+\n",
+            "| 1. x | y |\n",
+            "|      |   |\n",
+            "| z    |   |\n",
+            "+------+---+\n",
+        ),
+        Some(&options),
+    );
+}
+
+#[test]
+fn table_caption() {
+    html_opts!(
+        [extension.table, extension.table_captions],
+        concat!(
+            "Table: Before *caption*\n",
+            "\n",
+            "| a |\n",
+            "|---|\n",
+            "\n",
+            "| b |\n",
+            "|---|\n",
+            "\n",
+            ": After  caption\n",
+            "  {#tbl-b}\n",
+        ),
+        concat!(
+            "<table>\n",
+            "<caption>Before <em>caption</em></caption>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>a</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "</table>\n",
+            "<table id=\"tbl-b\">\n",
+            "<caption>After  caption</caption>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>b</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_caption_inlines() {
+    html_opts!(
+        [extension.table, extension.table_captions, render.sourcepos],
+        concat!(
+            "| a |\n",
+            "|---|\n",
+            "\n",
+            "Table: *Results* for `x` {#tbl}\n",
+        ),
+        concat!(
+            "<table data-sourcepos=\"1:1-4:31\" id=\"tbl\">\n",
+            "<caption data-sourcepos=\"4:1-4:31\">",
+            "<em data-sourcepos=\"4:8-4:16\">Results</em> for ",
+            "<code data-sourcepos=\"4:22-4:24\">x</code>",
+            "</caption>\n",
+            "<thead>\n",
+            "<tr data-sourcepos=\"1:1-1:5\">\n",
+            "<th data-sourcepos=\"1:2-1:4\">a</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_caption_sourcepos() {
+    assert_ast_match!(
+        [extension.table, extension.table_captions],
+        "Table: Before\n"
+        "| a |\n"
+        "|---|\n"
+        "\n"
+        "| b |\n"
+        "|---|\n"
+        "\n"
+        "Table: \n",
+        (document (1:1-8:7) [
+            (table (1:1-3:5) [
+                (table_caption (1:1-1:13) [
+                    (text (1:8-1:13) "Before")
+                ])
+                (table_row (2:1-2:5) [
+                    (table_cell (2:2-2:4) [
+                        (text (2:3-2:3) "a")
+                    ])
+                ])
+            ])
+            (table (5:1-6:5) [
+                (table_row (5:1-5:5) [
+                    (table_cell (5:2-5:4) [
+                        (text (5:3-5:3) "b")
+                    ])
+                ])
+            ])
+            (paragraph (8:1-8:7) [
+                (text (8:1-8:6) "Table:")
+            ])
+        ])
+    );
+}
+
+#[test]
+fn table_caption_multimarkdown() {
+    html_opts!(
+        [extension.table, extension.table_captions],
+        concat!(
+            "[Prices][tbl-prices]\n",
+            "| a |\n",
+            "|---|\n",
+            "\n",
+            "| b |\n",
+            "|---|\n",
+            "\n",
+            "[Not a caption]\n",
+        ),
+        concat!(
+            "<table id=\"tbl-prices\">\n",
+            "<caption>Prices</caption>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>a</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "</table>\n",
+            "<table>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>b</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "</table>\n",
+            "<p>[Not a caption]</p>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_caption_disabled() {
+    html_opts!(
+        [extension.table],
+        concat!("| a |\n", "|---|\n", "\n", "Table: Caption\n"),
+        concat!(
+            "<table>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th>a</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "</table>\n",
+            "<p>Table: Caption</p>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_caption_grid_table() {
+    html_opts!(
+        [extension.grid_tables, extension.table_captions],
+        concat!("+---+\n", "| a |\n", "+---+\n", "[Grid]\n"),
+        concat!(
+            "<table>\n",
+            "<caption>Grid</caption>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td>a</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_column_widths() {
+    html_opts!(
+        [extension.table, render.table_column_widths],
+        concat!("| a | b | c |\n", "|:-|:--:|-----|\n"),
+        concat!(
+            "<table>\n",
+            "<colgroup>\n",
+            "<col style=\"width: 13%\" />\n",
+            "<col style=\"width: 25%\" />\n",
+            "<col style=\"width: 63%\" />\n",
+            "</colgroup>\n",
+            "<thead>\n",
+            "<tr>\n",
+            "<th align=\"left\">a</th>\n",
+            "<th align=\"center\">b</th>\n",
+            "<th>c</th>\n",
+            "</tr>\n",
+            "</thead>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_column_widths_grid_table() {
+    html_opts!(
+        [extension.grid_tables, render.table_column_widths],
+        concat!(
+            "+---+---------+\n",
+            "| a | b       |\n",
+            "+---+---------+\n"
+        ),
+        concat!(
+            "<table>\n",
+            "<colgroup>\n",
+            "<col style=\"width: 25%\" />\n",
+            "<col style=\"width: 75%\" />\n",
+            "</colgroup>\n",
+            "<tbody>\n",
+            "<tr>\n",
+            "<td>a</td>\n",
+            "<td>b</td>\n",
+            "</tr>\n",
+            "</tbody>\n",
+            "</table>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_caption_commonmark() {
+    let mut options = Options::default();
+    options.extension.table = true;
+    options.extension.table_captions = true;
+    options.render.table_column_widths = true;
+
+    commonmark(
+        concat!("[Caption][tbl]\n", "| a | b |\n", "|:-----|--:|\n"),
+        concat!(
+            "| a | b |\n",
+            "| :----- | --: |\n",
+            "\n",
+            "Table: Caption {#tbl}\n",
+        ),
+        Some(&options),
+    );
+}
+
+#[test]
+fn table_caption_xml() {
+    xml_opts(
+        concat!("| a |\n", "|---|\n", "\n", "Table: Caption {#tbl}\n"),
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<!DOCTYPE document SYSTEM \"CommonMark.dtd\">\n",
+            "<document xmlns=\"http://commonmark.org/xml/1.0\">\n",
+            "  <table id=\"tbl\">\n",
+            "    <table_caption>\n",
+            "      <text xml:space=\"preserve\">Caption</text>\n",
+            "    </table_caption>\n",
+            "    <table_row>\n",
+            "      <table_cell>\n",
+            "        <text xml:space=\"preserve\">a</text>\n",
+            "      </table_cell>\n",
+            "    </table_row>\n",
+            "  </table>\n",
+            "</document>\n",
+        ),
+        |opts| {
+            opts.extension.table = true;
+            opts.extension.table_captions = true;
+        },
+    );
+}
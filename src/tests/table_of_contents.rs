@@ -0,0 +1,212 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+#[test]
+fn table_of_contents_api() {
+    let arena = Arena::new();
+    let options = Options::default();
+    let root = parse_document(
+        &arena,
+        concat!(
+            "# *Hello*, `world`\n",
+            "\n",
+            "para\n",
+            "\n",
+            "Setext\n",
+            "------\n",
+            "\n",
+            "> ### Quoted\n",
+            "\n",
+            "# Hello, world\n",
+        ),
+        &options,
+    );
+
+    let entries = html::toc(root, &options);
+    assert_eq!(
+        entries,
+        [
+            html::TocEntry {
+                level: 1,
+                text: "Hello, world".into(),
+                anchor: "hello-world".into(),
+                number: None,
+                sourcepos: (1, 1, 1, 18).into(),
+            },
+            html::TocEntry {
+                level: 2,
+                text: "Setext".into(),
+                anchor: "setext".into(),
+                number: None,
+                sourcepos: (5, 1, 6, 6).into(),
+            },
+            html::TocEntry {
+                level: 3,
+                text: "Quoted".into(),
+                anchor: "quoted".into(),
+                number: None,
+                sourcepos: (8, 3, 8, 12).into(),
+            },
+            html::TocEntry {
+                level: 1,
+                text: "Hello, world".into(),
+                anchor: "hello-world-1".into(),
+                number: None,
+                sourcepos: (10, 1, 10, 14).into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn table_of_contents_placeholder() {
+    html_opts!(
+        [
+            extension.table_of_contents = true,
+            extension.header_id_prefix = Some("".to_owned())
+        ],
+        concat!(
+            "[[_TOC_]]\n",
+            "\n",
+            "# One\n",
+            "\n",
+            "## One A\n",
+            "\n",
+            "## One B\n",
+            "\n",
+            "# Two\n",
+        ),
+        concat!(
+            "<ul class=\"table-of-contents\">\n",
+            "<li><a href=\"#one\">One</a>\n",
+            "<ul>\n",
+            "<li><a href=\"#one-a\">One A</a></li>\n",
+            "<li><a href=\"#one-b\">One B</a></li>\n",
+            "</ul>\n",
+            "</li>\n",
+            "<li><a href=\"#two\">Two</a></li>\n",
+            "</ul>\n",
+            "<h1><a href=\"#one\" aria-hidden=\"true\" class=\"anchor\" id=\"one\"></a>One</h1>\n",
+            "<h2><a href=\"#one-a\" aria-hidden=\"true\" class=\"anchor\" id=\"one-a\"></a>One A</h2>\n",
+            "<h2><a href=\"#one-b\" aria-hidden=\"true\" class=\"anchor\" id=\"one-b\"></a>One B</h2>\n",
+            "<h1><a href=\"#two\" aria-hidden=\"true\" class=\"anchor\" id=\"two\"></a>Two</h1>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_of_contents_skipped_levels() {
+    html_opts!(
+        [extension.table_of_contents],
+        concat!(
+            "## Start\n",
+            "\n",
+            "#### Deep\n",
+            "\n",
+            "### Middle\n",
+            "\n",
+            "# Top\n",
+            "\n",
+            "[TOC]\n",
+        ),
+        concat!(
+            "<h2><a href=\"#start\" aria-hidden=\"true\" class=\"anchor\" id=\"start\"></a>Start</h2>\n",
+            "<h4><a href=\"#deep\" aria-hidden=\"true\" class=\"anchor\" id=\"deep\"></a>Deep</h4>\n",
+            "<h3><a href=\"#middle\" aria-hidden=\"true\" class=\"anchor\" id=\"middle\"></a>Middle</h3>\n",
+            "<h1><a href=\"#top\" aria-hidden=\"true\" class=\"anchor\" id=\"top\"></a>Top</h1>\n",
+            "<ul class=\"table-of-contents\">\n",
+            "<li><a href=\"#start\">Start</a>\n",
+            "<ul>\n",
+            "<li><a href=\"#deep\">Deep</a></li>\n",
+            "<li><a href=\"#middle\">Middle</a></li>\n",
+            "</ul>\n",
+            "</li>\n",
+            "<li><a href=\"#top\">Top</a></li>\n",
+            "</ul>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_of_contents_prefix_in_href() {
+    html_opts!(
+        [
+            extension.table_of_contents = true,
+            extension.header_id_prefix = Some("user-content-".to_owned()),
+            extension.header_id_prefix_in_href = true
+        ],
+        "[TOC]\n\n# A & B\n",
+        concat!(
+            "<ul class=\"table-of-contents\">\n",
+            "<li><a href=\"#user-content-a--b\">A &amp; B</a></li>\n",
+            "</ul>\n",
+            "<h1><a href=\"#user-content-a--b\" aria-hidden=\"true\" class=\"anchor\" id=\"user-content-a--b\"></a>A &amp; B</h1>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_of_contents_not_placeholder() {
+    html_opts!(
+        [extension.table_of_contents],
+        "[TOC] here\n\n`[TOC]`\n",
+        "<p>[TOC] here</p>\n<p><code>[TOC]</code></p>\n",
+    );
+    html_opts!([extension.table_of_contents], "[TOC]\n", "",);
+    html("[TOC]\n\n# Hi\n", "<p>[TOC]</p>\n<h1>Hi</h1>\n");
+}
+
+#[test]
+fn table_of_contents_reference_definition() {
+    html_opts!(
+        [extension.table_of_contents],
+        "[TOC]\n\n[TOC]: /toc\n\n# Hi\n",
+        concat!(
+            "<ul class=\"table-of-contents\">\n",
+            "<li><a href=\"#hi\">Hi</a></li>\n",
+            "</ul>\n",
+            "<h1><a href=\"#hi\" aria-hidden=\"true\" class=\"anchor\" id=\"hi\"></a>Hi</h1>\n",
+        ),
+    );
+}
+
+#[test]
+fn table_of_contents_heading_ids_only_with_placeholder() {
+    html_opts!(
+        [extension.table_of_contents],
+        "# Start\n\n## Next\n",
+        "<h1>Start</h1>\n<h2>Next</h2>\n",
+    );
+}
+
+#[test]
+fn table_of_contents_xml() {
+    xml_opts(
+        "[[_TOC_]]\n",
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<!DOCTYPE document SYSTEM \"CommonMark.dtd\">\n",
+            "<document xmlns=\"http://commonmark.org/xml/1.0\">\n",
+            "  <table_of_contents />\n",
+            "</document>\n",
+        ),
+        |opts| opts.extension.table_of_contents = true,
+    );
+}
+
+#[test]
+fn sourcepos() {
+    assert_ast_match!(
+        [extension.table_of_contents],
+        "para\n"
+        "\n"
+        "  [TOC]\n",
+        (document (1:1-3:7) [
+            (paragraph (1:1-1:4) [
+                (text (1:1-1:4) "para")
+            ])
+            (table_of_contents (3:3-3:7))
+        ])
+    );
+}
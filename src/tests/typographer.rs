@@ -0,0 +1,96 @@
+use super::*;
+
+#[test]
+fn typographer_symbols() {
+    html_opts!(
+        [parse.typographer],
+        "(c) (C) (r) (tm) (TM) +-5 a -> b <- c <-> d => e\n",
+        "<p>© © ® ™ ™ ±5 a → b ← c ↔ d ⇒ e</p>\n",
+    );
+}
+
+#[test]
+fn typographer_fractions_and_ordinals() {
+    html_opts!(
+        [parse.typographer],
+        "1/2 1/4 3/4 2/3 1/2/3 v1.1/2 11/2\n\n1st 2nd 3rd 4th 11th 12th 21st 22nd 2st 1stly\n",
+        concat!(
+            "<p>½ ¼ ¾ ⅔ 1/2/3 v1.1/2 11/2</p>\n",
+            "<p>1<sup>st</sup> 2<sup>nd</sup> 3<sup>rd</sup> 4<sup>th</sup> 11<sup>th</sup> ",
+            "12<sup>th</sup> 21<sup>st</sup> 22<sup>nd</sup> 2st 1stly</p>\n"
+        ),
+    );
+}
+
+#[test]
+fn typographer_arrow_context() {
+    html_opts!(
+        [parse.typographer],
+        "a --> b <-- c ==> d\n",
+        "<p>a --&gt; b &lt;-- c ==&gt; d</p>\n",
+    );
+}
+
+#[test]
+fn typographer_skips_code_links_and_html() {
+    html_opts!(
+        [parse.typographer, render.r#unsafe, extension.math_dollars],
+        concat!(
+            "`(c) ->` $a -> b$ <span title=\"(c)\">(c)</span> \\(c) [1/2](/a->b \"(c)\")\n",
+            "\n",
+            "```\n",
+            "(c) -> 1/2\n",
+            "```\n"
+        ),
+        concat!(
+            "<p><code>(c) -&gt;</code> <span data-math-style=\"inline\">a -&gt; b</span> ",
+            "<span title=\"(c)\">©</span> (c) <a href=\"/a-%3Eb\" title=\"(c)\">½</a></p>\n",
+            "<pre><code>(c) -&gt; 1/2\n",
+            "</code></pre>\n"
+        ),
+    );
+}
+
+#[test]
+fn typographer_off_by_default() {
+    html("(c) -> 1/2 1st\n", "<p>(c) -&gt; 1/2 1st</p>\n");
+}
+
+#[test]
+fn typographer_commonmark_roundtrip() {
+    let input = "(c) Acme -> 1/2 of the 2nd (tm)\n";
+
+    let mut options = Options::default();
+    options.parse.typographer = true;
+    commonmark(input, "© Acme → ½ of the 2nd ™\n", Some(&options));
+
+    options.render.typographer_source = true;
+    commonmark(input, input, Some(&options));
+}
+
+#[test]
+fn typographer_sourcepos() {
+    assert_ast_match!(
+        [parse.typographer],
+        "a (c) 1st\n",
+        (document (1:1-1:9) [
+            (paragraph (1:1-1:9) [
+                (text (1:1-1:2) "a ")
+                (typographic (1:3-1:5))
+                (text (1:6-1:7) " 1")
+                (typographic (1:8-1:9))
+            ])
+        ])
+    );
+}
+
+#[test]
+fn typographer_commonmark_escapes() {
+    let mut options = Options::default();
+    options.parse.typographer = true;
+    commonmark(
+        "\\(c) 1\\/2 \\-> \\<- (c)\n",
+        "\\(c) 1\\/2 -\\> \\<- ©\n",
+        Some(&options),
+    );
+}
@@ -1,4 +1,19 @@
+use std::sync::Arc;
+
+use pretty_assertions::assert_eq;
+
 use super::*;
+use crate::backlinks::Backlinks;
+use crate::options::{ResolvedWikiLink, WikiLinkResolver};
+
+fn wiki() -> Arc<dyn WikiLinkResolver> {
+    Arc::new(|page: &str| {
+        (page != "Elsewhere").then(|| ResolvedWikiLink {
+            url: format!("/wiki/{}.html", page.to_lowercase().replace(' ', "-")),
+            exists: page != "Missing Page",
+        })
+    })
+}
 
 #[test]
 fn wikilinks_does_not_unescape_html_entities_in_link_label() {
@@ -248,3 +263,211 @@ fn sourcepos() {
         ])
     );
 }
+
+#[test]
+fn wikilinks_resolver() {
+    html_opts!(
+        [
+            extension.wikilinks_title_after_pipe = true,
+            parse.wikilink_resolver = Some(wiki())
+        ],
+        concat!(
+            "[[My Page]], [[My Page#Getting Started|started]], [[My Page#^intro-para]], ",
+            "[[#Local Heading]], [[Missing Page]] and [[Elsewhere#Top]]\n"
+        ),
+        concat!(
+            "<p><a href=\"/wiki/my-page.html\" data-wikilink=\"true\">My Page</a>, ",
+            "<a href=\"/wiki/my-page.html#getting-started\" data-wikilink=\"true\">started</a>, ",
+            "<a href=\"/wiki/my-page.html#intro-para\" data-wikilink=\"true\">My Page#^intro-para</a>, ",
+            "<a href=\"#local-heading\" data-wikilink=\"true\">#Local Heading</a>, ",
+            "<a href=\"/wiki/missing-page.html\" data-wikilink=\"true\" class=\"new\">Missing Page</a> and ",
+            "<a href=\"Elsewhere#Top\" data-wikilink=\"true\">Elsewhere#Top</a></p>\n"
+        ),
+    );
+
+    html_opts!(
+        [
+            extension.wikilinks_title_before_pipe = true,
+            extension.header_id_prefix = Some("user-content-".to_string()),
+            extension.header_id_prefix_in_href = true,
+            parse.wikilink_resolver = Some(wiki())
+        ],
+        "[[Setup|Guide#Install]]\n",
+        "<p><a href=\"/wiki/guide.html#user-content-install\" data-wikilink=\"true\">Setup</a></p>\n",
+    );
+}
+
+#[test]
+fn wikilinks_resolver_anchorize() {
+    struct Underscores;
+
+    impl WikiLinkResolver for Underscores {
+        fn resolve(&self, page: &str) -> Option<ResolvedWikiLink> {
+            Some(ResolvedWikiLink {
+                url: format!("/{}", page),
+                exists: true,
+            })
+        }
+
+        fn anchorize(&self, heading: &str) -> String {
+            heading.to_lowercase().replace(' ', "_")
+        }
+    }
+
+    html_opts!(
+        [
+            extension.wikilinks_title_after_pipe = true,
+            parse.wikilink_resolver = Some(Arc::new(Underscores))
+        ],
+        "[[Guide#Getting Started]]\n",
+        "<p><a href=\"/Guide#getting_started\" data-wikilink=\"true\">Guide#Getting Started</a></p>\n",
+    );
+}
+
+#[test]
+fn wikilinks_resolver_xml() {
+    xml_opts(
+        "[[Missing Page]]\n",
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<!DOCTYPE document SYSTEM \"CommonMark.dtd\">\n",
+            "<document xmlns=\"http://commonmark.org/xml/1.0\">\n",
+            "  <paragraph>\n",
+            "    <wikilink destination=\"/wiki/missing-page.html\" missing=\"true\">\n",
+            "      <text xml:space=\"preserve\">Missing Page</text>\n",
+            "    </wikilink>\n",
+            "  </paragraph>\n",
+            "</document>\n",
+        ),
+        |opts| {
+            opts.extension.wikilinks_title_after_pipe = true;
+            opts.parse.wikilink_resolver = Some(wiki());
+        },
+    );
+}
+
+#[test]
+fn wikilinks_backlinks() {
+    let mut options = Options::default();
+    options.extension.wikilinks_title_after_pipe = true;
+
+    let arena = Arena::new();
+    let mut backlinks = Backlinks::new();
+    backlinks.add(
+        "Home",
+        parse_document(
+            &arena,
+            "Read [[Guide]].\n\nThen [[Guide#^step-2|step 2]].\n",
+            &options,
+        ),
+    );
+    backlinks.add("About", parse_document(&arena, "[[Home]]\n", &options));
+
+    let guide = backlinks.get("Guide");
+    assert_eq!(guide.len(), 2);
+    assert_eq!(guide[0].from, "Home");
+    assert_eq!(guide[0].sourcepos, sourcepos!((1:6-1:14)));
+    assert_eq!(guide[1].block.as_deref(), Some("step-2"));
+    assert_eq!(guide[1].text, "step 2");
+    assert_eq!(guide[1].sourcepos, sourcepos!((3:6-3:29)));
+    assert_eq!(backlinks.get("Home")[0].from, "About");
+    assert!(backlinks.get("About").is_empty());
+}
+
+#[test]
+fn wikilinks_image_embeds() {
+    html_opts!(
+        [extension.wikilinks_title_after_pipe = true],
+        concat!(
+            "![[cat.png|300]]\n",
+            "\n",
+            "![[images/dog.jpg|300x200]]\n",
+            "\n",
+            "![[cat.png|A cat]]\n",
+        ),
+        concat!(
+            "<p><img src=\"cat.png\" alt=\"cat.png\" width=\"300\" data-wikilink=\"true\" /></p>\n",
+            "<p><img src=\"images/dog.jpg\" alt=\"images/dog.jpg\" width=\"300\" height=\"200\" data-wikilink=\"true\" /></p>\n",
+            "<p><img src=\"cat.png\" alt=\"A cat\" data-wikilink=\"true\" /></p>\n",
+        ),
+    );
+}
+
+#[test]
+fn wikilinks_note_embeds() {
+    html_opts!(
+        [extension.wikilinks_title_after_pipe = true],
+        "![[Note#Section]] and ![not a link]\n",
+        "<p><a href=\"Note#section\" data-wikilink=\"true\" data-embed=\"true\">Note#Section</a> and ![not a link]</p>\n",
+    );
+}
+
+#[test]
+fn wikilinks_block_anchors() {
+    html_opts!(
+        [
+            extension.wikilinks_title_after_pipe = true,
+            extension.block_anchors = true
+        ],
+        concat!(
+            "A paragraph. ^intro\n",
+            "\n",
+            "- An item ^first\n",
+            "- Another\n",
+            "\n",
+            "Not an anchor ^no_good\n",
+        ),
+        concat!(
+            "<p id=\"intro\">A paragraph.</p>\n",
+            "<ul>\n",
+            "<li id=\"first\">An item</li>\n",
+            "<li>Another</li>\n",
+            "</ul>\n",
+            "<p>Not an anchor ^no_good</p>\n",
+        ),
+    );
+    html_opts!(
+        [
+            extension.wikilinks_title_after_pipe = true,
+            extension.block_anchors = true
+        ],
+        concat!(
+            "A paragraph. ^intro\n",
+            "\n",
+            "See [[#^intro|the intro]] and [[#Local Heading]].\n",
+        ),
+        concat!(
+            "<p id=\"intro\">A paragraph.</p>\n",
+            "<p>See <a href=\"#intro\" data-wikilink=\"true\">the intro</a> and ",
+            "<a href=\"#local-heading\" data-wikilink=\"true\">#Local Heading</a>.</p>\n",
+        ),
+    );
+    html_opts!(
+        [extension.wikilinks_title_after_pipe = true],
+        "The limit is 2 ^10\n",
+        "<p>The limit is 2 ^10</p>\n",
+    );
+}
+
+#[test]
+fn wikilinks_embeds_xml() {
+    xml_opts(
+        "![[cat.png|300x200]] ^pic\n",
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<!DOCTYPE document SYSTEM \"CommonMark.dtd\">\n",
+            "<document xmlns=\"http://commonmark.org/xml/1.0\">\n",
+            "  <paragraph>\n",
+            "    <wikilink destination=\"cat.png\" embed=\"true\" width=\"300\" height=\"200\">\n",
+            "      <text xml:space=\"preserve\">cat.png</text>\n",
+            "    </wikilink>\n",
+            "    <block_anchor id=\"pic\" />\n",
+            "  </paragraph>\n",
+            "</document>\n",
+        ),
+        |opts| {
+            opts.extension.wikilinks_title_after_pipe = true;
+            opts.extension.block_anchors = true;
+        },
+    );
+}
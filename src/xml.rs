@@ -106,6 +106,9 @@ impl<'o, 'c> XmlFormatter<'o, 'c> {
                             NodeValue::Math(NodeMath { ref literal, .. }) => {
                                 self.escape(literal)?;
                             }
+                            NodeValue::Typographic(ref nt) => {
+                                self.escape(nt.text())?;
+                            }
                             _ => (),
                         }
                         plain
@@ -249,24 +252,33 @@ impl<'o, 'c> XmlFormatter<'o, 'c> {
                     self.escape(&nl.title)?;
                     self.output.write_str("\"")?;
                 }
-                NodeValue::Table(..) => {
-                    // noop
+                NodeValue::Table(ref nt) => {
+                    if let Some(ref id) = nt.id {
+                        self.output.write_str(" id=\"")?;
+                        self.escape(id)?;
+                        self.output.write_str("\"")?;
+                    }
                 }
-                NodeValue::TableRow(..) => {
+                NodeValue::TableRow(..) | NodeValue::TableCaption => {
                     // noop
                 }
-                NodeValue::TableCell => {
+                NodeValue::TableCell(ref ntc) => {
                     let mut ancestors = node.ancestors().skip(1);
 
                     let header_row = &ancestors.next().unwrap().data().value;
                     let table = &ancestors.next().unwrap().data().value;
 
                     if let (NodeValue::TableRow(true), NodeValue::Table(nt)) = (header_row, table) {
-                        let ix = node.preceding_siblings().count() - 1;
-                        if let Some(xml_align) = nt.alignments[ix].xml_name() {
+                        if let Some(xml_align) = nt.alignments[ntc.column].xml_name() {
                             write!(self.output, " align=\"{}\"", xml_align)?;
                         }
                     }
+                    if ntc.colspan > 1 {
+                        write!(self.output, " colspan=\"{}\"", ntc.colspan)?;
+                    }
+                    if ntc.rowspan > 1 {
+                        write!(self.output, " rowspan=\"{}\"", ntc.rowspan)?;
+                    }
                 }
                 NodeValue::FootnoteDefinition(ref fd) => {
                     self.output.write_str(" label=\"")?;
@@ -309,6 +321,18 @@ impl<'o, 'c> XmlFormatter<'o, 'c> {
                     self.output.write_str(" destination=\"")?;
                     self.escape(&nl.url)?;
                     self.output.write_str("\"")?;
+                    if nl.missing {
+                        self.output.write_str(" missing=\"true\"")?;
+                    }
+                    if nl.embed {
+                        self.output.write_str(" embed=\"true\"")?;
+                    }
+                    if let Some(width) = nl.width {
+                        write!(self.output, " width=\"{}\"", width)?;
+                    }
+                    if let Some(height) = nl.height {
+                        write!(self.output, " height=\"{}\"", height)?;
+                    }
                 }
                 NodeValue::Underline => {}
                 NodeValue::Subscript => {}
@@ -337,6 +361,37 @@ impl<'o, 'c> XmlFormatter<'o, 'c> {
                     self.escape(&nbd.info)?;
                     self.output.write_str("\"")?;
                 }
+                NodeValue::TableOfContents => {}
+                NodeValue::Equation(ref ne) => {
+                    self.output.write_str(" label=\"")?;
+                    self.escape(&ne.label)?;
+                    write!(self.output, "\" number=\"{}\"", ne.number)?;
+                }
+                NodeValue::BlockAnchor(ref id) => {
+                    self.output.write_str(" id=\"")?;
+                    self.escape(id)?;
+                    self.output.write_str("\"")?;
+                }
+                NodeValue::Include(ref ni) => {
+                    self.output.write_str(" target=\"")?;
+                    self.escape(&ni.target)?;
+                    self.output.write_str("\" path=\"")?;
+                    self.escape(&ni.path)?;
+                    write!(self.output, "\" embed=\"{}\"", ni.embed)?;
+                }
+                NodeValue::Figure(ref nf) => {
+                    self.output.write_str(" label=\"")?;
+                    self.escape(&nf.label)?;
+                    write!(self.output, "\" number=\"{}\"", nf.number)?;
+                }
+                NodeValue::Typographic(ref nt) => {
+                    self.output.write_str(" source=\"")?;
+                    self.escape(nt.source)?;
+                    self.output.write_str("\" xml:space=\"preserve\">")?;
+                    self.escape(nt.text())?;
+                    write!(self.output, "</{}", ast.value.xml_node_name())?;
+                    was_literal = true;
+                }
             }
 
             if node.first_child().is_some() {